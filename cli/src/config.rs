@@ -1,5 +1,5 @@
 use crate::is_hidden;
-use anchor_client::Cluster;
+use anchor_client::{read_wallet_keypair, Cluster, DynSigner, RemoteSigner};
 use anchor_syn::idl::types::Idl;
 use anyhow::{anyhow, bail, Context, Error, Result};
 use clap::{Parser, ValueEnum};
@@ -17,10 +17,12 @@ use std::ffi::OsStr;
 use std::fs::{self, File};
 use std::io::prelude::*;
 use std::marker::PhantomData;
+use std::net::IpAddr;
 use std::ops::Deref;
 use std::path::Path;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::{fmt, io};
 use walkdir::WalkDir;
 
@@ -499,12 +501,64 @@ impl Config {
             .parse::<Self>()
     }
 
+    /// Reads the keypair of the wallet, see [`read_wallet_keypair`].
     pub fn wallet_kp(&self) -> Result<Keypair> {
-        solana_sdk::signature::read_keypair_file(&self.provider.wallet.to_string())
-            .map_err(|_| anyhow!("Unable to read keypair file"))
+        read_wallet_keypair(self.provider.wallet.to_string()).map_err(Into::into)
+    }
+
+    /// Returns the signer of the wallet, see [`read_wallet`].
+    pub fn wallet_signer(&self) -> Result<DynSigner> {
+        read_wallet(&self.provider.wallet.to_string())
+    }
+}
+
+/// The environment variable holding the token authenticating the requests
+/// to a remote wallet.
+pub const REMOTE_WALLET_TOKEN_ENV: &str = "ANCHOR_REMOTE_WALLET_TOKEN";
+
+/// Returns whether `wallet` is the URL of a signing service rather than the
+/// path of a keypair file: an `https` URL, or an `http` one on the loopback
+/// interface only, since the requests carry the token of the wallet.
+pub fn is_remote_wallet(wallet: &str) -> bool {
+    if wallet.starts_with("https://") {
+        return true;
+    }
+    if !wallet.starts_with("http://") {
+        return false;
+    }
+    let host = Url::parse(wallet)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_owned()));
+    match host.as_deref() {
+        Some("localhost") => true,
+        Some(host) => host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .map(|ip| ip.is_loopback())
+            .unwrap_or(false),
+        None => false,
     }
 }
 
+/// Reads the wallet at `wallet`, a keypair file, possibly an encrypted
+/// keystore, or the URL of a signing service used through a
+/// [`RemoteSigner`].
+pub fn read_wallet(wallet: &str) -> Result<DynSigner> {
+    if wallet.starts_with("http://") && !is_remote_wallet(wallet) {
+        bail!("Remote wallets must be served over https, unless on localhost: {wallet}");
+    }
+    if is_remote_wallet(wallet) {
+        let auth_token = std::env::var(REMOTE_WALLET_TOKEN_ENV).ok();
+        let signer = RemoteSigner::connect_with_auth_token(wallet, auth_token)
+            .with_context(|| format!("Unable to connect to the remote wallet {wallet}"))?;
+        return Ok(DynSigner(Arc::new(signer)));
+    }
+    let keypair = read_wallet_keypair(wallet)?;
+
+    Ok(DynSigner(Arc::new(keypair)))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct _Config {
     anchor_version: Option<String>,
@@ -1369,4 +1423,16 @@ mod tests {
         let config = Config::from_str(&string).unwrap();
         assert!(!config.features.skip_lint);
     }
+
+    #[test]
+    fn remote_wallet_urls() {
+        assert!(is_remote_wallet("https://signer.example.com"));
+        assert!(is_remote_wallet("http://localhost:8080"));
+        assert!(is_remote_wallet("http://127.0.0.1:8080"));
+        assert!(is_remote_wallet("http://[::1]:8080"));
+        assert!(!is_remote_wallet("http://signer.example.com"));
+        assert!(!is_remote_wallet("http://10.0.0.1"));
+        assert!(!is_remote_wallet("~/.config/solana/id.json"));
+        assert!(read_wallet("http://signer.example.com").is_err());
+    }
 }
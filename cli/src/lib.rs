@@ -1,9 +1,11 @@
 use crate::config::{
-    AnchorPackage, BootstrapMode, BuildConfig, Config, ConfigOverride, Manifest, ProgramArch,
-    ProgramDeployment, ProgramWorkspace, ScriptsConfig, TestValidator, WithPath, SHUTDOWN_WAIT,
-    STARTUP_WAIT,
+    is_remote_wallet, read_wallet, AnchorPackage, BootstrapMode, BuildConfig, Config,
+    ConfigOverride, Manifest, ProgramArch, ProgramDeployment, ProgramWorkspace, ScriptsConfig,
+    TestValidator, WithPath, SHUTDOWN_WAIT, STARTUP_WAIT,
+};
+use anchor_client::{
+    is_keystore_file, read_wallet_keypair, Cluster, Keystore, KEYSTORE_PASSPHRASE_ENV,
 };
-use anchor_client::Cluster;
 use anchor_lang::idl::{IdlAccount, IdlInstruction, ERASED_AUTHORITY};
 use anchor_lang::{AccountDeserialize, AnchorDeserialize, AnchorSerialize};
 use anchor_syn::idl::types::{
@@ -349,6 +351,15 @@ pub enum KeysCommand {
         #[clap(short, long)]
         program_name: Option<String>,
     },
+    /// Encrypt a keypair file into a password-protected keystore, usable as
+    /// the provider wallet.
+    Encrypt {
+        /// The keypair file to encrypt
+        keypair: String,
+        /// The keystore file to write
+        #[clap(short, long)]
+        out: String,
+    },
 }
 
 #[derive(Debug, Parser)]
@@ -454,6 +465,17 @@ pub enum IdlCommand {
         #[clap(short, long)]
         out: Option<String>,
     },
+    /// Generates a standalone client crate from an IDL.
+    Client {
+        /// Path to the IDL.
+        file: String,
+        /// Language of the client. Only `rust` is supported.
+        #[clap(long, default_value = "rust")]
+        lang: String,
+        /// Output directory of the client crate.
+        #[clap(short, long)]
+        out: String,
+    },
 }
 
 #[derive(Debug, Parser)]
@@ -1879,6 +1901,7 @@ fn idl(cfg_override: &ConfigOverride, subcmd: IdlCommand) -> Result<()> {
             no_docs,
         } => idl_build(out, out_ts, no_docs),
         IdlCommand::Fetch { address, out } => idl_fetch(cfg_override, address, out),
+        IdlCommand::Client { file, lang, out } => idl_client(file, lang, out),
     }
 }
 
@@ -1979,8 +2002,7 @@ fn idl_set_buffer(
     print_only: bool,
 ) -> Result<()> {
     with_workspace(cfg_override, |cfg| {
-        let keypair = solana_sdk::signature::read_keypair_file(&cfg.provider.wallet.to_string())
-            .map_err(|_| anyhow!("Unable to read keypair file"))?;
+        let keypair = cfg.wallet_signer()?;
         let url = cluster_url(cfg, &cfg.test_validator);
         let client = create_client(url);
 
@@ -2011,12 +2033,8 @@ fn idl_set_buffer(
         } else {
             // Build the transaction.
             let latest_hash = client.get_latest_blockhash()?;
-            let tx = Transaction::new_signed_with_payer(
-                &[ix],
-                Some(&keypair.pubkey()),
-                &[&keypair],
-                latest_hash,
-            );
+            let mut tx = Transaction::new_with_payer(&[ix], Some(&keypair.pubkey()));
+            tx.try_sign(&[&keypair], latest_hash)?;
 
             // Send the transaction.
             client.send_and_confirm_transaction_with_spinner(&tx)?;
@@ -2069,8 +2087,7 @@ fn idl_set_authority(
             None => IdlAccount::address(&program_id),
             Some(addr) => addr,
         };
-        let keypair = solana_sdk::signature::read_keypair_file(&cfg.provider.wallet.to_string())
-            .map_err(|_| anyhow!("Unable to read keypair file"))?;
+        let keypair = cfg.wallet_signer()?;
         let url = cluster_url(cfg, &cfg.test_validator);
         let client = create_client(url);
 
@@ -2102,12 +2119,8 @@ fn idl_set_authority(
         } else {
             // Send transaction.
             let latest_hash = client.get_latest_blockhash()?;
-            let tx = Transaction::new_signed_with_payer(
-                &[ix],
-                Some(&keypair.pubkey()),
-                &[&keypair],
-                latest_hash,
-            );
+            let mut tx = Transaction::new_with_payer(&[ix], Some(&keypair.pubkey()));
+            tx.try_sign(&[&keypair], latest_hash)?;
             client.send_and_confirm_transaction_with_spinner(&tx)?;
 
             println!("Authority update complete.");
@@ -2139,8 +2152,7 @@ fn idl_close_account(
     idl_address: Pubkey,
     print_only: bool,
 ) -> Result<()> {
-    let keypair = solana_sdk::signature::read_keypair_file(&cfg.provider.wallet.to_string())
-        .map_err(|_| anyhow!("Unable to read keypair file"))?;
+    let keypair = cfg.wallet_signer()?;
     let url = cluster_url(cfg, &cfg.test_validator);
     let client = create_client(url);
 
@@ -2167,12 +2179,8 @@ fn idl_close_account(
     } else {
         // Send transaction.
         let latest_hash = client.get_latest_blockhash()?;
-        let tx = Transaction::new_signed_with_payer(
-            &[ix],
-            Some(&keypair.pubkey()),
-            &[&keypair],
-            latest_hash,
-        );
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&keypair.pubkey()));
+        tx.try_sign(&[&keypair], latest_hash)?;
         client.send_and_confirm_transaction_with_spinner(&tx)?;
     }
 
@@ -2188,8 +2196,7 @@ fn idl_write(cfg: &Config, program_id: &Pubkey, idl: &Idl, idl_address: Pubkey)
     idl.metadata = None;
 
     // Misc.
-    let keypair = solana_sdk::signature::read_keypair_file(&cfg.provider.wallet.to_string())
-        .map_err(|_| anyhow!("Unable to read keypair file"))?;
+    let keypair = cfg.wallet_signer()?;
     let url = cluster_url(cfg, &cfg.test_validator);
     let client = create_client(url);
 
@@ -2225,12 +2232,8 @@ fn idl_write(cfg: &Config, program_id: &Pubkey, idl: &Idl, idl_address: Pubkey)
         };
         // Send transaction.
         let latest_hash = client.get_latest_blockhash()?;
-        let tx = Transaction::new_signed_with_payer(
-            &[ix],
-            Some(&keypair.pubkey()),
-            &[&keypair],
-            latest_hash,
-        );
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&keypair.pubkey()));
+        tx.try_sign(&[&keypair], latest_hash)?;
         client.send_and_confirm_transaction_with_spinner(&tx)?;
         offset += MAX_WRITE_SIZE;
     }
@@ -2529,6 +2532,34 @@ fn idl_fetch(cfg_override: &ConfigOverride, address: Pubkey, out: Option<String>
     write_idl(&idl, out)
 }
 
+fn idl_client(file: String, lang: String, out: String) -> Result<()> {
+    if lang != "rust" {
+        return Err(anyhow!("Unsupported client language: {lang}"));
+    }
+
+    let idl: Idl = serde_json::from_slice(&fs::read(&*shellexpand::tilde(&file))?)?;
+    let out = PathBuf::from(&*shellexpand::tilde(&out));
+    let src = out.join("src");
+    fs::create_dir_all(&src)?;
+    fs::write(
+        out.join("Cargo.toml"),
+        rust_template::client_cargo_toml(&idl),
+    )?;
+    fs::write(src.join("lib.rs"), rust_template::client_lib_rs(&idl))?;
+
+    // Formatting is best effort, the client is valid either way.
+    let _ = std::process::Command::new("rustfmt")
+        .arg("--edition")
+        .arg("2021")
+        .arg(src.join("lib.rs"))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+
+    println!("Client generated in {}", out.display());
+    Ok(())
+}
+
 fn write_idl(idl: &Idl, out: OutFile) -> Result<()> {
     let idl_json = serde_json::to_string_pretty(idl)?;
     match out {
@@ -3260,7 +3291,7 @@ fn start_test_validator(
         .arg("--ledger")
         .arg(test_ledger_directory)
         .arg("--mint")
-        .arg(cfg.wallet_kp()?.pubkey().to_string())
+        .arg(cfg.wallet_signer()?.pubkey().to_string())
         .args(flags.unwrap_or_default())
         .stdout(test_validator_stdout)
         .stderr(test_validator_stderr)
@@ -3388,6 +3419,11 @@ fn deploy(
     with_workspace(cfg_override, |cfg| {
         let url = cluster_url(cfg, &cfg.test_validator);
         let keypair = cfg.provider.wallet.to_string();
+        if is_remote_wallet(&keypair) {
+            return Err(anyhow!(
+                "Remote wallets can't deploy programs, `solana program deploy` requires a keypair file"
+            ));
+        }
 
         // Deploy the programs.
         println!("Deploying cluster: {}", url);
@@ -3413,20 +3449,17 @@ fn deploy(
             };
 
             // Send deploy transactions using the Solana CLI
-            let exit = std::process::Command::new("solana")
-                .arg("program")
+            let mut cmd = std::process::Command::new("solana");
+            cmd.arg("program")
                 .arg("deploy")
                 .arg("--url")
                 .arg(&url)
-                .arg("--keypair")
-                .arg(&keypair)
                 .arg("--program-id")
                 .arg(strip_workspace_prefix(program_keypair_filepath))
                 .arg(strip_workspace_prefix(binary_path))
                 .stdout(Stdio::inherit())
-                .stderr(Stdio::inherit())
-                .output()
-                .expect("Must deploy");
+                .stderr(Stdio::inherit());
+            let exit = solana_output_with_wallet(cmd, &keypair)?;
 
             // Check if deployment was successful
             if !exit.status.success() {
@@ -3454,6 +3487,35 @@ fn deploy(
     })
 }
 
+// Runs a `solana` CLI command signed by `wallet`. A keystore is decrypted and
+// passed through stdin, the CLI only reading plaintext keypairs.
+fn solana_output_with_wallet(
+    mut cmd: std::process::Command,
+    wallet: &str,
+) -> Result<std::process::Output> {
+    if !is_keystore_file(wallet) {
+        return Ok(cmd
+            .arg("--keypair")
+            .arg(wallet)
+            .output()
+            .expect("Must deploy"));
+    }
+    let keypair = read_wallet_keypair(wallet)?;
+    let mut child = cmd
+        .arg("--keypair")
+        .arg("stdin://")
+        .stdin(Stdio::piped())
+        .spawn()
+        .expect("Must deploy");
+    let stdin = child.stdin.take().expect("Stdin is piped");
+    // Dropping stdin closes it, the CLI reading the keypair until then.
+    let written = serde_json::to_writer(stdin, &keypair.to_bytes().to_vec());
+    let output = child.wait_with_output()?;
+    written?;
+
+    Ok(output)
+}
+
 fn upgrade(
     cfg_override: &ConfigOverride,
     program_id: Pubkey,
@@ -3463,21 +3525,23 @@ fn upgrade(
     let program_filepath = path.canonicalize()?.display().to_string();
 
     with_workspace(cfg_override, |cfg| {
+        if is_remote_wallet(&cfg.provider.wallet.to_string()) {
+            return Err(anyhow!(
+                "Remote wallets can't upgrade programs, `solana program deploy` requires a keypair file"
+            ));
+        }
         let url = cluster_url(cfg, &cfg.test_validator);
-        let exit = std::process::Command::new("solana")
-            .arg("program")
+        let mut cmd = std::process::Command::new("solana");
+        cmd.arg("program")
             .arg("deploy")
             .arg("--url")
             .arg(url)
-            .arg("--keypair")
-            .arg(&cfg.provider.wallet.to_string())
             .arg("--program-id")
             .arg(strip_workspace_prefix(program_id.to_string()))
             .arg(strip_workspace_prefix(program_filepath))
             .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .output()
-            .expect("Must deploy");
+            .stderr(Stdio::inherit());
+        let exit = solana_output_with_wallet(cmd, &cfg.provider.wallet.to_string())?;
         if !exit.status.success() {
             println!("There was a problem deploying: {exit:?}.");
             std::process::exit(exit.status.code().unwrap_or(1));
@@ -3494,8 +3558,7 @@ fn create_idl_account(
 ) -> Result<Pubkey> {
     // Misc.
     let idl_address = IdlAccount::address(program_id);
-    let keypair = solana_sdk::signature::read_keypair_file(keypair_path)
-        .map_err(|_| anyhow!("Unable to read keypair file"))?;
+    let keypair = read_wallet(keypair_path)?;
     let url = cluster_url(cfg, &cfg.test_validator);
     let client = create_client(url);
     let idl_data = serialize_idl(idl)?;
@@ -3546,12 +3609,8 @@ fn create_idl_account(
             });
         }
         let latest_hash = client.get_latest_blockhash()?;
-        let tx = Transaction::new_signed_with_payer(
-            &instructions,
-            Some(&keypair.pubkey()),
-            &[&keypair],
-            latest_hash,
-        );
+        let mut tx = Transaction::new_with_payer(&instructions, Some(&keypair.pubkey()));
+        tx.try_sign(&[&keypair], latest_hash)?;
         client.send_and_confirm_transaction_with_spinner(&tx)?;
     }
 
@@ -3567,8 +3626,7 @@ fn create_idl_buffer(
     program_id: &Pubkey,
     idl: &Idl,
 ) -> Result<Pubkey> {
-    let keypair = solana_sdk::signature::read_keypair_file(keypair_path)
-        .map_err(|_| anyhow!("Unable to read keypair file"))?;
+    let keypair = read_wallet(keypair_path)?;
     let url = cluster_url(cfg, &cfg.test_validator);
     let client = create_client(url);
 
@@ -3605,12 +3663,11 @@ fn create_idl_buffer(
 
     // Build the transaction.
     let latest_hash = client.get_latest_blockhash()?;
-    let tx = Transaction::new_signed_with_payer(
+    let mut tx = Transaction::new_with_payer(
         &[create_account_ix, create_buffer_ix],
         Some(&keypair.pubkey()),
-        &[&keypair, &buffer],
-        latest_hash,
     );
+    tx.try_sign(&[&keypair as &dyn Signer, &buffer], latest_hash)?;
 
     // Send the transaction.
     client.send_and_confirm_transaction_with_spinner(&tx)?;
@@ -4046,6 +4103,7 @@ fn keys(cfg_override: &ConfigOverride, cmd: KeysCommand) -> Result<()> {
     match cmd {
         KeysCommand::List => keys_list(cfg_override),
         KeysCommand::Sync { program_name } => keys_sync(cfg_override, program_name),
+        KeysCommand::Encrypt { keypair, out } => keys_encrypt(keypair, out),
     }
 }
 
@@ -4059,6 +4117,33 @@ fn keys_list(cfg_override: &ConfigOverride) -> Result<()> {
     })
 }
 
+/// Encrypt the keypair at `keypair` into a keystore at `out`, with the passphrase of
+/// `ANCHOR_WALLET_PASSPHRASE` or prompted for.
+fn keys_encrypt(keypair: String, out: String) -> Result<()> {
+    if Path::new(&out).exists() {
+        return Err(anyhow!("{out} already exists"));
+    }
+    let kp = solana_sdk::signature::read_keypair_file(&keypair)
+        .map_err(|_| anyhow!("Unable to read keypair file {keypair}"))?;
+    let passphrase = match std::env::var(KEYSTORE_PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        Err(_) => {
+            let passphrase = rpassword::prompt_password("Passphrase: ")?;
+            if passphrase != rpassword::prompt_password("Confirm passphrase: ")? {
+                return Err(anyhow!("Passphrases don't match"));
+            }
+            passphrase
+        }
+    };
+    if passphrase.is_empty() {
+        return Err(anyhow!("The passphrase can't be empty"));
+    }
+    Keystore::encrypt(&kp, &passphrase)?.write(&out)?;
+    println!("Encrypted {} into {out}", kp.pubkey());
+
+    Ok(())
+}
+
 /// Sync the program's `declare_id!` pubkey with the pubkey from `target/deploy/<KEYPAIR>.json`.
 fn keys_sync(cfg_override: &ConfigOverride, program_name: Option<String>) -> Result<()> {
     with_workspace(cfg_override, |cfg| {
@@ -3,7 +3,7 @@ use crate::{config::ProgramWorkspace, create_files, Files};
 use anchor_syn::idl::types::Idl;
 use anyhow::Result;
 use clap::{Parser, ValueEnum};
-use heck::{ToLowerCamelCase, ToSnakeCase, ToUpperCamelCase};
+use heck::{ToKebabCase, ToLowerCamelCase, ToSnakeCase, ToUpperCamelCase};
 use solana_sdk::{
     pubkey::Pubkey,
     signature::{read_keypair_file, write_keypair_file, Keypair},
@@ -236,6 +236,40 @@ export const IDL: {} = {};
     ))
 }
 
+pub fn client_cargo_toml(idl: &Idl) -> String {
+    format!(
+        r#"[package]
+name = "{0}-client"
+version = "{1}"
+description = "Client for the {2} program, generated by `anchor idl client`"
+edition = "2021"
+
+[lib]
+name = "{3}_client"
+
+[dependencies]
+anchor-lang = "{4}"
+"#,
+        idl.name.to_kebab_case(),
+        idl.version,
+        idl.name,
+        idl.name.to_snake_case(),
+        VERSION,
+    )
+}
+
+pub fn client_lib_rs(idl: &Idl) -> String {
+    format!(
+        r#"//! Client for the `{}` program, generated by `anchor idl client`.
+#![allow(clippy::all)]
+
+{}
+"#,
+        idl.name,
+        anchor_syn::idl::client::generate(idl),
+    )
+}
+
 pub fn deploy_js_script_host(cluster_url: &str, script_path: &str) -> String {
     format!(
         r#"
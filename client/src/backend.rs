@@ -0,0 +1,239 @@
+//! Backends executing the requests of a client, see `Client::with_backend`.
+
+use crate::{ClientError, Simulation};
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use async_trait::async_trait;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::client_error::ClientError as SolanaClientError;
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_client::rpc_config::{
+    RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSimulateTransactionConfig,
+};
+use solana_client::rpc_filter::RpcFilterType;
+use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+
+/// Sends transactions and reads accounts on behalf of a client, e.g. over
+/// RPC or in process for tests.
+///
+/// Retries, lookup table creation, incremental reallocations and sends with
+/// a spinner rely on RPC methods, failing with backends without an
+/// [`rpc_url`](Backend::rpc_url). Subscriptions and event backfills use the
+/// RPC of the cluster whatever the backend.
+#[async_trait]
+pub trait Backend: Send + Sync {
+    /// Returns the blockhash to sign transactions with.
+    async fn get_latest_blockhash(&self) -> Result<Hash, ClientError>;
+
+    /// Sends a transaction, returning its signature once it's confirmed.
+    async fn send_transaction(&self, tx: &VersionedTransaction) -> Result<Signature, ClientError>;
+
+    /// Simulates a transaction, signatures unverified.
+    async fn simulate_transaction(
+        &self,
+        tx: &VersionedTransaction,
+    ) -> Result<Simulation, ClientError>;
+
+    async fn get_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError>;
+
+    /// Returns the accounts at `addresses`, at most `MAX_MULTIPLE_ACCOUNTS`,
+    /// as of `min_context_slot` or later if given. Backends without slots
+    /// may ignore it.
+    async fn get_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+        _min_context_slot: Option<u64>,
+    ) -> Result<Vec<Option<Account>>, ClientError> {
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            accounts.push(self.get_account(address).await?);
+        }
+        Ok(accounts)
+    }
+
+    /// Returns the accounts owned by `program_id` matching all `filters`.
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, Account)>, ClientError>;
+
+    /// Returns the prioritization fees recently paid to write the
+    /// `writable` accounts, none by default.
+    async fn get_recent_prioritization_fees(
+        &self,
+        _writable: &[Pubkey],
+    ) -> Result<Vec<u64>, ClientError> {
+        Ok(Vec::new())
+    }
+
+    /// Returns the URL of the JSON RPC the backend sends to, if any.
+    fn rpc_url(&self) -> Option<String> {
+        None
+    }
+}
+
+/// The backend of a cluster's JSON RPC, the default one.
+pub struct RpcBackend {
+    rpc_client: AsyncRpcClient,
+}
+
+impl RpcBackend {
+    pub fn new(url: &str, commitment: CommitmentConfig) -> Self {
+        Self {
+            rpc_client: AsyncRpcClient::new_with_commitment(url.to_string(), commitment),
+        }
+    }
+
+    pub fn rpc(&self) -> &AsyncRpcClient {
+        &self.rpc_client
+    }
+}
+
+impl From<AsyncRpcClient> for RpcBackend {
+    fn from(rpc_client: AsyncRpcClient) -> Self {
+        Self { rpc_client }
+    }
+}
+
+#[async_trait]
+impl Backend for RpcBackend {
+    async fn get_latest_blockhash(&self) -> Result<Hash, ClientError> {
+        self.rpc_client
+            .get_latest_blockhash()
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn send_transaction(&self, tx: &VersionedTransaction) -> Result<Signature, ClientError> {
+        self.rpc_client
+            .send_and_confirm_transaction(tx)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &VersionedTransaction,
+    ) -> Result<Simulation, ClientError> {
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(self.rpc_client.commitment()),
+            ..RpcSimulateTransactionConfig::default()
+        };
+        let result = self
+            .rpc_client
+            .simulate_transaction_with_config(tx, config)
+            .await?
+            .value;
+
+        let return_data = match result.return_data {
+            Some(return_data) => {
+                let program_id = return_data
+                    .program_id
+                    .parse()
+                    .map_err(|_| ClientError::LogParseError(return_data.program_id.clone()))?;
+                let data = anchor_lang::__private::base64::decode(&return_data.data.0)
+                    .map_err(|e| ClientError::LogParseError(e.to_string()))?;
+                Some((program_id, data))
+            }
+            None => None,
+        };
+
+        Ok(Simulation::new(
+            result.err,
+            result.logs.unwrap_or_default(),
+            result.units_consumed,
+            return_data,
+        ))
+    }
+
+    async fn get_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        Ok(self
+            .rpc_client
+            .get_account_with_commitment(address, self.rpc_client.commitment())
+            .await?
+            .value)
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+        min_context_slot: Option<u64>,
+    ) -> Result<Vec<Option<Account>>, ClientError> {
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64Zstd),
+            commitment: Some(self.rpc_client.commitment()),
+            min_context_slot,
+            ..RpcAccountInfoConfig::default()
+        };
+        Ok(self
+            .rpc_client
+            .get_multiple_accounts_with_config(addresses, config)
+            .await?
+            .value)
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, Account)>, ClientError> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        self.rpc_client
+            .get_program_accounts_with_config(program_id, config)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn get_recent_prioritization_fees(
+        &self,
+        writable: &[Pubkey],
+    ) -> Result<Vec<u64>, ClientError> {
+        Ok(self
+            .rpc_client
+            .get_recent_prioritization_fees(writable)
+            .await?
+            .iter()
+            .map(|fee| fee.prioritization_fee)
+            .collect())
+    }
+
+    fn rpc_url(&self) -> Option<String> {
+        Some(self.rpc_client.url())
+    }
+}
+
+// Returns the error of a transaction failing its preflight simulation, as
+// returned by the RPC, for the logs to be decoded along with the error.
+pub(crate) fn preflight_failure(
+    err: TransactionError,
+    logs: Vec<String>,
+    units_consumed: Option<u64>,
+) -> ClientError {
+    SolanaClientError::from(RpcError::RpcResponseError {
+        code: -32002,
+        message: format!("Transaction simulation failed: {err}"),
+        data: RpcResponseErrorData::SendTransactionPreflightFailure(RpcSimulateTransactionResult {
+            err: Some(err),
+            logs: Some(logs),
+            accounts: None,
+            units_consumed,
+            return_data: None,
+        }),
+    })
+    .into()
+}
@@ -0,0 +1,509 @@
+//! Events of past transactions, see `Program::transaction_events` and
+//! `Program::backfill`.
+
+use crate::logs::LOG_TRUNCATED;
+use crate::{ClientError, EventContext, PROGRAM_DATA, PROGRAM_LOG};
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{AnchorDeserialize, Event};
+use futures::Stream;
+use regex::Regex;
+
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransactionWithStatusMeta, UiInstruction,
+    UiTransactionEncoding,
+};
+use std::pin::Pin;
+use std::str::FromStr;
+#[cfg(not(feature = "async"))]
+use tokio::runtime::Handle;
+
+/// A position in the transaction history of a program, see
+/// `Program::backfill`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryPosition {
+    Signature(Signature),
+    Slot(u64),
+}
+
+/// The events of `Program::backfill`, oldest first.
+pub type EventStream<T> = Pin<Box<dyn Stream<Item = Result<(EventContext, T), ClientError>>>>;
+
+/// Iterator over the events of `Program::backfill`, oldest first. Pages of
+/// transactions are fetched as the iterator advances.
+#[cfg(not(feature = "async"))]
+pub struct EventIterator<'a, T> {
+    pub(crate) stream: EventStream<T>,
+    pub(crate) handle: &'a Handle,
+}
+
+#[cfg(not(feature = "async"))]
+impl<'a, T> Iterator for EventIterator<'a, T> {
+    type Item = Result<(EventContext, T), ClientError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use futures::StreamExt;
+        self.handle.block_on(self.stream.next())
+    }
+}
+
+// The transaction history isn't available at the processed commitment.
+fn history_commitment(commitment: CommitmentConfig) -> CommitmentConfig {
+    if commitment.is_at_least_confirmed() {
+        commitment
+    } else {
+        CommitmentConfig::confirmed()
+    }
+}
+
+// Returns the cursors of the pages of the transaction history of `address`
+// reaching back to `after`, oldest page first, a cursor being the signature
+// its page is fetched before. The history is walked newest first a page at a
+// time, its pages being fetched again oldest first by `page_signatures`.
+pub(crate) async fn history_pages(
+    rpc_client: &AsyncRpcClient,
+    address: &Pubkey,
+    after: Option<HistoryPosition>,
+    before: Option<HistoryPosition>,
+    commitment: CommitmentConfig,
+) -> Result<Vec<Option<Signature>>, ClientError> {
+    let mut cursor = match before {
+        Some(HistoryPosition::Signature(signature)) => Some(signature),
+        _ => None,
+    };
+    let mut cursors = Vec::new();
+    loop {
+        let page = signatures_page(rpc_client, address, cursor, after, commitment).await?;
+        let last = match page.last() {
+            Some(last) => last.signature.parse().ok(),
+            None => break,
+        };
+        cursors.push(cursor);
+        let reached_after = page.iter().any(
+            |status| matches!(after, Some(HistoryPosition::Slot(slot)) if status.slot <= slot),
+        );
+        if reached_after || last.is_none() {
+            break;
+        }
+        cursor = last;
+    }
+    cursors.reverse();
+
+    Ok(cursors)
+}
+
+// Returns the signatures and slots of the successful transactions of the
+// page of `address` fetched before `cursor`, strictly between `after` and
+// `before`, oldest first.
+pub(crate) async fn page_signatures(
+    rpc_client: &AsyncRpcClient,
+    address: &Pubkey,
+    cursor: Option<Signature>,
+    after: Option<HistoryPosition>,
+    before: Option<HistoryPosition>,
+    commitment: CommitmentConfig,
+) -> Result<Vec<(Signature, u64)>, ClientError> {
+    let page = signatures_page(rpc_client, address, cursor, after, commitment).await?;
+    let mut signatures: Vec<(Signature, u64)> = page
+        .into_iter()
+        .filter(|status| {
+            !matches!(after, Some(HistoryPosition::Slot(slot)) if status.slot <= slot)
+                && !matches!(before, Some(HistoryPosition::Slot(slot)) if status.slot >= slot)
+                && status.err.is_none()
+        })
+        .filter_map(|status| Some((status.signature.parse().ok()?, status.slot)))
+        .collect();
+    signatures.reverse();
+
+    Ok(signatures)
+}
+
+// Fetches the page of signatures of `address` before `cursor`, newest first,
+// down to `after` if it's a signature.
+async fn signatures_page(
+    rpc_client: &AsyncRpcClient,
+    address: &Pubkey,
+    cursor: Option<Signature>,
+    after: Option<HistoryPosition>,
+    commitment: CommitmentConfig,
+) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, ClientError> {
+    let config = GetConfirmedSignaturesForAddress2Config {
+        before: cursor,
+        until: match after {
+            Some(HistoryPosition::Signature(signature)) => Some(signature),
+            _ => None,
+        },
+        limit: None,
+        commitment: Some(history_commitment(commitment)),
+    };
+    rpc_client
+        .get_signatures_for_address_with_config(address, config)
+        .await
+        .map_err(Into::into)
+}
+
+// Flattens the items of a fetch into a stream, the error if it failed.
+pub(crate) fn flatten_fetch<T>(
+    fetch: Result<Vec<T>, ClientError>,
+) -> futures::stream::Iter<std::vec::IntoIter<Result<T, ClientError>>> {
+    futures::stream::iter(match fetch {
+        Ok(items) => items.into_iter().map(Ok).collect::<Vec<_>>(),
+        Err(e) => vec![Err(e)],
+    })
+}
+
+pub(crate) async fn get_transaction(
+    rpc_client: &AsyncRpcClient,
+    signature: &Signature,
+    commitment: CommitmentConfig,
+) -> Result<EncodedConfirmedTransactionWithStatusMeta, ClientError> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(history_commitment(commitment)),
+        max_supported_transaction_version: Some(0),
+    };
+    rpc_client
+        .get_transaction_with_config(signature, config)
+        .await
+        .map_err(Into::into)
+}
+
+// Decodes the events of `program_id` in a transaction, logged with `emit!`
+// or emitted through a self-CPI with `emit_cpi!`, in the order they were
+// emitted. Failed transactions have no events, their changes being rolled
+// back, and events that don't decode as `T` are skipped.
+pub(crate) fn transaction_events<T: Event + AnchorDeserialize>(
+    program_id: &Pubkey,
+    tx: &EncodedTransactionWithStatusMeta,
+) -> Result<Vec<T>, ClientError> {
+    let meta = match &tx.meta {
+        Some(meta) if meta.err.is_none() => meta,
+        _ => return Ok(Vec::new()),
+    };
+    let cpis = program_cpis(program_id, tx)?;
+    let logs = match &meta.log_messages {
+        OptionSerializer::Some(logs) => &logs[..],
+        _ => &[],
+    };
+    let invoke_re = Regex::new(r"^Program (\w+) invoke \[(\d+)\]$").unwrap();
+    let return_re = Regex::new(r"^Program \w+ (?:success|failed: .*)$").unwrap();
+
+    // CPIs are matched to inner instructions by the order they're invoked
+    // in, the data of the self-CPIs of an instruction being emitted where
+    // they're invoked.
+    let mut events = Vec::new();
+    let mut consumed = vec![0; cpis.len()];
+    let mut instruction: Option<usize> = None;
+    let mut stack: Vec<Option<Pubkey>> = Vec::new();
+    for log in logs {
+        if log == LOG_TRUNCATED {
+            break;
+        } else if let Some(captures) = invoke_re.captures(log) {
+            let depth = match captures[2].parse::<usize>() {
+                Ok(depth) if depth > 0 => depth,
+                _ => continue,
+            };
+            stack.truncate(depth - 1);
+            stack.push(Pubkey::from_str(&captures[1]).ok());
+            if depth == 1 {
+                instruction = Some(instruction.map_or(0, |i| i + 1));
+            } else if let Some(i) = instruction.filter(|i| *i < cpis.len()) {
+                if let Some(Some(data)) = cpis[i].get(consumed[i]) {
+                    events.extend(decode_cpi_event(data));
+                }
+                consumed[i] += 1;
+            }
+        } else if return_re.is_match(log) {
+            stack.pop();
+        } else if stack.last() == Some(&Some(*program_id)) {
+            let data = if let Some(data) = log.strip_prefix(PROGRAM_DATA) {
+                // Data is logged as space separated base64 fields.
+                data.split(' ')
+                    .map(anchor_lang::__private::base64::decode)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|fields| fields.concat())
+                    .ok()
+            } else if let Some(msg) = log.strip_prefix(PROGRAM_LOG) {
+                // Events logged in base64 with `msg!` by older programs.
+                anchor_lang::__private::base64::decode(msg).ok()
+            } else {
+                None
+            };
+            events.extend(data.as_deref().and_then(decode_event));
+        }
+    }
+
+    // The self-CPIs the logs don't reach, e.g. once truncated.
+    for (instruction, cpis) in cpis.iter().enumerate() {
+        let unreached = &cpis[consumed[instruction].min(cpis.len())..];
+        for data in unreached.iter().flatten() {
+            events.extend(decode_cpi_event(data));
+        }
+    }
+
+    Ok(events)
+}
+
+// Returns the data of the CPIs of each instruction of a transaction, in the
+// order they were invoked, `None` for CPIs to other programs than
+// `program_id`.
+fn program_cpis(
+    program_id: &Pubkey,
+    tx: &EncodedTransactionWithStatusMeta,
+) -> Result<Vec<Vec<Option<Vec<u8>>>>, ClientError> {
+    let mut cpis: Vec<Vec<Option<Vec<u8>>>> = Vec::new();
+    let inner_instructions = match tx.meta.as_ref().map(|meta| &meta.inner_instructions) {
+        Some(OptionSerializer::Some(inner_instructions)) => inner_instructions,
+        _ => return Ok(cpis),
+    };
+    let transaction = tx
+        .transaction
+        .decode()
+        .ok_or_else(|| ClientError::LogParseError("Unable to decode transaction".into()))?;
+    let mut account_keys = transaction.message.static_account_keys().to_vec();
+    if let Some(OptionSerializer::Some(loaded)) =
+        tx.meta.as_ref().map(|meta| &meta.loaded_addresses)
+    {
+        for key in loaded.writable.iter().chain(&loaded.readonly) {
+            let key = key
+                .parse()
+                .map_err(|_| ClientError::LogParseError(key.to_string()))?;
+            account_keys.push(key);
+        }
+    }
+    for inner in inner_instructions {
+        let index = usize::from(inner.index);
+        if cpis.len() <= index {
+            cpis.resize_with(index + 1, Vec::new);
+        }
+        for ix in &inner.instructions {
+            let data = match ix {
+                UiInstruction::Compiled(ix)
+                    if account_keys.get(usize::from(ix.program_id_index)) == Some(program_id) =>
+                {
+                    solana_sdk::bs58::decode(&ix.data).into_vec().ok()
+                }
+                _ => None,
+            };
+            cpis[index].push(data);
+        }
+    }
+
+    Ok(cpis)
+}
+
+// Decodes logged event data, `None` if it isn't an event of type `T`.
+fn decode_event<T: Event + AnchorDeserialize>(data: &[u8]) -> Option<T> {
+    let event_data = data.strip_prefix(&T::discriminator()[..])?;
+    T::deserialize(&mut &event_data[..]).ok()
+}
+
+// Decodes the data of an `emit_cpi!` instruction, `None` if it isn't an
+// event of type `T`.
+fn decode_cpi_event<T: Event + AnchorDeserialize>(data: &[u8]) -> Option<T> {
+    decode_event(data.strip_prefix(&EVENT_IX_TAG_LE[..])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logs::tests::Counted;
+    use anchor_lang::Discriminator;
+    use serde_json::json;
+    use serde_json::Value as JsonValue;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::transaction::Transaction;
+    use solana_transaction_status::Encodable;
+
+    #[test]
+    fn logged_and_cpi_events() {
+        let program_id = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let ix = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![AccountMeta::new_readonly(other, false)],
+        );
+        let tx = Transaction::new_with_payer(&[ix.clone(), ix], Some(&payer));
+
+        let logged = |count: u64| {
+            let data = Counted { count }.data();
+            format!(
+                "Program data: {}",
+                anchor_lang::__private::base64::encode(data)
+            )
+        };
+        let cpi_data = |count: u64| {
+            let mut data = EVENT_IX_TAG_LE.to_vec();
+            data.extend_from_slice(&Counted { count }.data());
+            solana_sdk::bs58::encode(data).into_string()
+        };
+        let index_of = |key: Pubkey| tx.message.account_keys.iter().position(|k| *k == key);
+        // An event of the right type that doesn't deserialize.
+        let undecodable = format!(
+            "Program data: {}",
+            anchor_lang::__private::base64::encode(Counted::discriminator())
+        );
+        let mut meta = json!({
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5000,
+            "preBalances": [],
+            "postBalances": [],
+            "innerInstructions": [{
+                "index": 0,
+                "instructions": [
+                    {
+                        "programIdIndex": index_of(other),
+                        "accounts": [],
+                        "data": cpi_data(0),
+                    },
+                    {
+                        "programIdIndex": index_of(program_id),
+                        "accounts": [],
+                        "data": cpi_data(2),
+                    },
+                ],
+            }],
+            "logMessages": [
+                format!("Program {program_id} invoke [1]"),
+                logged(1),
+                format!("Program {other} invoke [2]"),
+                logged(0),
+                format!("Program {other} success"),
+                undecodable,
+                format!("Program {program_id} invoke [2]"),
+                format!("Program {program_id} success"),
+                logged(3),
+                format!("Program {program_id} success"),
+                format!("Program {program_id} invoke [1]"),
+                logged(4),
+                format!("Program {program_id} success"),
+            ],
+        });
+        let encoded = |meta: &JsonValue| EncodedTransactionWithStatusMeta {
+            transaction: tx.encode(UiTransactionEncoding::Base64),
+            meta: Some(serde_json::from_value(meta.clone()).unwrap()),
+            version: None,
+        };
+
+        // Logged and CPI events are interleaved as they were emitted, events
+        // of other programs and undecodable ones being skipped.
+        let events: Vec<Counted> = transaction_events(&program_id, &encoded(&meta)).unwrap();
+        let counts: Vec<u64> = events.iter().map(|event| event.count).collect();
+        assert_eq!(counts, vec![1, 2, 3, 4]);
+        // Events of other programs are ignored.
+        let events: Vec<Counted> =
+            transaction_events(&Pubkey::new_unique(), &encoded(&meta)).unwrap();
+        assert!(events.is_empty());
+
+        // Failed transactions have no events.
+        meta["err"] = json!({ "InstructionError": [1, { "Custom": 1 }] });
+        meta["status"] = json!({ "Err": meta["err"] });
+        let events: Vec<Counted> = transaction_events(&program_id, &encoded(&meta)).unwrap();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn history_between_bounds() {
+        // The history of the address, newest first, served by pages of 2.
+        let history: Vec<(Signature, u64, bool)> = (1..=6)
+            .rev()
+            .map(|i| (Signature::new_unique(), i * 10, i == 4))
+            .collect();
+        let rpc = {
+            let history = history.clone();
+            crate::test_rpc::TestRpc::start(move |method, params| {
+                if method != "getSignaturesForAddress" {
+                    return None;
+                }
+                let position = |key: &str| {
+                    let signature = params[1][key].as_str()?;
+                    history
+                        .iter()
+                        .position(|(s, _, _)| s.to_string() == signature)
+                };
+                let start = position("before").map_or(0, |i| i + 1);
+                let end = position("until").unwrap_or(history.len());
+                let page: Vec<_> = history[start..end.max(start)]
+                    .iter()
+                    .take(2)
+                    .map(|(signature, slot, failed)| {
+                        json!({
+                            "signature": signature.to_string(),
+                            "slot": slot,
+                            "err": if *failed { json!({ "InstructionError": [0, { "Custom": 1 }] }) } else { json!(null) },
+                            "memo": null,
+                            "blockTime": null,
+                        })
+                    })
+                    .collect();
+                Some(json!(page))
+            })
+        };
+        let rpc_client = AsyncRpcClient::new(rpc.url().to_string());
+        let address = Pubkey::new_unique();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let commitment = CommitmentConfig::confirmed();
+        let pages = |after, before| {
+            rt.block_on(history_pages(
+                &rpc_client,
+                &address,
+                after,
+                before,
+                commitment,
+            ))
+            .unwrap()
+        };
+        let between = |after, before| {
+            pages(after, before)
+                .into_iter()
+                .flat_map(|cursor| {
+                    rt.block_on(page_signatures(
+                        &rpc_client,
+                        &address,
+                        cursor,
+                        after,
+                        before,
+                        commitment,
+                    ))
+                    .unwrap()
+                })
+                .map(|(_, slot)| slot)
+                .collect::<Vec<_>>()
+        };
+
+        // The history is walked a page at a time, oldest page last fetched
+        // before slot 30.
+        assert_eq!(
+            pages(None, None),
+            vec![Some(history[3].0), Some(history[1].0), None]
+        );
+        assert_eq!(pages(Some(HistoryPosition::Slot(40)), None).len(), 2);
+
+        // The failed transaction at slot 40 is skipped.
+        assert_eq!(between(None, None), vec![10, 20, 30, 50, 60]);
+        assert_eq!(
+            between(
+                Some(HistoryPosition::Signature(history[4].0)),
+                Some(HistoryPosition::Signature(history[0].0)),
+            ),
+            vec![30, 50]
+        );
+        assert_eq!(
+            between(
+                Some(HistoryPosition::Slot(10)),
+                Some(HistoryPosition::Slot(60)),
+            ),
+            vec![20, 30, 50]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! A backend over the `BanksClient` of `solana-program-test`, see
+//! `BanksBackend`.
+
+use crate::backend::{preflight_failure, Backend};
+use crate::{ClientError, Simulation};
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use async_trait::async_trait;
+use solana_banks_client::{BanksClient, BanksClientError};
+use solana_client::client_error::ClientError as SolanaClientError;
+use solana_client::rpc_filter::RpcFilterType;
+use solana_sdk::account::Account;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+
+/// The backend of an in-process bank, e.g. of `ProgramTest::start`, to run
+/// the programs of a cluster without a validator.
+///
+/// The bank can't be searched for program accounts. With the blocking
+/// client, the bank must be served by a runtime other than the current
+/// thread, e.g. a multi-threaded one.
+#[derive(Clone)]
+pub struct BanksBackend {
+    banks_client: BanksClient,
+}
+
+impl BanksBackend {
+    pub fn new(banks_client: BanksClient) -> Self {
+        Self { banks_client }
+    }
+}
+
+impl From<BanksClient> for BanksBackend {
+    fn from(banks_client: BanksClient) -> Self {
+        Self::new(banks_client)
+    }
+}
+
+#[async_trait]
+impl Backend for BanksBackend {
+    async fn get_latest_blockhash(&self) -> Result<Hash, ClientError> {
+        self.banks_client
+            .clone()
+            .get_latest_blockhash()
+            .await
+            .map_err(banks_error)
+    }
+
+    async fn send_transaction(&self, tx: &VersionedTransaction) -> Result<Signature, ClientError> {
+        self.banks_client
+            .clone()
+            .process_transaction_with_preflight(tx.clone())
+            .await
+            .map_err(banks_error)?;
+
+        Ok(tx.signatures[0])
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &VersionedTransaction,
+    ) -> Result<Simulation, ClientError> {
+        let simulated = self
+            .banks_client
+            .clone()
+            .simulate_transaction(tx.clone())
+            .await
+            .map_err(banks_error)?;
+        let err = simulated.result.and_then(Result::err);
+        let (logs, units_consumed, return_data) = match simulated.simulation_details {
+            Some(details) => (
+                details.logs,
+                Some(details.units_consumed),
+                details
+                    .return_data
+                    .map(|return_data| (return_data.program_id, return_data.data)),
+            ),
+            None => (Vec::new(), None, None),
+        };
+
+        Ok(Simulation::new(err, logs, units_consumed, return_data))
+    }
+
+    async fn get_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        self.banks_client
+            .clone()
+            .get_account(*address)
+            .await
+            .map_err(banks_error)
+    }
+
+    async fn get_program_accounts(
+        &self,
+        _program_id: &Pubkey,
+        _filters: Vec<RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, Account)>, ClientError> {
+        Err(ClientError::BackendError(
+            "Program accounts can't be searched in a bank".to_string(),
+        ))
+    }
+}
+
+fn banks_error(err: BanksClientError) -> ClientError {
+    match err {
+        BanksClientError::TransactionError(err) => SolanaClientError::from(err).into(),
+        BanksClientError::SimulationError {
+            err,
+            logs,
+            units_consumed,
+            ..
+        } => preflight_failure(err, logs, Some(units_consumed)),
+        err => ClientError::BackendError(err.to_string()),
+    }
+}
@@ -1,12 +1,19 @@
+use crate::compute_budget::ComputeBudget;
 use crate::{
-    ClientError, Config, EventContext, EventUnsubscriber, Program, ProgramAccountsIterator,
-    RequestBuilder,
+    AccountContext, ClientError, Config, EventContext, EventIterator, EventUnsubscriber,
+    HistoryPosition, IdlProgram, MultipleAccounts, PartialTransaction, Program,
+    ProgramAccountsIterator, RequestBuilder, Simulation, TransactionPlanner,
 };
+use anchor_lang::solana_program::instruction::Instruction;
 use anchor_lang::{prelude::Pubkey, AccountDeserialize, Discriminator};
+use serde_json::Value as JsonValue;
 use solana_client::{rpc_config::RpcSendTransactionConfig, rpc_filter::RpcFilterType};
 use solana_sdk::{
-    commitment_config::CommitmentConfig, signature::Signature, signer::Signer,
-    transaction::Transaction,
+    address_lookup_table_account::AddressLookupTableAccount,
+    commitment_config::CommitmentConfig,
+    signature::Signature,
+    signer::Signer,
+    transaction::{Transaction, VersionedTransaction},
 };
 use std::{marker::PhantomData, ops::Deref, sync::Arc};
 use tokio::{
@@ -55,6 +62,73 @@ impl<C: Deref<Target = impl Signer> + Clone> Program<C> {
         self.rt.block_on(self.accounts_lazy_internal(filters))
     }
 
+    /// Returns the accounts of type `T` at `addresses`, `None` for those
+    /// that don't exist, fetched in batches of the `getMultipleAccounts`
+    /// limit. Each account's discriminator is checked.
+    pub fn accounts_multiple<T: AccountDeserialize>(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<T>>, ClientError> {
+        self.rt
+            .block_on(self.accounts_multiple_internal(addresses, None))
+    }
+
+    /// Returns the accounts of type `T` at `addresses` as of
+    /// `min_context_slot` or later, see
+    /// [`accounts_multiple`](Self::accounts_multiple).
+    pub fn accounts_multiple_with_min_context_slot<T: AccountDeserialize>(
+        &self,
+        addresses: &[Pubkey],
+        min_context_slot: u64,
+    ) -> Result<Vec<Option<T>>, ClientError> {
+        self.rt
+            .block_on(self.accounts_multiple_internal(addresses, Some(min_context_slot)))
+    }
+
+    /// Fetches the accounts at `addresses`, of any type, in batches of the
+    /// `getMultipleAccounts` limit. See [`MultipleAccounts`] to decode them.
+    pub fn fetch_many(&self, addresses: &[Pubkey]) -> Result<MultipleAccounts, ClientError> {
+        self.rt.block_on(self.fetch_many_internal(addresses, None))
+    }
+
+    /// Fetches the accounts at `addresses` as of `min_context_slot` or
+    /// later, see [`fetch_many`](Self::fetch_many).
+    pub fn fetch_many_with_min_context_slot(
+        &self,
+        addresses: &[Pubkey],
+        min_context_slot: u64,
+    ) -> Result<MultipleAccounts, ClientError> {
+        self.rt
+            .block_on(self.fetch_many_internal(addresses, Some(min_context_slot)))
+    }
+
+    /// Resolves the accounts of the instruction `name` of `program` that can
+    /// be derived from the given ones and `args`, fetching the accounts it
+    /// needs. See [`IdlProgram::resolve_accounts`].
+    pub fn resolve_accounts(
+        &self,
+        program: &IdlProgram,
+        name: &str,
+        accounts: &JsonValue,
+        args: &JsonValue,
+    ) -> Result<JsonValue, ClientError> {
+        self.rt
+            .block_on(self.resolve_accounts_internal(program, name, accounts, args))
+    }
+
+    /// Builds the instruction `name` of `program`, only requiring the
+    /// accounts that can't be resolved.
+    pub fn idl_instruction(
+        &self,
+        program: &IdlProgram,
+        name: &str,
+        accounts: &JsonValue,
+        args: &JsonValue,
+    ) -> Result<Instruction, ClientError> {
+        self.rt
+            .block_on(self.idl_instruction_internal(program, name, accounts, args))
+    }
+
     pub fn on<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
         &self,
         f: impl Fn(&EventContext, T) + Send + 'static,
@@ -68,6 +142,82 @@ impl<C: Deref<Target = impl Signer> + Clone> Program<C> {
             _lifetime_marker: PhantomData,
         })
     }
+
+    /// Returns the events of type `T` emitted by the program in a
+    /// transaction, logged with `emit!` or emitted with `emit_cpi!`, in the
+    /// order they were emitted. Failed transactions have no events.
+    pub fn transaction_events<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
+        &self,
+        signature: &Signature,
+    ) -> Result<Vec<T>, ClientError> {
+        self.rt
+            .block_on(self.transaction_events_internal(signature))
+    }
+
+    /// Returns the events of type `T` of the successful transactions of the
+    /// program strictly between `after` and `before`, oldest first. Without
+    /// `after` the whole history is walked, without `before` up to the
+    /// latest transaction.
+    ///
+    /// The history is first walked back to `after` a page of signatures at a
+    /// time, keeping the position of each page. Pages, then their
+    /// transactions, are fetched again oldest first as events are consumed.
+    pub fn backfill<T: anchor_lang::Event + anchor_lang::AnchorDeserialize + 'static>(
+        &self,
+        after: Option<HistoryPosition>,
+        before: Option<HistoryPosition>,
+    ) -> Result<EventIterator<T>, ClientError> {
+        let stream = self.rt.block_on(self.backfill_internal(after, before))?;
+
+        Ok(EventIterator {
+            stream,
+            handle: self.rt.handle(),
+        })
+    }
+
+    /// Subscribes to the changes of the account of type `T` at `address`,
+    /// `f` being called with the deserialized account on every change,
+    /// `None` once it's closed, or the error if it doesn't deserialize.
+    ///
+    /// Returns an [`EventUnsubscriber`] to unsubscribe and close connection gracefully.
+    pub fn account_subscribe<T: AccountDeserialize>(
+        &self,
+        address: Pubkey,
+        f: impl Fn(&AccountContext, Result<Option<T>, ClientError>) + Send + 'static,
+    ) -> Result<EventUnsubscriber, ClientError> {
+        let (handle, rx) = self
+            .rt
+            .block_on(self.account_subscribe_internal(address, f))?;
+
+        Ok(EventUnsubscriber {
+            handle,
+            rx,
+            runtime_handle: self.rt.handle(),
+            _lifetime_marker: PhantomData,
+        })
+    }
+
+    /// Subscribes to the changes of all accounts of type `T` of the program
+    /// matching `filters`, selected by their discriminator. `f` is called
+    /// with the account on every change, as for `account_subscribe`.
+    ///
+    /// Returns an [`EventUnsubscriber`] to unsubscribe and close connection gracefully.
+    pub fn program_subscribe<T: AccountDeserialize + Discriminator>(
+        &self,
+        filters: Vec<RpcFilterType>,
+        f: impl Fn(&AccountContext, Result<Option<T>, ClientError>) + Send + 'static,
+    ) -> Result<EventUnsubscriber, ClientError> {
+        let (handle, rx) = self
+            .rt
+            .block_on(self.program_subscribe_internal(filters, f))?;
+
+        Ok(EventUnsubscriber {
+            handle,
+            rx,
+            runtime_handle: self.rt.handle(),
+            _lifetime_marker: PhantomData,
+        })
+    }
 }
 
 impl<'a, C: Deref<Target = impl Signer> + Clone> RequestBuilder<'a, C> {
@@ -87,6 +237,13 @@ impl<'a, C: Deref<Target = impl Signer> + Clone> RequestBuilder<'a, C> {
             instructions: Vec::new(),
             instruction_data: None,
             signers: Vec::new(),
+            lookup_tables: Vec::new(),
+            compute_budget: ComputeBudget::default(),
+            auto_compute_budget: None,
+            nonce: None,
+            retry: None,
+            program_errors: None,
+            backend: None,
             handle,
         }
     }
@@ -99,6 +256,64 @@ impl<'a, C: Deref<Target = impl Signer> + Clone> RequestBuilder<'a, C> {
         self.handle.block_on(self.send_internal())
     }
 
+    /// Simulates the request, returning its logs parsed into invocations,
+    /// along with the error, events and return data they hold.
+    pub fn simulate(&self) -> Result<Simulation, ClientError> {
+        self.handle.block_on(self.simulate_internal())
+    }
+
+    /// Signs a v0 transaction of the request, compiled with the lookup tables
+    /// of the request.
+    pub fn signed_versioned_transaction(&self) -> Result<VersionedTransaction, ClientError> {
+        self.handle
+            .block_on(self.signed_versioned_transaction_internal())
+    }
+
+    /// Sends the request as a v0 transaction, see
+    /// [`signed_versioned_transaction`](Self::signed_versioned_transaction).
+    pub fn send_versioned(&self) -> Result<Signature, ClientError> {
+        self.handle.block_on(self.send_versioned_internal())
+    }
+
+    /// Exports a transaction of the request to sign offline, signed by the
+    /// payer and signers whose keys are available. Signers without their
+    /// key, e.g. `NullSigner`, are left for [`PartialTransaction::sign`].
+    /// A legacy transaction, or a v0 one with lookup tables.
+    pub fn partial_transaction(&self) -> Result<PartialTransaction, ClientError> {
+        self.handle.block_on(self.partial_transaction_internal())
+    }
+
+    /// Sends a transaction exported with
+    /// [`partial_transaction`](Self::partial_transaction), once all its
+    /// signatures are added and verified.
+    pub fn send_partial_transaction(
+        &self,
+        partial: &PartialTransaction,
+    ) -> Result<Signature, ClientError> {
+        self.handle
+            .block_on(self.send_partial_transaction_internal(partial))
+    }
+
+    /// Creates an address lookup table holding the
+    /// [`lookup_table_addresses`](Self::lookup_table_addresses) of the
+    /// request, with the payer as authority. The table can be used from the
+    /// slot after its creation.
+    pub fn create_lookup_table(&self) -> Result<AddressLookupTableAccount, ClientError> {
+        self.handle.block_on(self.create_lookup_table_internal())
+    }
+
+    /// Sends the request until `account` has grown to `target_len` bytes,
+    /// for instructions reallocating it with `realloc::incremental`.
+    /// Returns the signatures of all sent transactions.
+    pub fn send_realloc_incremental(
+        &self,
+        account: Pubkey,
+        target_len: usize,
+    ) -> Result<Vec<Signature>, ClientError> {
+        self.handle
+            .block_on(self.send_realloc_incremental_internal(account, target_len))
+    }
+
     pub fn send_with_spinner_and_config(
         &self,
         config: RpcSendTransactionConfig,
@@ -107,3 +322,18 @@ impl<'a, C: Deref<Target = impl Signer> + Clone> RequestBuilder<'a, C> {
             .block_on(self.send_with_spinner_and_config_internal(config))
     }
 }
+
+impl<'a, C: Deref<Target = impl Signer> + Clone> TransactionPlanner<'a, C> {
+    /// Sends the planned transactions one after the other, returning the
+    /// result of each sent transaction. Stops at the first failure, later
+    /// transactions being left unsent.
+    pub fn send(&self) -> Result<Vec<Result<Signature, ClientError>>, ClientError> {
+        self.handle.block_on(self.send_internal())
+    }
+
+    /// Sends all planned transactions at once, returning the result of
+    /// each. Only for groups independent of each other.
+    pub fn send_parallel(&self) -> Result<Vec<Result<Signature, ClientError>>, ClientError> {
+        self.handle.block_on(self.send_parallel_internal())
+    }
+}
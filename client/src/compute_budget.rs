@@ -0,0 +1,97 @@
+//! Compute budget of requests, see `RequestBuilder::compute_unit_limit`,
+//! `RequestBuilder::compute_unit_price` and
+//! `RequestBuilder::auto_compute_budget`.
+
+use anchor_lang::solana_program::instruction::Instruction;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+
+/// The maximum compute unit limit of a transaction.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// The compute units an instruction may consume without a compute unit
+/// limit.
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+/// Estimates the compute budget of a request before sending it.
+///
+/// The compute unit limit is the units consumed by a simulation of the
+/// request, plus a margin. The compute unit price is a percentile of the
+/// prioritization fees recently paid to write the accounts the request
+/// writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoComputeBudget {
+    /// The margin added to the simulated compute units, in percent.
+    pub unit_margin_percent: u32,
+    /// The percentile of the recent prioritization fees, from 0 to 100.
+    pub fee_percentile: u8,
+}
+
+impl Default for AutoComputeBudget {
+    fn default() -> Self {
+        Self {
+            unit_margin_percent: 10,
+            fee_percentile: 50,
+        }
+    }
+}
+
+impl AutoComputeBudget {
+    /// Returns the compute unit limit for `units_consumed` simulated units.
+    pub fn unit_limit(&self, units_consumed: u64) -> u32 {
+        let units = units_consumed * (100 + u64::from(self.unit_margin_percent)) / 100;
+        units.min(u64::from(MAX_COMPUTE_UNIT_LIMIT)) as u32
+    }
+
+    /// Returns the compute unit price, in micro-lamports, for the recent
+    /// prioritization `fees`.
+    pub fn unit_price(&self, fees: &[u64]) -> u64 {
+        if fees.is_empty() {
+            return 0;
+        }
+        let mut fees = fees.to_vec();
+        fees.sort_unstable();
+        let percentile = usize::from(self.fee_percentile.min(100));
+        fees[(fees.len() - 1) * percentile / 100]
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ComputeBudget {
+    pub unit_limit: Option<u32>,
+    pub unit_price: Option<u64>,
+}
+
+impl ComputeBudget {
+    pub fn instructions(&self) -> Vec<Instruction> {
+        let mut instructions = Vec::new();
+        if let Some(units) = self.unit_limit {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(units));
+        }
+        if let Some(micro_lamports) = self.unit_price {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
+                micro_lamports,
+            ));
+        }
+        instructions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_compute_budget() {
+        let auto = AutoComputeBudget::default();
+        assert_eq!(auto.unit_limit(10_000), 11_000);
+        assert_eq!(auto.unit_limit(1_300_000), MAX_COMPUTE_UNIT_LIMIT);
+
+        assert_eq!(auto.unit_price(&[]), 0);
+        assert_eq!(auto.unit_price(&[300, 100, 200]), 200);
+        let auto = AutoComputeBudget {
+            fee_percentile: 100,
+            ..auto
+        };
+        assert_eq!(auto.unit_price(&[300, 100, 200]), 300);
+    }
+}
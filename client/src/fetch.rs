@@ -0,0 +1,144 @@
+//! Batched fetches of accounts of any type, see `MultipleAccounts`.
+
+use crate::ClientError;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::AccountDeserialize;
+use solana_sdk::account::Account;
+
+/// Accounts fetched together by `Program::fetch_many`, in the order of
+/// their addresses, decoded on access.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultipleAccounts {
+    addresses: Vec<Pubkey>,
+    accounts: Vec<Option<Account>>,
+}
+
+impl MultipleAccounts {
+    pub(crate) fn new(addresses: Vec<Pubkey>, accounts: Vec<Option<Account>>) -> Self {
+        Self {
+            addresses,
+            accounts,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    pub fn addresses(&self) -> &[Pubkey] {
+        &self.addresses
+    }
+
+    /// Returns the raw account at `index`, `None` if it doesn't exist.
+    pub fn raw(&self, index: usize) -> Option<&Account> {
+        self.accounts.get(index).and_then(Option::as_ref)
+    }
+
+    /// Decodes the account at `index` as a `T`, its discriminator checked.
+    /// Returns `None` if it doesn't exist.
+    pub fn get<T: AccountDeserialize>(&self, index: usize) -> Result<Option<T>, ClientError> {
+        self.raw(index).map(decode).transpose()
+    }
+
+    /// Decodes the account at `address` as a `T`, see [`get`](Self::get).
+    pub fn find<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<Option<T>, ClientError> {
+        match self.addresses.iter().position(|a| a == address) {
+            Some(index) => self.get(index),
+            None => Ok(None),
+        }
+    }
+
+    /// Decodes all accounts as `T`s.
+    pub fn decode_all<T: AccountDeserialize>(&self) -> Result<Vec<Option<T>>, ClientError> {
+        self.accounts
+            .iter()
+            .map(|account| account.as_ref().map(decode).transpose())
+            .collect()
+    }
+}
+
+fn decode<T: AccountDeserialize>(account: &Account) -> Result<T, ClientError> {
+    T::try_deserialize(&mut (&account.data as &[u8])).map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Client, Cluster, MockBackend};
+    use anchor_lang::prelude::*;
+    use solana_sdk::account::Account;
+    use solana_sdk::signature::Keypair;
+    use std::sync::Arc;
+
+    // Namespaced, the crate having no program id to own the accounts.
+    #[account("vault")]
+    #[derive(Debug, PartialEq, Eq)]
+    struct Vault {
+        amount: u64,
+    }
+
+    #[account("pool")]
+    #[derive(Debug, PartialEq, Eq)]
+    struct Pool {
+        vault: Pubkey,
+    }
+
+    fn account<T: AccountSerialize>(owner: Pubkey, value: &T) -> Account {
+        let mut data = Vec::new();
+        value.try_serialize(&mut data).unwrap();
+        Account {
+            lamports: 1,
+            data,
+            owner,
+            ..Account::default()
+        }
+    }
+
+    #[test]
+    fn fetch_many() {
+        let program_id = Pubkey::new_unique();
+        let backend = Arc::new(MockBackend::new());
+        // More vaults than a single `getMultipleAccounts` call returns, every
+        // third one missing.
+        let vaults: Vec<Pubkey> = (0..150).map(|_| Pubkey::new_unique()).collect();
+        for (amount, address) in vaults.iter().enumerate() {
+            if amount % 3 != 0 {
+                let vault = Vault {
+                    amount: amount as u64,
+                };
+                backend.set_account(*address, account(program_id, &vault));
+            }
+        }
+        let pool = Pubkey::new_unique();
+        backend.set_account(pool, account(program_id, &Pool { vault: vaults[1] }));
+
+        let client = Client::new(Cluster::Localnet, Arc::new(Keypair::new())).with_backend(backend);
+        let program = client.program(program_id).unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+
+        let fetched: Vec<Option<Vault>> = rt
+            .block_on(program.accounts_multiple_internal(&vaults, None))
+            .unwrap();
+        assert_eq!(fetched.len(), 150);
+        assert_eq!(fetched[0], None);
+        assert_eq!(fetched[149], Some(Vault { amount: 149 }));
+
+        let many = rt
+            .block_on(program.fetch_many_internal(&[pool, vaults[1], vaults[0]], Some(1)))
+            .unwrap();
+        assert_eq!(
+            many.get::<Pool>(0).unwrap(),
+            Some(Pool { vault: vaults[1] })
+        );
+        assert_eq!(
+            many.find::<Vault>(&vaults[1]).unwrap(),
+            Some(Vault { amount: 1 })
+        );
+        assert_eq!(many.get::<Vault>(2).unwrap(), None);
+        assert!(many.get::<Vault>(0).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+//! Typed filters of program accounts, see `Filter`.
+
+use anchor_lang::__private::bytemuck::{self, Pod};
+use anchor_lang::{AnchorSerialize, FieldOffset, Space, ZeroCopy};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use std::marker::PhantomData;
+
+/// Filters of the accounts of type `T`, built from the field offsets
+/// generated by `#[account]`, e.g.
+/// `Filter::<Vault>::eq(VaultOffsets::authority, key)`.
+///
+/// Converts into the filters taken by `Program::accounts`.
+pub struct Filter<T> {
+    filters: Vec<RpcFilterType>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Filter<T> {
+    /// Matches the accounts whose `field` is equal to `value`, Borsh
+    /// serialized.
+    pub fn eq<V: AnchorSerialize>(field: FieldOffset<T, V>, value: V) -> Self {
+        // Fields at a fixed offset are of fixed size types, whose
+        // serialization into a vector can't fail.
+        let bytes = value.try_to_vec().unwrap();
+        Self::bytes(field, &bytes)
+    }
+
+    /// Matches the accounts whose `field` is equal to `value`, in its zero
+    /// copy representation, e.g. for fields of `#[zero_copy]` types.
+    pub fn eq_pod<V: Pod>(field: FieldOffset<T, V>, value: V) -> Self {
+        Self::bytes(field, bytemuck::bytes_of(&value))
+    }
+
+    /// Matches the accounts whose `field` starts with `bytes`.
+    pub fn bytes<V>(field: FieldOffset<T, V>, bytes: &[u8]) -> Self {
+        Self::from(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            field.offset,
+            bytes,
+        )))
+    }
+
+    /// Matches the accounts of `len` bytes of data, discriminator included.
+    pub fn data_size(len: u64) -> Self {
+        Self::from(RpcFilterType::DataSize(len))
+    }
+
+    /// Adds the filters of `other`, matching the accounts matched by both.
+    #[must_use]
+    pub fn and(mut self, mut other: Filter<T>) -> Self {
+        self.filters.append(&mut other.filters);
+        self
+    }
+
+    pub fn filters(&self) -> &[RpcFilterType] {
+        &self.filters
+    }
+}
+
+impl<T: Space> Filter<T> {
+    /// Matches the accounts allocated with `8 + T::INIT_SPACE` bytes.
+    pub fn init_space() -> Self {
+        Self::data_size(8 + T::INIT_SPACE as u64)
+    }
+}
+
+impl<T: ZeroCopy> Filter<T> {
+    /// Matches the zero copy accounts of the exact size of `T`.
+    pub fn zero_copy_size() -> Self {
+        Self::data_size(8 + std::mem::size_of::<T>() as u64)
+    }
+}
+
+impl<T> From<RpcFilterType> for Filter<T> {
+    fn from(filter: RpcFilterType) -> Self {
+        Self {
+            filters: vec![filter],
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> From<Filter<T>> for Vec<RpcFilterType> {
+    fn from(filter: Filter<T>) -> Self {
+        filter.filters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockBackend;
+    use crate::{Backend, Client, Cluster};
+    use anchor_lang::prelude::*;
+    use solana_sdk::account::Account;
+    use solana_sdk::signature::Keypair;
+    use std::sync::Arc;
+
+    // Namespaced, the crate having no program id to own the account.
+    #[account("vault")]
+    #[derive(InitSpace)]
+    struct Vault {
+        bump: u8,
+        authority: Pubkey,
+        amount: u64,
+    }
+
+    #[test]
+    fn filter_accounts() {
+        let program_id = Pubkey::new_unique();
+        let backend = Arc::new(MockBackend::new());
+        let authority = Pubkey::new_unique();
+        let mut vaults = Vec::new();
+        for (bump, authority) in [(1, authority), (2, Pubkey::new_unique()), (3, authority)] {
+            let mut data = Vec::new();
+            Vault {
+                bump,
+                authority,
+                amount: 10,
+            }
+            .try_serialize(&mut data)
+            .unwrap();
+            let address = Pubkey::new_unique();
+            backend.set_account(
+                address,
+                Account {
+                    lamports: 1,
+                    data,
+                    owner: program_id,
+                    ..Account::default()
+                },
+            );
+            vaults.push(address);
+        }
+
+        let filter = Filter::<Vault>::eq(VaultOffsets::authority, authority)
+            .and(Filter::eq(VaultOffsets::bump, 3u8))
+            .and(Filter::init_space());
+        assert_eq!(filter.filters().len(), 3);
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let matched = rt
+            .block_on(backend.get_program_accounts(&program_id, filter.into()))
+            .unwrap();
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].0, vaults[2]);
+
+        let client =
+            Client::new(Cluster::Localnet, Arc::new(Keypair::new())).with_backend(backend.clone());
+        let program = client.program(program_id).unwrap();
+        let matched: Vec<(Pubkey, Vault)> = rt
+            .block_on(
+                program
+                    .accounts_lazy_internal(Filter::eq(VaultOffsets::authority, authority).into()),
+            )
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        assert_eq!(matched.len(), 2);
+        assert!(matched
+            .iter()
+            .all(|(_, vault)| vault.authority == authority));
+    }
+}
@@ -0,0 +1,1244 @@
+//! Dynamic client of a program known only through its IDL, encoding
+//! instructions from JSON and decoding accounts, events and return data back
+//! to JSON.
+//!
+//! Values are represented as follows:
+//!
+//! - integers are numbers, or strings when they don't fit in a JSON number.
+//!   `u128` and `i128` are always decoded as strings
+//! - public keys are base 58 strings and `bytes` are arrays of numbers
+//! - `Option`s are `null` when absent
+//! - structs are objects keyed by field name
+//! - enums are objects with the variant name as their single key, holding an
+//!   object of named fields, an array of tuple fields, or `{}`. Unit variants
+//!   can also be encoded from the variant name alone
+
+use crate::ClientError;
+use anchor_lang::prelude::borsh::BorshSerialize;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::AnchorDeserialize;
+use heck::{MixedCase, SnakeCase};
+use serde_json::{json, Map, Value as JsonValue};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use types::{
+    EnumFields, Idl, IdlAccount, IdlAccountItem, IdlDefinedTypeArg, IdlInstruction, IdlSeed,
+    IdlType, IdlTypeDefinition, IdlTypeDefinitionTy,
+};
+
+pub use anchor_syn::idl::types;
+
+/// `IdlProgram` encodes and decodes the instructions, accounts, events and
+/// return data of a program from its IDL, for programs without a Rust crate
+/// to depend on.
+#[derive(Debug, Clone)]
+pub struct IdlProgram {
+    idl: Idl,
+    program_id: Pubkey,
+}
+
+// Value of a generic parameter of a defined type.
+#[derive(Clone)]
+enum GenericArg {
+    Type(IdlType),
+    Len(usize),
+}
+
+type Generics = BTreeMap<String, GenericArg>;
+
+/// Data of the accounts available to [`IdlProgram::resolve_accounts`], `None`
+/// for accounts that don't exist.
+pub type FetchedAccounts = BTreeMap<Pubkey, Option<Vec<u8>>>;
+
+// Programs and sysvars resolved from the name of their account. The token
+// program is left to the caller, as mints may belong to Token or Token-2022.
+const WELL_KNOWN_ACCOUNTS: &[(&str, Pubkey)] = &[
+    ("systemProgram", system_program::ID),
+    (
+        "associatedTokenProgram",
+        solana_sdk::pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL"),
+    ),
+    ("rent", sysvar::rent::ID),
+    ("clock", sysvar::clock::ID),
+];
+
+impl IdlProgram {
+    pub fn new(idl: Idl, program_id: Pubkey) -> Self {
+        Self { idl, program_id }
+    }
+
+    /// Creates an `IdlProgram` with the program id in the `metadata.address`
+    /// of the IDL.
+    pub fn from_idl(idl: Idl) -> Result<Self, ClientError> {
+        let program_id = idl
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("address"))
+            .and_then(|address| address.as_str())
+            .ok_or_else(|| error("The IDL has no `metadata.address`"))?;
+        let program_id = parse_pubkey(program_id)?;
+        Ok(Self::new(idl, program_id))
+    }
+
+    pub fn idl(&self) -> &Idl {
+        &self.idl
+    }
+
+    pub fn id(&self) -> Pubkey {
+        self.program_id
+    }
+
+    /// Builds the instruction `name` of the IDL. `accounts` is an object
+    /// keyed by account name, nesting an object for each composite account.
+    /// Optional accounts can be `null` or left out. `args` is either an
+    /// object keyed by argument name or an array of the arguments in order.
+    pub fn instruction(
+        &self,
+        name: &str,
+        accounts: &JsonValue,
+        args: &JsonValue,
+    ) -> Result<Instruction, ClientError> {
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts: self.account_metas(name, accounts)?,
+            data: self.instruction_data(name, args)?,
+        })
+    }
+
+    /// Encodes the data of the instruction `name`, discriminator included.
+    pub fn instruction_data(&self, name: &str, args: &JsonValue) -> Result<Vec<u8>, ClientError> {
+        let ix = self.idl_instruction(name)?;
+        let mut data = instruction_discriminator(&ix.name).to_vec();
+        for (i, arg) in ix.args.iter().enumerate() {
+            let value = match args {
+                JsonValue::Object(args) => args.get(&arg.name),
+                JsonValue::Array(args) => args.get(i),
+                _ => None,
+            }
+            .ok_or_else(|| error(format!("Missing argument `{}` of `{name}`", arg.name)))?;
+            self.encode(&arg.ty, value, &Generics::new(), &mut data)?;
+        }
+        Ok(data)
+    }
+
+    /// Builds the account metas of the instruction `name`, see
+    /// [`instruction`](Self::instruction) for the format of `accounts`.
+    pub fn account_metas(
+        &self,
+        name: &str,
+        accounts: &JsonValue,
+    ) -> Result<Vec<AccountMeta>, ClientError> {
+        let ix = self.idl_instruction(name)?;
+        let mut metas = Vec::new();
+        self.collect_account_metas(&ix.accounts, accounts, &mut metas)?;
+        Ok(metas)
+    }
+
+    /// Decodes the data of an instruction of the program, returning the name
+    /// of the instruction and its arguments keyed by name.
+    pub fn decode_instruction(&self, data: &[u8]) -> Result<(String, JsonValue), ClientError> {
+        let (disc, mut data) = split_discriminator(data)?;
+        let ix = self
+            .idl
+            .instructions
+            .iter()
+            .find(|ix| instruction_discriminator(&ix.name) == disc)
+            .ok_or_else(|| error("Unknown instruction discriminator"))?;
+        let mut args = Map::new();
+        for arg in &ix.args {
+            let value = self.decode(&arg.ty, &mut data, &Generics::new())?;
+            args.insert(arg.name.clone(), value);
+        }
+        Ok((ix.name.clone(), JsonValue::Object(args)))
+    }
+
+    /// Decodes the data of the account type `name`, checking its
+    /// discriminator.
+    pub fn decode_account(&self, name: &str, data: &[u8]) -> Result<JsonValue, ClientError> {
+        let account = self
+            .idl
+            .accounts
+            .iter()
+            .find(|account| account.name == name)
+            .ok_or_else(|| error(format!("Account `{name}` not found in the IDL")))?;
+        let (disc, mut data) = split_discriminator(data)?;
+        if disc != discriminator("account", name) {
+            return Err(anchor_lang::error::Error::from(
+                anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch,
+            )
+            .into());
+        }
+        self.decode_definition(account, &[], &mut data, &Generics::new())
+    }
+
+    /// Decodes an event, as found base 64 encoded in the `Program data: `
+    /// logs, returning its name and fields.
+    pub fn decode_event(&self, data: &[u8]) -> Result<(String, JsonValue), ClientError> {
+        let (disc, mut data) = split_discriminator(data)?;
+        let event = self
+            .idl
+            .events
+            .iter()
+            .flatten()
+            .find(|event| discriminator("event", &event.name) == disc)
+            .ok_or_else(|| error("Unknown event discriminator"))?;
+        let mut fields = Map::new();
+        for field in &event.fields {
+            let value = self.decode(&field.ty, &mut data, &Generics::new())?;
+            fields.insert(field.name.clone(), value);
+        }
+        Ok((event.name.clone(), JsonValue::Object(fields)))
+    }
+
+    /// Decodes the data returned by the instruction `name`.
+    pub fn decode_return_data(
+        &self,
+        name: &str,
+        mut data: &[u8],
+    ) -> Result<JsonValue, ClientError> {
+        let returns = self
+            .idl_instruction(name)?
+            .returns
+            .as_ref()
+            .ok_or_else(|| error(format!("Instruction `{name}` returns nothing")))?;
+        self.decode(returns, &mut data, &Generics::new())
+    }
+
+    /// Borsh encodes a JSON value of the given type.
+    pub fn encode_type(&self, ty: &IdlType, value: &JsonValue) -> Result<Vec<u8>, ClientError> {
+        let mut data = Vec::new();
+        self.encode(ty, value, &Generics::new(), &mut data)?;
+        Ok(data)
+    }
+
+    /// Decodes a value of the given type, advancing `data` past it.
+    pub fn decode_type(&self, ty: &IdlType, data: &mut &[u8]) -> Result<JsonValue, ClientError> {
+        self.decode(ty, data, &Generics::new())
+    }
+
+    /// Fills in the accounts of the instruction `name` that can be derived
+    /// from the others and from `args`, see [`instruction`](Self::instruction)
+    /// for their format:
+    ///
+    /// - PDAs, from seeds referencing constants, arguments, other accounts
+    ///   and fields of their data
+    /// - `has_one` relations, from the data of the account holding them
+    /// - well known programs and sysvars, e.g. `systemProgram` and `rent`.
+    ///   `tokenProgram` is not resolved, since it depends on the mint
+    /// - the `eventAuthority` and `program` accounts of `emit_cpi!`
+    ///
+    /// Accounts already set are left untouched. As resolving some accounts
+    /// requires the data of others, this returns the addresses missing from
+    /// `fetched` whose data could resolve more accounts. Callers fetch them
+    /// and resolve again until nothing is returned.
+    pub fn resolve_accounts(
+        &self,
+        name: &str,
+        accounts: &mut JsonValue,
+        args: &JsonValue,
+        fetched: &FetchedAccounts,
+    ) -> Result<Vec<Pubkey>, ClientError> {
+        let ix = self.idl_instruction(name)?;
+        if accounts.is_null() {
+            *accounts = json!({});
+        }
+        let accounts = accounts
+            .as_object_mut()
+            .ok_or_else(|| error("Expected an object of accounts"))?;
+        let mut resolver = AccountsResolver {
+            program: self,
+            ix,
+            args,
+            fetched,
+            needed: Vec::new(),
+        };
+        while resolver.resolve(&ix.accounts, accounts)? {}
+
+        let mut needed = resolver.needed;
+        needed.sort();
+        needed.dedup();
+        Ok(needed)
+    }
+
+    fn idl_instruction(&self, name: &str) -> Result<&IdlInstruction, ClientError> {
+        self.idl
+            .instructions
+            .iter()
+            .find(|ix| ix.name == name)
+            .ok_or_else(|| error(format!("Instruction `{name}` not found in the IDL")))
+    }
+
+    fn collect_account_metas(
+        &self,
+        items: &[IdlAccountItem],
+        accounts: &JsonValue,
+        metas: &mut Vec<AccountMeta>,
+    ) -> Result<(), ClientError> {
+        for item in items {
+            match item {
+                IdlAccountItem::IdlAccounts(nested) => {
+                    let accounts = accounts
+                        .get(&nested.name)
+                        .ok_or_else(|| error(format!("Missing accounts `{}`", nested.name)))?;
+                    self.collect_account_metas(&nested.accounts, accounts, metas)?;
+                }
+                IdlAccountItem::IdlAccount(account) => {
+                    let pubkey = match accounts.get(&account.name) {
+                        Some(JsonValue::String(pubkey)) => parse_pubkey(pubkey)?,
+                        None | Some(JsonValue::Null) if account.is_optional == Some(true) => {
+                            // Absent optional accounts are passed as the program id.
+                            metas.push(AccountMeta::new_readonly(self.program_id, false));
+                            continue;
+                        }
+                        _ => return Err(error(format!("Missing account `{}`", account.name))),
+                    };
+                    metas.push(match account.is_mut {
+                        true => AccountMeta::new(pubkey, account.is_signer),
+                        false => AccountMeta::new_readonly(pubkey, account.is_signer),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn type_definition(&self, name: &str) -> Result<&IdlTypeDefinition, ClientError> {
+        self.idl
+            .types
+            .iter()
+            .chain(self.idl.accounts.iter())
+            .find(|def| def.name == name)
+            .ok_or_else(|| error(format!("Type `{name}` not found in the IDL")))
+    }
+
+    // Binds the generic parameters of the definition `name` to `args`.
+    fn definition_generics(
+        &self,
+        name: &str,
+        args: &[IdlDefinedTypeArg],
+        generics: &Generics,
+    ) -> Result<(&IdlTypeDefinition, Generics), ClientError> {
+        let def = self.type_definition(name)?;
+        let params = def.generics.as_deref().unwrap_or_default();
+        if params.len() != args.len() {
+            return Err(error(format!(
+                "Type `{name}` takes {} generic arguments, found {}",
+                params.len(),
+                args.len()
+            )));
+        }
+        let mut bound = Generics::new();
+        for (param, arg) in params.iter().zip(args) {
+            let arg = match arg {
+                IdlDefinedTypeArg::Generic(generic) => generic_arg(generics, generic)?.clone(),
+                IdlDefinedTypeArg::Value(value) => GenericArg::Len(
+                    value
+                        .parse()
+                        .map_err(|_| error(format!("Invalid generic length `{value}`")))?,
+                ),
+                IdlDefinedTypeArg::Type(ty) => GenericArg::Type(substitute(ty, generics)?),
+            };
+            bound.insert(param.clone(), arg);
+        }
+        Ok((def, bound))
+    }
+
+    fn encode(
+        &self,
+        ty: &IdlType,
+        value: &JsonValue,
+        generics: &Generics,
+        data: &mut Vec<u8>,
+    ) -> Result<(), ClientError> {
+        match ty {
+            IdlType::Bool => value
+                .as_bool()
+                .ok_or_else(|| mismatch(ty, value))?
+                .serialize(data)?,
+            IdlType::U8 => parse_int::<u8>(value)?.serialize(data)?,
+            IdlType::I8 => parse_int::<i8>(value)?.serialize(data)?,
+            IdlType::U16 => parse_int::<u16>(value)?.serialize(data)?,
+            IdlType::I16 => parse_int::<i16>(value)?.serialize(data)?,
+            IdlType::U32 => parse_int::<u32>(value)?.serialize(data)?,
+            IdlType::I32 => parse_int::<i32>(value)?.serialize(data)?,
+            IdlType::U64 => parse_int::<u64>(value)?.serialize(data)?,
+            IdlType::I64 => parse_int::<i64>(value)?.serialize(data)?,
+            IdlType::U128 => parse_int::<u128>(value)?.serialize(data)?,
+            IdlType::I128 => parse_int::<i128>(value)?.serialize(data)?,
+            IdlType::F32 => {
+                (value.as_f64().ok_or_else(|| mismatch(ty, value))? as f32).serialize(data)?
+            }
+            IdlType::F64 => value
+                .as_f64()
+                .ok_or_else(|| mismatch(ty, value))?
+                .serialize(data)?,
+            IdlType::U256 | IdlType::I256 => return Err(unsupported(ty)),
+            IdlType::Bytes => {
+                let bytes = value
+                    .as_array()
+                    .ok_or_else(|| mismatch(ty, value))?
+                    .iter()
+                    .map(parse_int::<u8>)
+                    .collect::<Result<Vec<u8>, _>>()?;
+                bytes.serialize(data)?
+            }
+            IdlType::String => value
+                .as_str()
+                .ok_or_else(|| mismatch(ty, value))?
+                .serialize(data)?,
+            IdlType::PublicKey => {
+                let pubkey = value.as_str().ok_or_else(|| mismatch(ty, value))?;
+                parse_pubkey(pubkey)?.serialize(data)?
+            }
+            IdlType::Option(inner) => match value {
+                JsonValue::Null => data.push(0),
+                value => {
+                    data.push(1);
+                    self.encode(inner, value, generics, data)?;
+                }
+            },
+            IdlType::Vec(inner) => {
+                let values = value.as_array().ok_or_else(|| mismatch(ty, value))?;
+                (values.len() as u32).serialize(data)?;
+                for value in values {
+                    self.encode(inner, value, generics, data)?;
+                }
+            }
+            IdlType::Array(inner, len) => self.encode_array(inner, *len, value, generics, data)?,
+            IdlType::GenericLenArray(inner, generic) => match generic_arg(generics, generic)? {
+                GenericArg::Len(len) => self.encode_array(inner, *len, value, generics, data)?,
+                GenericArg::Type(_) => return Err(error(format!("`{generic}` is not a length"))),
+            },
+            IdlType::Generic(generic) => match generic_arg(generics, generic)? {
+                GenericArg::Type(ty) => self.encode(ty, value, generics, data)?,
+                GenericArg::Len(_) => return Err(error(format!("`{generic}` is not a type"))),
+            },
+            IdlType::Defined(name) => {
+                let (def, generics) = self.definition_generics(name, &[], generics)?;
+                self.encode_definition(def, value, &generics, data)?
+            }
+            IdlType::DefinedWithTypeArgs { name, args } => {
+                let (def, generics) = self.definition_generics(name, args, generics)?;
+                self.encode_definition(def, value, &generics, data)?
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_array(
+        &self,
+        ty: &IdlType,
+        len: usize,
+        value: &JsonValue,
+        generics: &Generics,
+        data: &mut Vec<u8>,
+    ) -> Result<(), ClientError> {
+        let values = value
+            .as_array()
+            .filter(|values| values.len() == len)
+            .ok_or_else(|| {
+                error(format!(
+                    "Expected an array of {len} elements, found {value}"
+                ))
+            })?;
+        for value in values {
+            self.encode(ty, value, generics, data)?;
+        }
+        Ok(())
+    }
+
+    fn encode_definition(
+        &self,
+        def: &IdlTypeDefinition,
+        value: &JsonValue,
+        generics: &Generics,
+        data: &mut Vec<u8>,
+    ) -> Result<(), ClientError> {
+        match &def.ty {
+            IdlTypeDefinitionTy::Struct { fields } => {
+                for field in fields {
+                    let value = value.get(&field.name).ok_or_else(|| {
+                        error(format!("Missing field `{}` of `{}`", field.name, def.name))
+                    })?;
+                    self.encode(&field.ty, value, generics, data)?;
+                }
+            }
+            IdlTypeDefinitionTy::Enum { variants } => {
+                let (name, fields) = match value {
+                    JsonValue::String(name) => (name, &JsonValue::Null),
+                    JsonValue::Object(object) if object.len() == 1 => object.iter().next().unwrap(),
+                    _ => return Err(error(format!("Expected a `{}` variant", def.name))),
+                };
+                let index = variants
+                    .iter()
+                    .position(|variant| &variant.name == name)
+                    .ok_or_else(|| error(format!("Unknown variant `{name}` of `{}`", def.name)))?;
+                data.push(index as u8);
+                match &variants[index].fields {
+                    None => {}
+                    Some(EnumFields::Named(named)) => {
+                        for field in named {
+                            let value = fields.get(&field.name).ok_or_else(|| {
+                                error(format!("Missing field `{}` of `{name}`", field.name))
+                            })?;
+                            self.encode(&field.ty, value, generics, data)?;
+                        }
+                    }
+                    Some(EnumFields::Tuple(tuple)) => {
+                        let values = fields
+                            .as_array()
+                            .filter(|values| values.len() == tuple.len())
+                            .ok_or_else(|| {
+                                error(format!("Expected {} fields for `{name}`", tuple.len()))
+                            })?;
+                        for (ty, value) in tuple.iter().zip(values) {
+                            self.encode(ty, value, generics, data)?;
+                        }
+                    }
+                }
+            }
+            IdlTypeDefinitionTy::Alias { value: ty } => self.encode(ty, value, generics, data)?,
+        }
+        Ok(())
+    }
+
+    fn decode(
+        &self,
+        ty: &IdlType,
+        data: &mut &[u8],
+        generics: &Generics,
+    ) -> Result<JsonValue, ClientError> {
+        Ok(match ty {
+            IdlType::Bool => json!(bool::deserialize(data)?),
+            IdlType::U8 => json!(u8::deserialize(data)?),
+            IdlType::I8 => json!(i8::deserialize(data)?),
+            IdlType::U16 => json!(u16::deserialize(data)?),
+            IdlType::I16 => json!(i16::deserialize(data)?),
+            IdlType::U32 => json!(u32::deserialize(data)?),
+            IdlType::I32 => json!(i32::deserialize(data)?),
+            IdlType::U64 => json!(u64::deserialize(data)?),
+            IdlType::I64 => json!(i64::deserialize(data)?),
+            IdlType::U128 => json!(u128::deserialize(data)?.to_string()),
+            IdlType::I128 => json!(i128::deserialize(data)?.to_string()),
+            IdlType::F32 => json!(f32::deserialize(data)?),
+            IdlType::F64 => json!(f64::deserialize(data)?),
+            IdlType::U256 | IdlType::I256 => return Err(unsupported(ty)),
+            IdlType::Bytes => json!(Vec::<u8>::deserialize(data)?),
+            IdlType::String => json!(String::deserialize(data)?),
+            IdlType::PublicKey => json!(Pubkey::deserialize(data)?.to_string()),
+            IdlType::Option(inner) => match u8::deserialize(data)? {
+                0 => JsonValue::Null,
+                _ => self.decode(inner, data, generics)?,
+            },
+            IdlType::Vec(inner) => {
+                let len = u32::deserialize(data)?;
+                self.decode_array(inner, len as usize, data, generics)?
+            }
+            IdlType::Array(inner, len) => self.decode_array(inner, *len, data, generics)?,
+            IdlType::GenericLenArray(inner, generic) => match generic_arg(generics, generic)? {
+                GenericArg::Len(len) => self.decode_array(inner, *len, data, generics)?,
+                GenericArg::Type(_) => return Err(error(format!("`{generic}` is not a length"))),
+            },
+            IdlType::Generic(generic) => match generic_arg(generics, generic)? {
+                GenericArg::Type(ty) => self.decode(ty, data, generics)?,
+                GenericArg::Len(_) => return Err(error(format!("`{generic}` is not a type"))),
+            },
+            IdlType::Defined(name) => {
+                let def = self.type_definition(name)?;
+                self.decode_definition(def, &[], data, generics)?
+            }
+            IdlType::DefinedWithTypeArgs { name, args } => {
+                let def = self.type_definition(name)?;
+                self.decode_definition(def, args, data, generics)?
+            }
+        })
+    }
+
+    fn decode_array(
+        &self,
+        ty: &IdlType,
+        len: usize,
+        data: &mut &[u8],
+        generics: &Generics,
+    ) -> Result<JsonValue, ClientError> {
+        let values = (0..len)
+            .map(|_| self.decode(ty, data, generics))
+            .collect::<Result<_, _>>()?;
+        Ok(JsonValue::Array(values))
+    }
+
+    fn decode_definition(
+        &self,
+        def: &IdlTypeDefinition,
+        args: &[IdlDefinedTypeArg],
+        data: &mut &[u8],
+        generics: &Generics,
+    ) -> Result<JsonValue, ClientError> {
+        let (_, generics) = self.definition_generics(&def.name, args, generics)?;
+        let generics = &generics;
+        Ok(match &def.ty {
+            IdlTypeDefinitionTy::Struct { fields } => {
+                let mut values = Map::new();
+                for field in fields {
+                    values.insert(field.name.clone(), self.decode(&field.ty, data, generics)?);
+                }
+                JsonValue::Object(values)
+            }
+            IdlTypeDefinitionTy::Enum { variants } => {
+                let index = u8::deserialize(data)?;
+                let variant = variants.get(index as usize).ok_or_else(|| {
+                    error(format!("Invalid variant index {index} of `{}`", def.name))
+                })?;
+                let fields = match &variant.fields {
+                    None => json!({}),
+                    Some(EnumFields::Named(named)) => {
+                        let mut values = Map::new();
+                        for field in named {
+                            values.insert(
+                                field.name.clone(),
+                                self.decode(&field.ty, data, generics)?,
+                            );
+                        }
+                        JsonValue::Object(values)
+                    }
+                    Some(EnumFields::Tuple(tuple)) => JsonValue::Array(
+                        tuple
+                            .iter()
+                            .map(|ty| self.decode(ty, data, generics))
+                            .collect::<Result<_, _>>()?,
+                    ),
+                };
+                let mut value = Map::new();
+                value.insert(variant.name.clone(), fields);
+                JsonValue::Object(value)
+            }
+            IdlTypeDefinitionTy::Alias { value } => self.decode(value, data, generics)?,
+        })
+    }
+}
+
+// State of `IdlProgram::resolve_accounts`.
+struct AccountsResolver<'a> {
+    program: &'a IdlProgram,
+    ix: &'a IdlInstruction,
+    args: &'a JsonValue,
+    fetched: &'a FetchedAccounts,
+    needed: Vec<Pubkey>,
+}
+
+impl AccountsResolver<'_> {
+    // Resolves what it can of a level of accounts, returning whether any
+    // account was resolved.
+    fn resolve(
+        &mut self,
+        items: &[IdlAccountItem],
+        accounts: &mut Map<String, JsonValue>,
+    ) -> Result<bool, ClientError> {
+        let mut resolved = false;
+        for (i, item) in items.iter().enumerate() {
+            let account = match item {
+                IdlAccountItem::IdlAccounts(nested) => {
+                    let nested_accounts = accounts
+                        .entry(nested.name.clone())
+                        .or_insert_with(|| json!({}))
+                        .as_object_mut()
+                        .ok_or_else(|| {
+                            error(format!("Expected an object of accounts `{}`", nested.name))
+                        })?;
+                    resolved |= self.resolve(&nested.accounts, nested_accounts)?;
+                    continue;
+                }
+                IdlAccountItem::IdlAccount(account) => account,
+            };
+            if account_key(accounts, &account.name)?.is_some() {
+                continue;
+            }
+
+            let name_at = |i: Option<usize>| match i.and_then(|i| items.get(i)) {
+                Some(IdlAccountItem::IdlAccount(account)) => account.name.as_str(),
+                _ => "",
+            };
+            let pubkey = match account.name.as_str() {
+                "eventAuthority" if name_at(Some(i + 1)) == "program" => Some(
+                    Pubkey::find_program_address(&[b"__event_authority"], &self.program.program_id)
+                        .0,
+                ),
+                "program" if name_at(i.checked_sub(1)) == "eventAuthority" => {
+                    Some(self.program.program_id)
+                }
+                name => match WELL_KNOWN_ACCOUNTS.iter().find(|(known, _)| *known == name) {
+                    Some((_, pubkey)) => Some(*pubkey),
+                    None => self.pda(account, accounts)?,
+                },
+            };
+            if let Some(pubkey) = pubkey {
+                accounts.insert(account.name.clone(), json!(pubkey.to_string()));
+                resolved = true;
+            }
+        }
+
+        for item in items {
+            let holder = match item {
+                IdlAccountItem::IdlAccount(account) if !account.relations.is_empty() => account,
+                _ => continue,
+            };
+            let missing: Vec<String> = holder
+                .relations
+                .iter()
+                .map(|relation| relation.to_mixed_case())
+                .filter(|relation| !matches!(account_key(accounts, relation), Ok(Some(_))))
+                .collect();
+            if missing.is_empty() {
+                continue;
+            }
+            let data = match account_key(accounts, &holder.name)? {
+                Some(key) => self.account_data(key, None)?,
+                None => None,
+            };
+            if let Some(data) = data {
+                for relation in missing {
+                    if let Some(JsonValue::String(pubkey)) = data.get(&relation) {
+                        accounts.insert(relation, json!(pubkey));
+                        resolved = true;
+                    }
+                }
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    fn pda(
+        &mut self,
+        account: &IdlAccount,
+        accounts: &Map<String, JsonValue>,
+    ) -> Result<Option<Pubkey>, ClientError> {
+        let pda = match &account.pda {
+            Some(pda) => pda,
+            None => return Ok(None),
+        };
+        let mut seeds = Vec::new();
+        for seed in &pda.seeds {
+            match self.seed(seed, accounts)? {
+                Some(seed) => seeds.push(seed),
+                None => return Ok(None),
+            }
+        }
+        let program_id = match &pda.program_id {
+            None => self.program.program_id,
+            Some(seed) => match self.seed(seed, accounts)? {
+                Some(seed) => match <[u8; 32]>::try_from(seed.as_slice()) {
+                    Ok(program_id) => Pubkey::new_from_array(program_id),
+                    Err(_) => return Err(error("Invalid PDA program id")),
+                },
+                None => return Ok(None),
+            },
+        };
+        let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+        Ok(Some(Pubkey::find_program_address(&seeds, &program_id).0))
+    }
+
+    // Gets the bytes of a seed, `None` if its value isn't known yet.
+    fn seed(
+        &mut self,
+        seed: &IdlSeed,
+        accounts: &Map<String, JsonValue>,
+    ) -> Result<Option<Vec<u8>>, ClientError> {
+        let (ty, value) = match seed {
+            IdlSeed::Const(seed) => (&seed.ty, seed.value.clone()),
+            IdlSeed::Arg(seed) => {
+                let mut path = seed.path.split('.');
+                let name = path.next().unwrap_or_default().to_mixed_case();
+                let arg = match self.args {
+                    JsonValue::Object(args) => args.get(&name),
+                    JsonValue::Array(args) => self
+                        .ix
+                        .args
+                        .iter()
+                        .position(|arg| arg.name == name)
+                        .and_then(|i| args.get(i)),
+                    _ => None,
+                };
+                match arg.and_then(|arg| field(arg, path)) {
+                    Some(value) => (&seed.ty, value.clone()),
+                    None => return Ok(None),
+                }
+            }
+            IdlSeed::Account(seed) => {
+                let mut path = seed.path.split('.').peekable();
+                let name = path.next().unwrap_or_default().to_mixed_case();
+                let key = match account_key(accounts, &name)? {
+                    Some(key) => key,
+                    None => return Ok(None),
+                };
+                if path.peek().is_none() {
+                    (&seed.ty, json!(key.to_string()))
+                } else {
+                    let data = self.account_data(key, seed.account.as_deref())?;
+                    match data.as_ref().and_then(|data| field(data, path)) {
+                        Some(value) => (&seed.ty, value.clone()),
+                        None => return Ok(None),
+                    }
+                }
+            }
+        };
+
+        // Seeds are the raw bytes of strings and vectors, without the length
+        // prefix of their Borsh encoding.
+        let mut bytes = self.program.encode_type(ty, &value)?;
+        if matches!(ty, IdlType::String | IdlType::Bytes | IdlType::Vec(_)) {
+            bytes.drain(..4);
+        }
+        Ok(Some(bytes))
+    }
+
+    // Decodes the data of an account of the program, either of the given type
+    // or found from its discriminator. `None` if it isn't fetched yet, doesn't
+    // exist or isn't an account of the program.
+    fn account_data(
+        &mut self,
+        key: Pubkey,
+        ty: Option<&str>,
+    ) -> Result<Option<JsonValue>, ClientError> {
+        let data = match self.fetched.get(&key) {
+            Some(Some(data)) => data,
+            Some(None) => return Ok(None),
+            None => {
+                self.needed.push(key);
+                return Ok(None);
+            }
+        };
+        let accounts = &self.program.idl.accounts;
+        let account = match ty {
+            Some(ty) => accounts.iter().find(|account| account.name == ty),
+            None => accounts
+                .iter()
+                .find(|account| data.starts_with(&discriminator("account", &account.name))),
+        };
+        account
+            .map(|account| self.program.decode_account(&account.name, data))
+            .transpose()
+    }
+}
+
+// Gets the address of a set account of a level of accounts.
+fn account_key(
+    accounts: &Map<String, JsonValue>,
+    name: &str,
+) -> Result<Option<Pubkey>, ClientError> {
+    match accounts.get(name) {
+        None | Some(JsonValue::Null) => Ok(None),
+        Some(JsonValue::String(pubkey)) => parse_pubkey(pubkey).map(Some),
+        Some(_) => Err(error(format!("Expected a public key for account `{name}`"))),
+    }
+}
+
+// Follows a path of field names through nested objects.
+fn field<'a, 'b>(
+    value: &'a JsonValue,
+    mut path: impl Iterator<Item = &'b str>,
+) -> Option<&'a JsonValue> {
+    path.try_fold(value, |value, name| value.get(name.to_mixed_case()))
+}
+
+// Replaces the generic parameters of `ty` by their value, so that it can be
+// used as the argument of another definition.
+fn substitute(ty: &IdlType, generics: &Generics) -> Result<IdlType, ClientError> {
+    Ok(match ty {
+        IdlType::Generic(generic) => match generic_arg(generics, generic)? {
+            GenericArg::Type(ty) => ty.clone(),
+            GenericArg::Len(_) => return Err(error(format!("`{generic}` is not a type"))),
+        },
+        IdlType::GenericLenArray(inner, generic) => match generic_arg(generics, generic)? {
+            GenericArg::Len(len) => IdlType::Array(Box::new(substitute(inner, generics)?), *len),
+            GenericArg::Type(_) => return Err(error(format!("`{generic}` is not a length"))),
+        },
+        IdlType::Option(inner) => IdlType::Option(Box::new(substitute(inner, generics)?)),
+        IdlType::Vec(inner) => IdlType::Vec(Box::new(substitute(inner, generics)?)),
+        IdlType::Array(inner, len) => IdlType::Array(Box::new(substitute(inner, generics)?), *len),
+        IdlType::DefinedWithTypeArgs { name, args } => IdlType::DefinedWithTypeArgs {
+            name: name.clone(),
+            args: args
+                .iter()
+                .map(|arg| {
+                    Ok(match arg {
+                        IdlDefinedTypeArg::Generic(generic) => {
+                            match generic_arg(generics, generic)? {
+                                GenericArg::Type(ty) => IdlDefinedTypeArg::Type(ty.clone()),
+                                GenericArg::Len(len) => IdlDefinedTypeArg::Value(len.to_string()),
+                            }
+                        }
+                        IdlDefinedTypeArg::Type(ty) => {
+                            IdlDefinedTypeArg::Type(substitute(ty, generics)?)
+                        }
+                        arg => arg.clone(),
+                    })
+                })
+                .collect::<Result<_, ClientError>>()?,
+        },
+        ty => ty.clone(),
+    })
+}
+
+fn generic_arg<'a>(generics: &'a Generics, name: &str) -> Result<&'a GenericArg, ClientError> {
+    generics
+        .get(name)
+        .ok_or_else(|| error(format!("Unbound generic `{name}`")))
+}
+
+fn discriminator(namespace: &str, name: &str) -> [u8; 8] {
+    let mut disc = [0; 8];
+    disc.copy_from_slice(&hash(format!("{namespace}:{name}").as_bytes()).to_bytes()[..8]);
+    disc
+}
+
+fn instruction_discriminator(name: &str) -> [u8; 8] {
+    discriminator("global", &name.to_snake_case())
+}
+
+fn split_discriminator(data: &[u8]) -> Result<([u8; 8], &[u8]), ClientError> {
+    if data.len() < 8 {
+        return Err(anchor_lang::error::Error::from(
+            anchor_lang::error::ErrorCode::AccountDiscriminatorNotFound,
+        )
+        .into());
+    }
+    let mut disc = [0; 8];
+    disc.copy_from_slice(&data[..8]);
+    Ok((disc, &data[8..]))
+}
+
+fn parse_pubkey(pubkey: &str) -> Result<Pubkey, ClientError> {
+    Pubkey::from_str(pubkey).map_err(|_| error(format!("Invalid public key `{pubkey}`")))
+}
+
+fn parse_int<T: FromStr + TryFrom<u64> + TryFrom<i64>>(
+    value: &JsonValue,
+) -> Result<T, ClientError> {
+    match value {
+        JsonValue::Number(number) => number
+            .as_u64()
+            .and_then(|number| T::try_from(number).ok())
+            .or_else(|| number.as_i64().and_then(|number| T::try_from(number).ok())),
+        JsonValue::String(number) => number.parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| error(format!("Invalid integer {value}")))
+}
+
+fn error(msg: impl Into<String>) -> ClientError {
+    ClientError::IdlError(msg.into())
+}
+
+fn mismatch(ty: &IdlType, value: &JsonValue) -> ClientError {
+    error(format!("Expected a value of type {ty:?}, found {value}"))
+}
+
+fn unsupported(ty: &IdlType) -> ClientError {
+    error(format!("{ty:?} is not supported"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program() -> IdlProgram {
+        let idl = serde_json::from_value(json!({
+            "version": "0.1.0",
+            "name": "dynamic",
+            "instructions": [{
+                "name": "setData",
+                "accounts": [
+                    { "name": "data", "isMut": true, "isSigner": false },
+                    {
+                        "name": "common",
+                        "accounts": [
+                            { "name": "authority", "isMut": false, "isSigner": true }
+                        ]
+                    },
+                    { "name": "log", "isMut": true, "isSigner": false, "isOptional": true }
+                ],
+                "args": [
+                    { "name": "amount", "type": "u64" },
+                    { "name": "mode", "type": { "defined": "Mode" } }
+                ],
+                "returns": { "option": "u128" }
+            }],
+            "accounts": [{
+                "name": "Data",
+                "type": {
+                    "kind": "struct",
+                    "fields": [
+                        { "name": "authority", "type": "publicKey" },
+                        { "name": "total", "type": "i128" },
+                        { "name": "label", "type": { "option": "string" } },
+                        { "name": "mode", "type": { "defined": "Mode" } },
+                        {
+                            "name": "history",
+                            "type": {
+                                "definedWithTypeArgs": {
+                                    "name": "History",
+                                    "args": [{ "type": "u16" }, { "value": "2" }]
+                                }
+                            }
+                        }
+                    ]
+                }
+            }],
+            "types": [
+                {
+                    "name": "Mode",
+                    "type": {
+                        "kind": "enum",
+                        "variants": [
+                            { "name": "Off" },
+                            { "name": "Fixed", "fields": ["u8", "bool"] },
+                            { "name": "Custom", "fields": [{ "name": "bytes", "type": "bytes" }] }
+                        ]
+                    }
+                },
+                {
+                    "name": "History",
+                    "generics": ["T", "N"],
+                    "type": {
+                        "kind": "struct",
+                        "fields": [
+                            { "name": "entries", "type": { "genericLenArray": [{ "generic": "T" }, "N"] } }
+                        ]
+                    }
+                }
+            ],
+            "events": [{
+                "name": "DataSet",
+                "fields": [{ "name": "amount", "type": "u64", "index": false }]
+            }],
+            "metadata": { "address": "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS" }
+        }))
+        .unwrap();
+        IdlProgram::from_idl(idl).unwrap()
+    }
+
+    #[test]
+    fn account_roundtrip() {
+        let program = program();
+        let value = json!({
+            "authority": Pubkey::new_unique().to_string(),
+            "total": "-170141183460469231731687303715884105728",
+            "label": null,
+            "mode": { "Custom": { "bytes": [1, 2, 3] } },
+            "history": { "entries": [7, 8] }
+        });
+        let mut data = discriminator("account", "Data").to_vec();
+        data.extend(
+            program
+                .encode_type(&IdlType::Defined("Data".into()), &value)
+                .unwrap(),
+        );
+        assert_eq!(program.decode_account("Data", &data).unwrap(), value);
+
+        data[0] ^= 1;
+        assert!(program.decode_account("Data", &data).is_err());
+    }
+
+    #[test]
+    fn instruction() {
+        let program = program();
+        let data_key = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let accounts = json!({
+            "data": data_key.to_string(),
+            "common": { "authority": authority.to_string() }
+        });
+        let ix = program
+            .instruction(
+                "setData",
+                &accounts,
+                &json!({ "amount": "5", "mode": { "Fixed": [1, true] } }),
+            )
+            .unwrap();
+
+        assert_eq!(ix.program_id, program.id());
+        assert_eq!(
+            ix.accounts,
+            vec![
+                AccountMeta::new(data_key, false),
+                AccountMeta::new_readonly(authority, true),
+                AccountMeta::new_readonly(program.id(), false),
+            ]
+        );
+        let mut expected = instruction_discriminator("set_data").to_vec();
+        expected.extend([5, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1]);
+        assert_eq!(ix.data, expected);
+
+        let (name, args) = program.decode_instruction(&ix.data).unwrap();
+        assert_eq!(name, "setData");
+        assert_eq!(args, json!({ "amount": 5, "mode": { "Fixed": [1, true] } }));
+
+        // Positional arguments and unit variants by name.
+        let data = program
+            .instruction_data("setData", &json!([5, "Off"]))
+            .unwrap();
+        assert_eq!(data[8..], [5, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn invalid_values() {
+        let program = program();
+        let accounts = json!({ "data": Pubkey::new_unique().to_string() });
+        assert!(program.account_metas("setData", &accounts).is_err());
+        assert!(program
+            .instruction_data("setData", &json!({ "amount": -1, "mode": "Off" }))
+            .is_err());
+        assert!(program
+            .instruction_data("setData", &json!({ "amount": 1, "mode": "On" }))
+            .is_err());
+        assert!(program.instruction_data("missing", &json!([])).is_err());
+    }
+
+    #[test]
+    fn events_and_return_data() {
+        let program = program();
+        let mut data = discriminator("event", "DataSet").to_vec();
+        data.extend(9u64.to_le_bytes());
+        assert_eq!(
+            program.decode_event(&data).unwrap(),
+            ("DataSet".to_string(), json!({ "amount": 9 }))
+        );
+
+        let mut data = vec![1];
+        data.extend(u128::MAX.to_le_bytes());
+        assert_eq!(
+            program.decode_return_data("setData", &data).unwrap(),
+            json!(u128::MAX.to_string())
+        );
+        assert_eq!(
+            program.decode_return_data("setData", &[0]).unwrap(),
+            JsonValue::Null
+        );
+    }
+
+    #[test]
+    fn resolve_accounts() {
+        let idl = serde_json::from_value(json!({
+            "version": "0.1.0",
+            "name": "resolver",
+            "instructions": [{
+                "name": "update",
+                "accounts": [
+                    { "name": "settings", "isMut": false, "isSigner": false, "relations": ["authority"] },
+                    { "name": "authority", "isMut": false, "isSigner": true },
+                    {
+                        "name": "counter",
+                        "isMut": true,
+                        "isSigner": false,
+                        "pda": {
+                            "seeds": [
+                                { "kind": "const", "type": "string", "value": "counter" },
+                                { "kind": "account", "type": "publicKey", "path": "authority" },
+                                { "kind": "arg", "type": "string", "path": "params.label" }
+                            ]
+                        }
+                    },
+                    {
+                        "name": "vault",
+                        "isMut": true,
+                        "isSigner": false,
+                        "pda": {
+                            "seeds": [
+                                { "kind": "account", "type": "u64", "account": "Settings", "path": "settings.limit" }
+                            ]
+                        }
+                    },
+                    { "name": "systemProgram", "isMut": false, "isSigner": false },
+                    { "name": "tokenProgram", "isMut": false, "isSigner": false },
+                    { "name": "eventAuthority", "isMut": false, "isSigner": false },
+                    { "name": "program", "isMut": false, "isSigner": false }
+                ],
+                "args": [{ "name": "params", "type": { "defined": "Params" } }]
+            }],
+            "accounts": [{
+                "name": "Settings",
+                "type": {
+                    "kind": "struct",
+                    "fields": [
+                        { "name": "authority", "type": "publicKey" },
+                        { "name": "limit", "type": "u64" }
+                    ]
+                }
+            }],
+            "types": [{
+                "name": "Params",
+                "type": {
+                    "kind": "struct",
+                    "fields": [{ "name": "label", "type": "string" }]
+                }
+            }]
+        }))
+        .unwrap();
+        let program = IdlProgram::new(idl, Pubkey::new_unique());
+        let settings = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let args = json!({ "params": { "label": "main" } });
+        let mut accounts = json!({ "settings": settings.to_string() });
+
+        // The settings are needed to resolve the rest.
+        let mut fetched = FetchedAccounts::new();
+        let needed = program
+            .resolve_accounts("update", &mut accounts, &args, &fetched)
+            .unwrap();
+        assert_eq!(needed, vec![settings]);
+        assert!(accounts.get("counter").is_none());
+        assert_eq!(
+            accounts["systemProgram"],
+            json!(system_program::ID.to_string())
+        );
+        // Either Token or Token-2022, depending on the mint.
+        assert!(accounts.get("tokenProgram").is_none());
+        let event_authority =
+            Pubkey::find_program_address(&[b"__event_authority"], &program.id()).0;
+        assert_eq!(
+            accounts["eventAuthority"],
+            json!(event_authority.to_string())
+        );
+        assert_eq!(accounts["program"], json!(program.id().to_string()));
+
+        let mut data = discriminator("account", "Settings").to_vec();
+        data.extend(authority.to_bytes());
+        data.extend(7u64.to_le_bytes());
+        fetched.insert(settings, Some(data));
+        let needed = program
+            .resolve_accounts("update", &mut accounts, &args, &fetched)
+            .unwrap();
+        assert!(needed.is_empty());
+
+        let counter =
+            Pubkey::find_program_address(&[b"counter", authority.as_ref(), b"main"], &program.id())
+                .0;
+        let vault = Pubkey::find_program_address(&[&7u64.to_le_bytes()], &program.id()).0;
+        assert_eq!(accounts["authority"], json!(authority.to_string()));
+        assert_eq!(accounts["counter"], json!(counter.to_string()));
+        assert_eq!(accounts["vault"], json!(vault.to_string()));
+        accounts["tokenProgram"] = json!(Pubkey::new_unique().to_string());
+        assert!(program.instruction("update", &accounts, &args).is_ok());
+    }
+
+    #[test]
+    fn resolve_accounts_keeps_given_accounts() {
+        let program = program();
+        let data = Pubkey::new_unique().to_string();
+        let mut accounts = json!({ "data": data });
+        let needed = program
+            .resolve_accounts(
+                "setData",
+                &mut accounts,
+                &json!([]),
+                &FetchedAccounts::new(),
+            )
+            .unwrap();
+        assert!(needed.is_empty());
+        assert_eq!(accounts, json!({ "data": data, "common": {} }));
+    }
+}
@@ -0,0 +1,270 @@
+//! Password encrypted keypair files, see `Keystore`.
+
+use crate::ClientError;
+use aes_gcm_siv::aead::{Aead, NewAead, Payload};
+use aes_gcm_siv::{Aes256GcmSiv, Key, Nonce};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use hmac::Hmac;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use solana_sdk::signature::{Keypair, Signer};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use zeroize::{Zeroize, Zeroizing};
+
+/// The environment variable holding the passphrase of keystore wallets,
+/// prompted for otherwise.
+pub const KEYSTORE_PASSPHRASE_ENV: &str = "ANCHOR_WALLET_PASSPHRASE";
+
+const KEYSTORE_VERSION: u8 = 1;
+const KDF: &str = "pbkdf2-hmac-sha256";
+const KDF_ITERATIONS: u32 = 600_000;
+// Bounds the cost of decrypting a keystore given by someone else.
+const MAX_KDF_ITERATIONS: u32 = 10_000_000;
+const CIPHER: &str = "aes-256-gcm-siv";
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// A keypair encrypted with a passphrase, stored as JSON.
+///
+/// The key is derived from the passphrase with PBKDF2-HMAC-SHA256 and
+/// encrypts the keypair with AES-256-GCM-SIV, authenticating its pubkey,
+/// kept in clear.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    version: u8,
+    pubkey: String,
+    kdf: KdfParams,
+    cipher: CipherParams,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct KdfParams {
+    name: String,
+    iterations: u32,
+    salt: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CipherParams {
+    name: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl Keystore {
+    /// Encrypts `keypair` with `passphrase`.
+    pub fn encrypt(keypair: &Keypair, passphrase: &str) -> Result<Self, ClientError> {
+        Self::encrypt_with_iterations(keypair, passphrase, KDF_ITERATIONS)
+    }
+
+    pub(crate) fn encrypt_with_iterations(
+        keypair: &Keypair,
+        passphrase: &str,
+        iterations: u32,
+    ) -> Result<Self, ClientError> {
+        let mut salt = [0; SALT_LEN];
+        let mut nonce = [0; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+        let pubkey = keypair.pubkey().to_string();
+        let ciphertext = cipher(passphrase, &salt, iterations)
+            .encrypt(
+                &Nonce::from(nonce),
+                Payload {
+                    msg: &*Zeroizing::new(keypair.to_bytes()),
+                    aad: pubkey.as_bytes(),
+                },
+            )
+            .map_err(|_| ClientError::KeystoreError("Unable to encrypt keypair".to_string()))?;
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            pubkey,
+            kdf: KdfParams {
+                name: KDF.to_string(),
+                iterations,
+                salt: anchor_lang::__private::base64::encode(salt),
+            },
+            cipher: CipherParams {
+                name: CIPHER.to_string(),
+                nonce: anchor_lang::__private::base64::encode(nonce),
+                ciphertext: anchor_lang::__private::base64::encode(ciphertext),
+            },
+        })
+    }
+
+    /// Decrypts the keypair, failing with a wrong passphrase or a key
+    /// derivation weaker than the one of [`Keystore::encrypt`].
+    pub fn decrypt(&self, passphrase: &str) -> Result<Keypair, ClientError> {
+        self.decrypt_with_min_iterations(passphrase, KDF_ITERATIONS)
+    }
+
+    pub(crate) fn decrypt_with_min_iterations(
+        &self,
+        passphrase: &str,
+        min_iterations: u32,
+    ) -> Result<Keypair, ClientError> {
+        if self.version != KEYSTORE_VERSION || self.kdf.name != KDF || self.cipher.name != CIPHER {
+            return Err(ClientError::KeystoreError(format!(
+                "Unsupported keystore: version {}, {}, {}",
+                self.version, self.kdf.name, self.cipher.name
+            )));
+        }
+        if !(min_iterations..=MAX_KDF_ITERATIONS).contains(&self.kdf.iterations) {
+            return Err(ClientError::KeystoreError(format!(
+                "Unsupported keystore: {} iterations, not within {min_iterations}..={}",
+                self.kdf.iterations, MAX_KDF_ITERATIONS
+            )));
+        }
+        let decode = |data: &str| {
+            anchor_lang::__private::base64::decode(data)
+                .map_err(|e| ClientError::KeystoreError(e.to_string()))
+        };
+        let nonce: [u8; NONCE_LEN] = decode(&self.cipher.nonce)?
+            .try_into()
+            .map_err(|_| ClientError::KeystoreError("Invalid nonce".to_string()))?;
+        let bytes = Zeroizing::new(
+            cipher(passphrase, &decode(&self.kdf.salt)?, self.kdf.iterations)
+                .decrypt(
+                    &Nonce::from(nonce),
+                    Payload {
+                        msg: &decode(&self.cipher.ciphertext)?,
+                        aad: self.pubkey.as_bytes(),
+                    },
+                )
+                .map_err(|_| ClientError::KeystoreError("Wrong passphrase".to_string()))?,
+        );
+        let keypair =
+            Keypair::from_bytes(&bytes).map_err(|e| ClientError::KeystoreError(e.to_string()))?;
+        if keypair.pubkey().to_string() != self.pubkey {
+            return Err(ClientError::KeystoreError(
+                "Keypair doesn't match its pubkey".to_string(),
+            ));
+        }
+
+        Ok(keypair)
+    }
+
+    pub fn pubkey(&self) -> Result<Pubkey, ClientError> {
+        Pubkey::from_str(&self.pubkey).map_err(|e| ClientError::KeystoreError(e.to_string()))
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, ClientError> {
+        let data = fs::read_to_string(path)?;
+        serde_json::from_str(&data).map_err(|e| ClientError::KeystoreError(e.to_string()))
+    }
+
+    /// Writes the keystore, only readable by its owner on Unix.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), ClientError> {
+        let data = serde_json::to_string_pretty(self)
+            .map_err(|e| ClientError::KeystoreError(e.to_string()))?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        std::io::Write::write_all(&mut options.open(path)?, data.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// Returns whether the file at `path` is a keystore rather than a plaintext
+/// keypair.
+pub fn is_keystore_file(path: impl AsRef<Path>) -> bool {
+    fs::read_to_string(path)
+        .map(|data| data.trim_start().starts_with('{'))
+        .unwrap_or(false)
+}
+
+/// Reads the keypair of a wallet file, either a plaintext keypair or a
+/// [`Keystore`] decrypted with the passphrase of the
+/// [`KEYSTORE_PASSPHRASE_ENV`] environment variable, prompted for if unset.
+pub fn read_wallet_keypair(path: impl AsRef<Path>) -> Result<Keypair, ClientError> {
+    let path = path.as_ref();
+    if !is_keystore_file(path) {
+        return solana_sdk::signature::read_keypair_file(path).map_err(|e| {
+            ClientError::KeystoreError(format!(
+                "Unable to read keypair file {}: {e}",
+                path.display()
+            ))
+        });
+    }
+    let keystore = Keystore::read(path)?;
+    let passphrase = Zeroizing::new(match std::env::var(KEYSTORE_PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        Err(_) => rpassword::prompt_password(format!("Passphrase of {}: ", path.display()))
+            .map_err(|e| {
+                ClientError::KeystoreError(format!(
+                    "Unable to read the passphrase, set {KEYSTORE_PASSPHRASE_ENV}: {e}"
+                ))
+            })?,
+    });
+    keystore.decrypt(&passphrase)
+}
+
+fn cipher(passphrase: &str, salt: &[u8], iterations: u32) -> Aes256GcmSiv {
+    let mut key = [0; 32];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase.as_bytes(), salt, iterations, &mut key);
+    let cipher = Aes256GcmSiv::new(&Key::from(key));
+    key.zeroize();
+    cipher
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        let keypair = Keypair::new();
+        let keystore = Keystore::encrypt_with_iterations(&keypair, "passphrase", 10).unwrap();
+        assert_eq!(keystore.pubkey().unwrap(), keypair.pubkey());
+        assert_eq!(
+            keystore
+                .decrypt_with_min_iterations("passphrase", 10)
+                .unwrap()
+                .to_bytes(),
+            keypair.to_bytes()
+        );
+        assert!(keystore.decrypt_with_min_iterations("wrong", 10).is_err());
+
+        // The pubkey is authenticated.
+        let mut tampered = keystore.clone();
+        tampered.pubkey = Pubkey::new_unique().to_string();
+        assert!(tampered
+            .decrypt_with_min_iterations("passphrase", 10)
+            .is_err());
+
+        // The key derivation can be neither weaker than the one keystores are
+        // encrypted with, nor too costly.
+        assert!(matches!(
+            keystore.decrypt("passphrase"),
+            Err(ClientError::KeystoreError(_))
+        ));
+        let mut costly = keystore.clone();
+        costly.kdf.iterations = MAX_KDF_ITERATIONS + 1;
+        assert!(matches!(
+            costly.decrypt("passphrase"),
+            Err(ClientError::KeystoreError(_))
+        ));
+
+        let dir = std::env::temp_dir().join(format!("keystore-{}", keypair.pubkey()));
+        fs::create_dir_all(&dir).unwrap();
+        let keystore_path = dir.join("keystore.json");
+        keystore.write(&keystore_path).unwrap();
+        assert!(is_keystore_file(&keystore_path));
+        assert_eq!(Keystore::read(&keystore_path).unwrap(), keystore);
+        let keypair_path = dir.join("id.json");
+        solana_sdk::signature::write_keypair_file(&keypair, &keypair_path).unwrap();
+        assert!(!is_keystore_file(&keypair_path));
+        assert_eq!(
+            read_wallet_keypair(&keypair_path).unwrap().pubkey(),
+            keypair.pubkey()
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
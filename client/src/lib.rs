@@ -1,14 +1,23 @@
 //! `anchor_client` provides an RPC client to send transactions and fetch
 //! deserialized accounts from Solana programs written in `anchor_lang`.
 
+use anchor_lang::error::ProgramErrorCode;
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
 use anchor_lang::solana_program::hash::Hash;
 use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
 use anchor_lang::solana_program::program_error::ProgramError;
 use anchor_lang::solana_program::pubkey::Pubkey;
 use anchor_lang::{AccountDeserialize, Discriminator, InstructionData, ToAccountMetas};
+use compute_budget::ComputeBudget;
 use futures::{Future, StreamExt};
-use regex::Regex;
-use solana_account_decoder::UiAccountEncoding;
+use idl::types::Idl;
+use idl::FetchedAccounts;
+use program_error::{message_program_ids, ErrorLookup};
+
+use retry::Lifetime;
+use serde_json::Value as JsonValue;
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_address_lookup_table_program::instruction as lookup_table;
 use solana_client::rpc_config::{
     RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSendTransactionConfig,
     RpcTransactionLogsConfig, RpcTransactionLogsFilter,
@@ -20,42 +29,96 @@ use solana_client::{
         pubsub_client::{PubsubClient, PubsubClientError},
         rpc_client::RpcClient as AsyncRpcClient,
     },
-    rpc_client::RpcClient,
+    rpc_client::{RpcClient, SerializableTransaction},
+    rpc_request::MAX_MULTIPLE_ACCOUNTS,
     rpc_response::{Response as RpcResponse, RpcLogsResponse},
 };
 use solana_sdk::account::Account;
+use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
 use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::message::{v0, CompileError, Message, VersionedMessage};
 use solana_sdk::signature::{Signature, Signer};
-use solana_sdk::transaction::Transaction;
+use solana_sdk::signer::presigner::PresignerError;
+use solana_sdk::signer::SignerError;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{Transaction, TransactionError, VersionedTransaction};
+use std::fmt;
 use std::iter::Map;
 use std::marker::PhantomData;
 use std::ops::Deref;
 use std::pin::Pin;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::vec::IntoIter;
 use thiserror::Error;
 use tokio::{
     runtime::Handle,
     sync::{
-        mpsc::{unbounded_channel, UnboundedReceiver},
+        mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
         RwLock,
     },
     task::JoinHandle,
 };
 
 pub use anchor_lang;
+pub use backend::{Backend, RpcBackend};
+#[cfg(not(feature = "async"))]
+pub use backfill::EventIterator;
+pub use backfill::{EventStream, HistoryPosition};
+#[cfg(feature = "banks")]
+pub use banks::BanksBackend;
 pub use cluster::Cluster;
+pub use compute_budget::{
+    AutoComputeBudget, DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT, MAX_COMPUTE_UNIT_LIMIT,
+};
+pub use fetch::MultipleAccounts;
+pub use filter::Filter;
+pub use idl::IdlProgram;
+pub use keystore::{is_keystore_file, read_wallet_keypair, Keystore, KEYSTORE_PASSPHRASE_ENV};
+pub use logs::{Invocation, InvocationTree};
+pub use mock::{MockBackend, MockExecution};
+pub use offline::{PartialTransaction, TransactionEncoding};
+pub use planner::TransactionPlanner;
+pub use remote_signer::RemoteSigner;
+pub use retry::RetryConfig;
+#[cfg(any(test, feature = "test-utils"))]
+pub use signing_server::LocalSigningServer;
+pub use simulation::{LoggedError, LoggedErrorOrigin, Simulation};
 pub use solana_client;
 pub use solana_sdk;
 
+mod backend;
+mod backfill;
+#[cfg(feature = "banks")]
+mod banks;
 mod cluster;
+mod compute_budget;
+mod fetch;
+mod filter;
+pub mod idl;
+mod keystore;
+mod logs;
+mod mock;
+mod offline;
+mod planner;
+mod program_error;
+mod remote_signer;
+mod retry;
+#[cfg(any(test, feature = "test-utils"))]
+mod signing_server;
+mod simulation;
 
 #[cfg(not(feature = "async"))]
 mod blocking;
 #[cfg(feature = "async")]
 mod nonblocking;
+#[cfg(test)]
+mod test_rpc;
 
 const PROGRAM_LOG: &str = "Program log: ";
+// Number of addresses per instruction extending a lookup table, small
+// enough for the transaction also creating the table to fit.
+const LOOKUP_TABLE_EXTEND_CHUNK_LEN: usize = 20;
 const PROGRAM_DATA: &str = "Program data: ";
 
 type UnsubscribeFn = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
@@ -73,6 +136,7 @@ impl<C: Clone + Deref<Target = impl Signer>> Client<C> {
                 cluster,
                 payer,
                 options: None,
+                backend: None,
             },
         }
     }
@@ -83,15 +147,26 @@ impl<C: Clone + Deref<Target = impl Signer>> Client<C> {
                 cluster,
                 payer,
                 options: Some(options),
+                backend: None,
             },
         }
     }
 
+    /// Sends the requests and reads the accounts of the programs with
+    /// `backend` instead of the RPC of the cluster, e.g. a [`MockBackend`]
+    /// in tests. See [`Backend`] for what still uses the cluster.
+    #[must_use]
+    pub fn with_backend(mut self, backend: Arc<dyn Backend>) -> Self {
+        self.cfg.backend = Some(backend);
+        self
+    }
+
     pub fn program(&self, program_id: Pubkey) -> Result<Program<C>, ClientError> {
         let cfg = Config {
             cluster: self.cfg.cluster.clone(),
             options: self.cfg.options,
             payer: self.cfg.payer.clone(),
+            backend: self.cfg.backend.clone(),
         };
 
         Program::new(program_id, cfg)
@@ -129,11 +204,21 @@ impl Signer for DynSigner {
 }
 
 // Internal configuration for a client.
-#[derive(Debug)]
 pub struct Config<C> {
     cluster: Cluster,
     payer: C,
     options: Option<CommitmentConfig>,
+    backend: Option<Arc<dyn Backend>>,
+}
+
+impl<C: fmt::Debug> fmt::Debug for Config<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("cluster", &self.cluster)
+            .field("payer", &self.payer)
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
 }
 
 pub struct EventUnsubscriber<'a> {
@@ -170,13 +255,28 @@ impl<C: Deref<Target = impl Signer> + Clone> Program<C> {
 
     /// Returns a request builder.
     pub fn request(&self) -> RequestBuilder<C> {
-        RequestBuilder::from(
+        let request = RequestBuilder::from(
             self.program_id,
             self.cfg.cluster.url(),
             self.cfg.payer.clone(),
             self.cfg.options,
             #[cfg(not(feature = "async"))]
             self.rt.handle(),
+        );
+        match &self.cfg.backend {
+            Some(backend) => request.backend(backend.clone()),
+            None => request,
+        }
+    }
+
+    /// Returns a planner splitting batches of instructions across
+    /// transactions.
+    pub fn planner(&self) -> TransactionPlanner<C> {
+        TransactionPlanner::new(
+            self.cfg.payer.clone(),
+            self.backend(),
+            #[cfg(not(feature = "async"))]
+            self.rt.handle(),
         )
     }
 
@@ -198,41 +298,76 @@ impl<C: Deref<Target = impl Signer> + Clone> Program<C> {
         )
     }
 
+    // Returns the backend of the client, the RPC of the cluster by default.
+    fn backend(&self) -> Arc<dyn Backend> {
+        match &self.cfg.backend {
+            Some(backend) => backend.clone(),
+            None => Arc::new(RpcBackend::new(
+                self.cfg.cluster.url(),
+                self.cfg.options.unwrap_or_default(),
+            )),
+        }
+    }
+
     async fn account_internal<T: AccountDeserialize>(
         &self,
         address: Pubkey,
     ) -> Result<T, ClientError> {
-        let rpc_client = AsyncRpcClient::new_with_commitment(
-            self.cfg.cluster.url().to_string(),
-            self.cfg.options.unwrap_or_default(),
-        );
-        let account = rpc_client
-            .get_account_with_commitment(&address, CommitmentConfig::processed())
-            .await?
-            .value
-            .ok_or(ClientError::AccountNotFound)?;
+        let account = match &self.cfg.backend {
+            Some(backend) => backend.get_account(&address).await?,
+            // Accounts are read as soon as processed by the cluster.
+            None => {
+                RpcBackend::new(self.cfg.cluster.url(), CommitmentConfig::processed())
+                    .get_account(&address)
+                    .await?
+            }
+        }
+        .ok_or(ClientError::AccountNotFound)?;
         let mut data: &[u8] = &account.data;
         T::try_deserialize(&mut data).map_err(Into::into)
     }
 
+    // Fetches the accounts at `addresses` in chunks of the
+    // `getMultipleAccounts` limit.
+    async fn fetch_many_internal(
+        &self,
+        addresses: &[Pubkey],
+        min_context_slot: Option<u64>,
+    ) -> Result<MultipleAccounts, ClientError> {
+        let backend = self.backend();
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            accounts.append(
+                &mut backend
+                    .get_multiple_accounts(chunk, min_context_slot)
+                    .await?,
+            );
+        }
+
+        Ok(MultipleAccounts::new(addresses.to_vec(), accounts))
+    }
+
+    async fn accounts_multiple_internal<T: AccountDeserialize>(
+        &self,
+        addresses: &[Pubkey],
+        min_context_slot: Option<u64>,
+    ) -> Result<Vec<Option<T>>, ClientError> {
+        self.fetch_many_internal(addresses, min_context_slot)
+            .await?
+            .decode_all()
+    }
+
     async fn accounts_lazy_internal<T: AccountDeserialize + Discriminator>(
         &self,
         filters: Vec<RpcFilterType>,
     ) -> Result<ProgramAccountsIterator<T>, ClientError> {
         let account_type_filter =
             RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &T::discriminator()));
-        let config = RpcProgramAccountsConfig {
-            filters: Some([vec![account_type_filter], filters].concat()),
-            account_config: RpcAccountInfoConfig {
-                encoding: Some(UiAccountEncoding::Base64),
-                ..RpcAccountInfoConfig::default()
-            },
-            ..RpcProgramAccountsConfig::default()
-        };
+        let filters = [vec![account_type_filter], filters].concat();
         Ok(ProgramAccountsIterator {
             inner: self
-                .async_rpc()
-                .get_program_accounts_with_config(&self.id(), config)
+                .backend()
+                .get_program_accounts(&self.id(), filters)
                 .await?
                 .into_iter()
                 .map(|(key, account)| {
@@ -241,6 +376,40 @@ impl<C: Deref<Target = impl Signer> + Clone> Program<C> {
         })
     }
 
+    async fn resolve_accounts_internal(
+        &self,
+        program: &IdlProgram,
+        name: &str,
+        accounts: &JsonValue,
+        args: &JsonValue,
+    ) -> Result<JsonValue, ClientError> {
+        let mut accounts = accounts.clone();
+        let mut fetched = FetchedAccounts::new();
+        loop {
+            let needed = program.resolve_accounts(name, &mut accounts, args, &fetched)?;
+            if needed.is_empty() {
+                return Ok(accounts);
+            }
+            let needed_accounts = self.fetch_many_internal(&needed, None).await?;
+            for (index, key) in needed.into_iter().enumerate() {
+                fetched.insert(key, needed_accounts.raw(index).map(|a| a.data.clone()));
+            }
+        }
+    }
+
+    async fn idl_instruction_internal(
+        &self,
+        program: &IdlProgram,
+        name: &str,
+        accounts: &JsonValue,
+        args: &JsonValue,
+    ) -> Result<Instruction, ClientError> {
+        let accounts = self
+            .resolve_accounts_internal(program, name, accounts, args)
+            .await?;
+        program.instruction(name, &accounts, args)
+    }
+
     async fn init_sub_client_if_needed(&self) -> Result<(), ClientError> {
         let lock = &self.sub_client;
         let mut client = lock.write().await;
@@ -278,12 +447,7 @@ impl<C: Deref<Target = impl Signer> + Clone> Program<C> {
                 let (mut notifications, unsubscribe) =
                     client.logs_subscribe(filter, config).await?;
 
-                tx.send(unsubscribe).map_err(|e| {
-                    ClientError::SolanaClientPubsubError(PubsubClientError::RequestFailed {
-                        message: "Unsubscribe failed".to_string(),
-                        reason: e.to_string(),
-                    })
-                })?;
+                send_unsubscribe(&tx, unsubscribe)?;
 
                 while let Some(logs) = notifications.next().await {
                     let ctx = EventContext {
@@ -301,115 +465,202 @@ impl<C: Deref<Target = impl Signer> + Clone> Program<C> {
 
         Ok((handle, rx))
     }
-}
 
-/// Iterator with items of type (Pubkey, T). Used to lazily deserialize account structs.
-/// Wrapper type hides the inner type from usages so the implementation can be changed.
-pub struct ProgramAccountsIterator<T> {
-    inner: Map<IntoIter<(Pubkey, Account)>, AccountConverterFunction<T>>,
-}
-
-/// Function type that accepts solana accounts and returns deserialized anchor accounts
-type AccountConverterFunction<T> = fn((Pubkey, Account)) -> Result<(Pubkey, T), ClientError>;
+    async fn transaction_events_internal<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
+        &self,
+        signature: &Signature,
+    ) -> Result<Vec<T>, ClientError> {
+        let tx = backfill::get_transaction(
+            &self.async_rpc(),
+            signature,
+            self.cfg.options.unwrap_or_default(),
+        )
+        .await?;
+        backfill::transaction_events(&self.program_id, &tx.transaction)
+    }
 
-impl<T> Iterator for ProgramAccountsIterator<T> {
-    type Item = Result<(Pubkey, T), ClientError>;
+    async fn backfill_internal<T: anchor_lang::Event + anchor_lang::AnchorDeserialize + 'static>(
+        &self,
+        after: Option<HistoryPosition>,
+        before: Option<HistoryPosition>,
+    ) -> Result<EventStream<T>, ClientError> {
+        let rpc_client = Arc::new(self.async_rpc());
+        let commitment = self.cfg.options.unwrap_or_default();
+        let program_id = self.program_id;
+        let cursors =
+            backfill::history_pages(&rpc_client, &program_id, after, before, commitment).await?;
+
+        // Pages of signatures, then their transactions, are fetched as the
+        // stream is polled.
+        let pages_rpc_client = Arc::clone(&rpc_client);
+        let stream = futures::stream::iter(cursors)
+            .then(move |cursor| {
+                let rpc_client = Arc::clone(&pages_rpc_client);
+                async move {
+                    backfill::page_signatures(
+                        &rpc_client,
+                        &program_id,
+                        cursor,
+                        after,
+                        before,
+                        commitment,
+                    )
+                    .await
+                }
+            })
+            .flat_map(backfill::flatten_fetch)
+            .then(move |signature: Result<(Signature, u64), ClientError>| {
+                let rpc_client = Arc::clone(&rpc_client);
+                async move {
+                    let (signature, slot) = signature?;
+                    let tx = backfill::get_transaction(&rpc_client, &signature, commitment).await?;
+                    let events = backfill::transaction_events::<T>(&program_id, &tx.transaction)?;
+                    Ok(events
+                        .into_iter()
+                        .map(|event| (EventContext { signature, slot }, event))
+                        .collect::<Vec<_>>())
+                }
+            })
+            .flat_map(backfill::flatten_fetch);
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+        Ok(Box::pin(stream))
     }
-}
 
-fn handle_program_log<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
-    self_program_str: &str,
-    l: &str,
-) -> Result<(Option<T>, Option<String>, bool), ClientError> {
-    // Log emitted from the current program.
-    if let Some(log) = l
-        .strip_prefix(PROGRAM_LOG)
-        .or_else(|| l.strip_prefix(PROGRAM_DATA))
-    {
-        let borsh_bytes = match anchor_lang::__private::base64::decode(log) {
-            Ok(borsh_bytes) => borsh_bytes,
-            _ => {
-                #[cfg(feature = "debug")]
-                println!("Could not base64 decode log: {}", log);
-                return Ok((None, None, false));
-            }
+    async fn account_subscribe_internal<T: AccountDeserialize>(
+        &self,
+        address: Pubkey,
+        f: impl Fn(&AccountContext, Result<Option<T>, ClientError>) + Send + 'static,
+    ) -> Result<
+        (
+            JoinHandle<Result<(), ClientError>>,
+            UnboundedReceiver<UnsubscribeFn>,
+        ),
+        ClientError,
+    > {
+        self.init_sub_client_if_needed().await?;
+        let (tx, rx) = unbounded_channel::<_>();
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: self.cfg.options,
+            ..RpcAccountInfoConfig::default()
         };
 
-        let mut slice: &[u8] = &borsh_bytes[..];
-        let disc: [u8; 8] = {
-            let mut disc = [0; 8];
-            disc.copy_from_slice(&borsh_bytes[..8]);
-            slice = &slice[8..];
-            disc
-        };
-        let mut event = None;
-        if disc == T::discriminator() {
-            let e: T = anchor_lang::AnchorDeserialize::deserialize(&mut slice)
-                .map_err(|e| ClientError::LogParseError(e.to_string()))?;
-            event = Some(e);
-        }
-        Ok((event, None, false))
-    }
-    // System log.
-    else {
-        let (program, did_pop) = handle_system_log(self_program_str, l);
-        Ok((None, program, did_pop))
+        let lock = Arc::clone(&self.sub_client);
+
+        let handle = tokio::spawn(async move {
+            if let Some(ref client) = *lock.read().await {
+                let (mut notifications, unsubscribe) =
+                    client.account_subscribe(&address, Some(config)).await?;
+
+                send_unsubscribe(&tx, unsubscribe)?;
+
+                while let Some(response) = notifications.next().await {
+                    let ctx = AccountContext {
+                        pubkey: address,
+                        slot: response.context.slot,
+                    };
+                    f(&ctx, decode_ui_account(&response.value));
+                }
+            }
+            Ok::<(), ClientError>(())
+        });
+
+        Ok((handle, rx))
     }
-}
 
-fn handle_system_log(this_program_str: &str, log: &str) -> (Option<String>, bool) {
-    if log.starts_with(&format!("Program {this_program_str} log:")) {
-        (Some(this_program_str.to_string()), false)
-    } else if log.contains("invoke") {
-        (Some("cpi".to_string()), false) // Any string will do.
-    } else {
-        let re = Regex::new(r"^Program (.*) success*$").unwrap();
-        if re.is_match(log) {
-            (None, true)
-        } else {
-            (None, false)
-        }
+    async fn program_subscribe_internal<T: AccountDeserialize + Discriminator>(
+        &self,
+        filters: Vec<RpcFilterType>,
+        f: impl Fn(&AccountContext, Result<Option<T>, ClientError>) + Send + 'static,
+    ) -> Result<
+        (
+            JoinHandle<Result<(), ClientError>>,
+            UnboundedReceiver<UnsubscribeFn>,
+        ),
+        ClientError,
+    > {
+        self.init_sub_client_if_needed().await?;
+        let (tx, rx) = unbounded_channel::<_>();
+        let account_type_filter =
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &T::discriminator()));
+        let config = RpcProgramAccountsConfig {
+            filters: Some([vec![account_type_filter], filters].concat()),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: self.cfg.options,
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        let program_id = self.program_id;
+
+        let lock = Arc::clone(&self.sub_client);
+
+        let handle = tokio::spawn(async move {
+            if let Some(ref client) = *lock.read().await {
+                let (mut notifications, unsubscribe) =
+                    client.program_subscribe(&program_id, Some(config)).await?;
+
+                send_unsubscribe(&tx, unsubscribe)?;
+
+                while let Some(response) = notifications.next().await {
+                    let pubkey = match response.value.pubkey.parse() {
+                        Ok(pubkey) => pubkey,
+                        Err(_) => continue,
+                    };
+                    let ctx = AccountContext {
+                        pubkey,
+                        slot: response.context.slot,
+                    };
+                    f(&ctx, decode_ui_account(&response.value.account));
+                }
+            }
+            Ok::<(), ClientError>(())
+        });
+
+        Ok((handle, rx))
     }
 }
 
-struct Execution {
-    stack: Vec<String>,
+// Hands the unsubscribe function of a subscription to its `EventUnsubscriber`.
+fn send_unsubscribe(
+    tx: &UnboundedSender<UnsubscribeFn>,
+    unsubscribe: UnsubscribeFn,
+) -> Result<(), ClientError> {
+    tx.send(unsubscribe).map_err(|e| {
+        ClientError::SolanaClientPubsubError(PubsubClientError::RequestFailed {
+            message: "Unsubscribe failed".to_string(),
+            reason: e.to_string(),
+        })
+    })
 }
 
-impl Execution {
-    pub fn new(logs: &mut &[String]) -> Result<Self, ClientError> {
-        let l = &logs[0];
-        *logs = &logs[1..];
-
-        let re = Regex::new(r"^Program (.*) invoke.*$").unwrap();
-        let c = re
-            .captures(l)
-            .ok_or_else(|| ClientError::LogParseError(l.to_string()))?;
-        let program = c
-            .get(1)
-            .ok_or_else(|| ClientError::LogParseError(l.to_string()))?
-            .as_str()
-            .to_string();
-        Ok(Self {
-            stack: vec![program],
-        })
+// Deserializes an account notification, `None` for accounts that were
+// closed.
+fn decode_ui_account<T: AccountDeserialize>(account: &UiAccount) -> Result<Option<T>, ClientError> {
+    let account: Account = account.decode().ok_or_else(|| {
+        anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::AccountDidNotDeserialize)
+    })?;
+    if account.lamports == 0 {
+        return Ok(None);
     }
+    Ok(Some(T::try_deserialize(&mut (&account.data as &[u8]))?))
+}
 
-    pub fn program(&self) -> String {
-        assert!(!self.stack.is_empty());
-        self.stack[self.stack.len() - 1].clone()
-    }
+/// Iterator with items of type (Pubkey, T). Used to lazily deserialize account structs.
+/// Wrapper type hides the inner type from usages so the implementation can be changed.
+pub struct ProgramAccountsIterator<T> {
+    inner: Map<IntoIter<(Pubkey, Account)>, AccountConverterFunction<T>>,
+}
 
-    pub fn push(&mut self, new_program: String) {
-        self.stack.push(new_program);
-    }
+/// Function type that accepts solana accounts and returns deserialized anchor accounts
+type AccountConverterFunction<T> = fn((Pubkey, Account)) -> Result<(Pubkey, T), ClientError>;
 
-    pub fn pop(&mut self) {
-        assert!(!self.stack.is_empty());
-        self.stack.pop().unwrap();
+impl<T> Iterator for ProgramAccountsIterator<T> {
+    type Item = Result<(Pubkey, T), ClientError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
     }
 }
 
@@ -419,6 +670,14 @@ pub struct EventContext {
     pub slot: u64,
 }
 
+/// The account of a notification of `Program::account_subscribe` or
+/// `Program::program_subscribe`.
+#[derive(Debug)]
+pub struct AccountContext {
+    pub pubkey: Pubkey,
+    pub slot: u64,
+}
+
 #[derive(Debug, Error)]
 pub enum ClientError {
     #[error("Account not found")]
@@ -435,6 +694,31 @@ pub enum ClientError {
     LogParseError(String),
     #[error(transparent)]
     IOError(#[from] std::io::Error),
+    #[error("IDL error: {0}")]
+    IdlError(String),
+    #[error("{0}")]
+    CompileError(#[from] CompileError),
+    #[error("{0}")]
+    SignerError(#[from] SignerError),
+    #[error("Backend error: {0}")]
+    BackendError(String),
+    #[error("Unable to plan transactions: {0}")]
+    PlanError(String),
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+    #[error("Keystore error: {0}")]
+    KeystoreError(String),
+    #[error("Incremental realloc failed: {0}")]
+    ReallocError(String),
+    /// A transaction failed with a custom error of the program, or of Anchor.
+    #[error("Error Code: {name}. Error Number: {code}. Error Message: {msg}.")]
+    Program {
+        code: u32,
+        name: String,
+        msg: String,
+        origin: Option<LoggedErrorOrigin>,
+        compared: Option<(String, String)>,
+    },
 }
 
 /// `RequestBuilder` provides a builder interface to create and send
@@ -449,6 +733,14 @@ pub struct RequestBuilder<'a, C> {
     // Serialized instruction data for the target RPC.
     instruction_data: Option<Vec<u8>>,
     signers: Vec<&'a dyn Signer>,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+    compute_budget: ComputeBudget,
+    auto_compute_budget: Option<AutoComputeBudget>,
+    // The nonce account and its authority.
+    nonce: Option<(Pubkey, Pubkey)>,
+    retry: Option<RetryConfig>,
+    program_errors: Option<ErrorLookup>,
+    backend: Option<Arc<dyn Backend>>,
     #[cfg(not(feature = "async"))]
     handle: &'a Handle,
 }
@@ -466,6 +758,14 @@ impl<'a, C: Deref<Target = impl Signer> + Clone> RequestBuilder<'a, C> {
         self
     }
 
+    /// Sends the request with `backend` instead of the RPC of the cluster,
+    /// see [`Backend`].
+    #[must_use]
+    pub fn backend(mut self, backend: Arc<dyn Backend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
     #[must_use]
     pub fn instruction(mut self, ix: Instruction) -> Self {
         self.instructions.push(ix);
@@ -503,7 +803,126 @@ impl<'a, C: Deref<Target = impl Signer> + Clone> RequestBuilder<'a, C> {
         self
     }
 
+    /// Adds an address lookup table to compile versioned transactions with.
+    #[must_use]
+    pub fn lookup_table(mut self, table: AddressLookupTableAccount) -> Self {
+        self.lookup_tables.push(table);
+        self
+    }
+
+    /// Sets the compute unit limit of the transaction.
+    #[must_use]
+    pub fn compute_unit_limit(mut self, units: u32) -> Self {
+        self.compute_budget.unit_limit = Some(units);
+        self
+    }
+
+    /// Sets the priority fee of the transaction, in micro-lamports per
+    /// compute unit.
+    #[must_use]
+    pub fn compute_unit_price(mut self, micro_lamports: u64) -> Self {
+        self.compute_budget.unit_price = Some(micro_lamports);
+        self
+    }
+
+    /// Estimates the compute unit limit and price when sending the request,
+    /// see [`AutoComputeBudget`]. A limit or price set explicitly is kept.
+    #[must_use]
+    pub fn auto_compute_budget(mut self, config: AutoComputeBudget) -> Self {
+        self.auto_compute_budget = Some(config);
+        self
+    }
+
+    /// Uses the durable nonce stored in `nonce_account` instead of a recent
+    /// blockhash, for the transaction to stay valid until the nonce is
+    /// advanced. `authority` must sign, as payer or signer of the request.
+    #[must_use]
+    pub fn nonce(mut self, nonce_account: Pubkey, authority: Pubkey) -> Self {
+        self.nonce = Some((nonce_account, authority));
+        self
+    }
+
+    /// Rebroadcasts the transaction until it lands when sending it, signing
+    /// it again if it expires, see [`RetryConfig`].
+    #[must_use]
+    pub fn retry(mut self, config: RetryConfig) -> Self {
+        self.retry = Some(config);
+        self
+    }
+
+    /// Decodes the custom errors of failed transactions into
+    /// [`ClientError::Program`] with the program's `#[error_code]` enum.
+    /// Errors of Anchor, and errors logged by the program, are decoded
+    /// without it.
+    #[must_use]
+    pub fn error_code<E: ProgramErrorCode>(mut self) -> Self {
+        self.program_errors = Some(ErrorLookup::of_type::<E>());
+        self
+    }
+
+    /// Decodes the custom errors of failed transactions into
+    /// [`ClientError::Program`] with the errors of the program's IDL.
+    #[must_use]
+    pub fn idl_errors(mut self, idl: &Idl) -> Self {
+        self.program_errors = Some(ErrorLookup::Idl(idl.errors.clone().unwrap_or_default()));
+        self
+    }
+
+    /// Returns the instructions of the request, preceded by the instruction
+    /// advancing the nonce and the compute budget instructions of the limit
+    /// and price set.
     pub fn instructions(&self) -> Result<Vec<Instruction>, ClientError> {
+        Ok(self.instructions_with_compute_budget(&self.compute_budget))
+    }
+
+    fn instructions_with_compute_budget(&self, compute_budget: &ComputeBudget) -> Vec<Instruction> {
+        let mut instructions = Vec::new();
+        // Advancing the nonce must be the first instruction.
+        if let Some((nonce_account, authority)) = &self.nonce {
+            instructions.push(system_instruction::advance_nonce_account(
+                nonce_account,
+                authority,
+            ));
+        }
+        instructions.append(&mut compute_budget.instructions());
+        instructions.append(&mut self.request_instructions());
+        instructions
+    }
+
+    // Returns the backend of the request, the RPC of the cluster by default.
+    fn get_backend(&self) -> Arc<dyn Backend> {
+        match &self.backend {
+            Some(backend) => backend.clone(),
+            None => Arc::new(RpcBackend::new(&self.cluster, self.options)),
+        }
+    }
+
+    // Returns the URL of the RPC of the request's backend, for `operation`
+    // relying on RPC methods, failing with backends other than RPC ones.
+    fn rpc_url(&self, operation: &str) -> Result<String, ClientError> {
+        match &self.backend {
+            Some(backend) => backend.rpc_url().ok_or_else(|| {
+                ClientError::BackendError(format!("{operation} require an RPC backend"))
+            }),
+            None => Ok(self.cluster.clone()),
+        }
+    }
+
+    // Decodes the errors raised by the program, `program_ids` being the
+    // programs of the instructions of the failed transaction.
+    fn decode_error(&self, err: ClientError, program_ids: &[Pubkey]) -> ClientError {
+        match err {
+            ClientError::SolanaClientError(err) => program_error::decode_program_error(
+                err,
+                program_ids,
+                Some(&self.program_id),
+                self.program_errors.as_ref(),
+            ),
+            err => err,
+        }
+    }
+
+    fn request_instructions(&self) -> Vec<Instruction> {
         let mut instructions = self.instructions.clone();
         if let Some(ix_data) = &self.instruction_data {
             instructions.push(Instruction {
@@ -513,61 +932,468 @@ impl<'a, C: Deref<Target = impl Signer> + Clone> RequestBuilder<'a, C> {
             });
         }
 
-        Ok(instructions)
+        instructions
     }
 
     fn signed_transaction_with_blockhash(
         &self,
+        instructions: &[Instruction],
         latest_hash: Hash,
     ) -> Result<Transaction, ClientError> {
-        let instructions = self.instructions()?;
         let mut signers = self.signers.clone();
         signers.push(&*self.payer);
 
-        let tx = Transaction::new_signed_with_payer(
-            &instructions,
-            Some(&self.payer.pubkey()),
-            &signers,
+        let mut tx = Transaction::new_with_payer(instructions, Some(&self.payer.pubkey()));
+        tx.try_sign(&signers, latest_hash)?;
+
+        Ok(tx)
+    }
+
+    fn signed_versioned_transaction_with_blockhash(
+        &self,
+        instructions: &[Instruction],
+        latest_hash: Hash,
+    ) -> Result<VersionedTransaction, ClientError> {
+        let mut signers = self.signers.clone();
+        signers.push(&*self.payer);
+
+        let message = v0::Message::try_compile(
+            &self.payer.pubkey(),
+            instructions,
+            &self.lookup_tables,
             latest_hash,
-        );
+        )?;
+        let tx = VersionedTransaction::try_new(VersionedMessage::V0(message), &signers)?;
 
         Ok(tx)
     }
 
+    /// Returns the addresses of the accounts of the request that can be
+    /// looked up from an address lookup table, i.e. all accounts except
+    /// signers and invoked programs.
+    pub fn lookup_table_addresses(&self) -> Result<Vec<Pubkey>, ClientError> {
+        let instructions = self.instructions()?;
+        let program_ids = program_ids(&instructions);
+        let mut addresses = Vec::new();
+        for meta in instructions.iter().flat_map(|ix| &ix.accounts) {
+            if !meta.is_signer
+                && meta.pubkey != self.payer.pubkey()
+                && !program_ids.contains(&meta.pubkey)
+                && !addresses.contains(&meta.pubkey)
+            {
+                addresses.push(meta.pubkey);
+            }
+        }
+
+        Ok(addresses)
+    }
+
     pub fn transaction(&self) -> Result<Transaction, ClientError> {
         let instructions = &self.instructions;
         let tx = Transaction::new_with_payer(instructions, Some(&self.payer.pubkey()));
         Ok(tx)
     }
 
-    async fn signed_transaction_internal(&self) -> Result<Transaction, ClientError> {
-        let latest_hash =
-            AsyncRpcClient::new_with_commitment(self.cluster.to_owned(), self.options)
-                .get_latest_blockhash()
+    // Returns the instructions of the request, with the compute budget
+    // estimated if `auto_compute_budget` is set.
+    async fn instructions_internal(
+        &self,
+        backend: &dyn Backend,
+    ) -> Result<Vec<Instruction>, ClientError> {
+        let auto = match self.auto_compute_budget {
+            Some(auto) => auto,
+            None => return self.instructions(),
+        };
+        let mut compute_budget = self.compute_budget;
+        let instructions = self.request_instructions();
+
+        if compute_budget.unit_limit.is_none() {
+            // Simulate with the maximum limit, the default one being lower.
+            let simulated = ComputeBudget {
+                unit_limit: Some(MAX_COMPUTE_UNIT_LIMIT),
+                unit_price: None,
+            };
+            let mut simulated_instructions = simulated.instructions();
+            simulated_instructions.extend(instructions.iter().cloned());
+            let simulation = self
+                .simulate_instructions_internal(backend, &simulated_instructions)
                 .await?;
-        let tx = self.signed_transaction_with_blockhash(latest_hash)?;
+            if let Some(err) = simulation.err {
+                return Err(SolanaClientError::from(err).into());
+            }
+            let units_consumed = simulation.units_consumed.ok_or_else(|| {
+                ClientError::LogParseError("Simulation returned no units consumed".to_string())
+            })?;
+            compute_budget.unit_limit = Some(auto.unit_limit(units_consumed));
+        }
+        if compute_budget.unit_price.is_none() {
+            let mut writable = Vec::new();
+            for meta in instructions.iter().flat_map(|ix| &ix.accounts) {
+                if meta.is_writable && !writable.contains(&meta.pubkey) {
+                    writable.push(meta.pubkey);
+                }
+            }
+            let fees = backend.get_recent_prioritization_fees(&writable).await?;
+            compute_budget.unit_price = Some(auto.unit_price(&fees)).filter(|price| *price > 0);
+        }
+
+        Ok(self.instructions_with_compute_budget(&compute_budget))
+    }
+
+    // Returns the blockhash to sign with, the durable nonce if set.
+    async fn latest_hash_internal(&self, backend: &dyn Backend) -> Result<Hash, ClientError> {
+        match &self.nonce {
+            Some((nonce_account, _)) => {
+                let account = backend
+                    .get_account(nonce_account)
+                    .await?
+                    .ok_or(ClientError::AccountNotFound)?;
+                retry::nonce_of(&account)
+            }
+            None => backend.get_latest_blockhash().await,
+        }
+    }
+
+    // Returns the blockhash to sign with, and until when it's valid.
+    async fn blockhash_internal(
+        &self,
+        rpc_client: &AsyncRpcClient,
+    ) -> Result<(Hash, Lifetime), ClientError> {
+        match &self.nonce {
+            Some((nonce_account, _)) => {
+                // Read at the commitment the lifetime expires at, for an
+                // unchanged nonce not to be mistaken for an advanced one.
+                let nonce =
+                    retry::get_nonce(rpc_client, nonce_account, CommitmentConfig::finalized())
+                        .await?;
+                let lifetime = Lifetime::Nonce {
+                    nonce_account: *nonce_account,
+                    nonce,
+                };
+                Ok((nonce, lifetime))
+            }
+            None => {
+                let (latest_hash, last_valid_block_height) = rpc_client
+                    .get_latest_blockhash_with_commitment(self.options)
+                    .await?;
+                Ok((latest_hash, Lifetime::BlockHeight(last_valid_block_height)))
+            }
+        }
+    }
+
+    // Sends transactions signed by `sign` until one lands, see `RetryConfig`.
+    async fn send_with_retry_internal<T: SerializableTransaction>(
+        &self,
+        rpc_client: &AsyncRpcClient,
+        retry: RetryConfig,
+        sign: impl Fn(Hash) -> Result<T, ClientError>,
+    ) -> Result<Signature, ClientError> {
+        let mut resigns = 0;
+        loop {
+            let (latest_hash, lifetime) = self.blockhash_internal(rpc_client).await?;
+            let tx = sign(latest_hash)?;
+            let signature = *tx.get_signature();
+            // Only the first broadcast is preflighted, the transaction may
+            // have landed by the next ones.
+            let mut config = RpcSendTransactionConfig {
+                preflight_commitment: Some(self.options.commitment),
+                max_retries: Some(0),
+                ..RpcSendTransactionConfig::default()
+            };
+            loop {
+                rpc_client.send_transaction_with_config(&tx, config).await?;
+                config.skip_preflight = true;
+                tokio::time::sleep(retry.rebroadcast_interval).await;
+
+                match rpc_client.get_signature_statuses(&[signature]).await?.value[0] {
+                    Some(ref status) => {
+                        if let Some(err) = &status.err {
+                            return Err(SolanaClientError::from(err.clone()).into());
+                        }
+                        if status.satisfies_commitment(self.options) {
+                            return Ok(signature);
+                        }
+                    }
+                    None => {
+                        if lifetime.is_expired(rpc_client).await? {
+                            break;
+                        }
+                    }
+                }
+            }
+
+            // The transaction can no longer land, unless it already has.
+            if let Some(status) = &rpc_client
+                .get_signature_statuses_with_history(&[signature])
+                .await?
+                .value[0]
+            {
+                return match &status.err {
+                    Some(err) => Err(SolanaClientError::from(err.clone()).into()),
+                    None => Ok(signature),
+                };
+            }
+            if resigns == retry.max_resigns {
+                return Err(SolanaClientError::from(TransactionError::BlockhashNotFound).into());
+            }
+            resigns += 1;
+        }
+    }
+
+    async fn signed_transaction_internal(&self) -> Result<Transaction, ClientError> {
+        let backend = self.get_backend();
+        let instructions = self.instructions_internal(&*backend).await?;
+        let latest_hash = self.latest_hash_internal(&*backend).await?;
+        let tx = self.signed_transaction_with_blockhash(&instructions, latest_hash)?;
 
         Ok(tx)
     }
 
     async fn send_internal(&self) -> Result<Signature, ClientError> {
-        let rpc_client = AsyncRpcClient::new_with_commitment(self.cluster.to_owned(), self.options);
-        let latest_hash = rpc_client.get_latest_blockhash().await?;
-        let tx = self.signed_transaction_with_blockhash(latest_hash)?;
+        let backend = self.get_backend();
+        let instructions = self.instructions_internal(&*backend).await?;
+        if let Some(retry) = self.retry {
+            let rpc_client =
+                AsyncRpcClient::new_with_commitment(self.rpc_url("Retries")?, self.options);
+            return self
+                .send_with_retry_internal(&rpc_client, retry, |latest_hash| {
+                    self.signed_transaction_with_blockhash(&instructions, latest_hash)
+                })
+                .await
+                .map_err(|e| self.decode_error(e, &program_ids(&instructions)));
+        }
+        let latest_hash = self.latest_hash_internal(&*backend).await?;
+        let tx = self.signed_transaction_with_blockhash(&instructions, latest_hash)?;
 
-        rpc_client
-            .send_and_confirm_transaction(&tx)
+        backend
+            .send_transaction(&tx.into())
+            .await
+            .map_err(|e| self.decode_error(e, &program_ids(&instructions)))
+    }
+
+    async fn simulate_internal(&self) -> Result<Simulation, ClientError> {
+        self.simulate_instructions_internal(&*self.get_backend(), &self.instructions()?)
+            .await
+    }
+
+    async fn simulate_instructions_internal(
+        &self,
+        backend: &dyn Backend,
+        instructions: &[Instruction],
+    ) -> Result<Simulation, ClientError> {
+        // Signatures aren't verified by simulations, so the transaction is
+        // left unsigned rather than asking the signers to sign it.
+        let latest_hash = self.latest_hash_internal(backend).await?;
+        let mut tx = Transaction::new_with_payer(instructions, Some(&self.payer.pubkey()));
+        tx.message.recent_blockhash = latest_hash;
+        let mut simulation = backend.simulate_transaction(&tx.into()).await?;
+        simulation.program_id = self.program_id;
+        Ok(simulation)
+    }
+
+    async fn signed_versioned_transaction_internal(
+        &self,
+    ) -> Result<VersionedTransaction, ClientError> {
+        let backend = self.get_backend();
+        let instructions = self.instructions_internal(&*backend).await?;
+        let latest_hash = self.latest_hash_internal(&*backend).await?;
+        let tx = self.signed_versioned_transaction_with_blockhash(&instructions, latest_hash)?;
+
+        Ok(tx)
+    }
+
+    async fn partial_transaction_internal(&self) -> Result<PartialTransaction, ClientError> {
+        let backend = self.get_backend();
+        let instructions = self.instructions_internal(&*backend).await?;
+        let latest_hash = self.latest_hash_internal(&*backend).await?;
+        let payer = self.payer.pubkey();
+        let message = if self.lookup_tables.is_empty() {
+            VersionedMessage::Legacy(Message::new_with_blockhash(
+                &instructions,
+                Some(&payer),
+                &latest_hash,
+            ))
+        } else {
+            VersionedMessage::V0(v0::Message::try_compile(
+                &payer,
+                &instructions,
+                &self.lookup_tables,
+                latest_hash,
+            )?)
+        };
+        let num_signatures = usize::from(message.header().num_required_signatures);
+        let mut partial = PartialTransaction::new(VersionedTransaction {
+            signatures: vec![Signature::default(); num_signatures],
+            message,
+        });
+
+        let mut signers = self.signers.clone();
+        signers.push(&*self.payer);
+        let data = partial.transaction().message.serialize();
+        for signer in signers {
+            let pubkey = signer.try_pubkey()?;
+            if !partial.missing_signers().contains(&pubkey) {
+                continue;
+            }
+            // Signers without their key, e.g. `NullSigner`, sign with the
+            // default signature, leaving theirs missing.
+            let signature = signer.try_sign_message(&data)?;
+            if signature != Signature::default() {
+                partial.add_signature(pubkey, signature)?;
+            }
+        }
+
+        Ok(partial)
+    }
+
+    async fn send_partial_transaction_internal(
+        &self,
+        partial: &PartialTransaction,
+    ) -> Result<Signature, ClientError> {
+        if !partial.is_signed() {
+            return Err(SignerError::NotEnoughSigners.into());
+        }
+        if partial.transaction().verify_with_results().contains(&false) {
+            return Err(SignerError::PresignerError(PresignerError::VerificationFailure).into());
+        }
+
+        self.get_backend()
+            .send_transaction(partial.transaction())
+            .await
+            .map_err(|e| self.decode_error(e, &message_program_ids(&partial.transaction().message)))
+    }
+
+    async fn send_versioned_internal(&self) -> Result<Signature, ClientError> {
+        let backend = self.get_backend();
+        let instructions = self.instructions_internal(&*backend).await?;
+        if let Some(retry) = self.retry {
+            let rpc_client =
+                AsyncRpcClient::new_with_commitment(self.rpc_url("Retries")?, self.options);
+            return self
+                .send_with_retry_internal(&rpc_client, retry, |latest_hash| {
+                    self.signed_versioned_transaction_with_blockhash(&instructions, latest_hash)
+                })
+                .await
+                .map_err(|e| self.decode_error(e, &program_ids(&instructions)));
+        }
+        let latest_hash = self.latest_hash_internal(&*backend).await?;
+        let tx = self.signed_versioned_transaction_with_blockhash(&instructions, latest_hash)?;
+
+        backend
+            .send_transaction(&tx)
             .await
-            .map_err(Into::into)
+            .map_err(|e| self.decode_error(e, &program_ids(&instructions)))
+    }
+
+    async fn create_lookup_table_internal(&self) -> Result<AddressLookupTableAccount, ClientError> {
+        let rpc_client = AsyncRpcClient::new_with_commitment(
+            self.rpc_url("Lookup table creations")?,
+            self.options,
+        );
+        let payer = self.payer.pubkey();
+        let addresses = self.lookup_table_addresses()?;
+        let recent_slot = rpc_client
+            .get_slot_with_commitment(CommitmentConfig::finalized())
+            .await?;
+        let (create_ix, key) = lookup_table::create_lookup_table(payer, payer, recent_slot);
+
+        // The table is created along with its first chunk of addresses.
+        let mut instructions = vec![create_ix];
+        let mut chunks = addresses.chunks(LOOKUP_TABLE_EXTEND_CHUNK_LEN).peekable();
+        loop {
+            if let Some(chunk) = chunks.next() {
+                instructions.push(lookup_table::extend_lookup_table(
+                    key,
+                    payer,
+                    Some(payer),
+                    chunk.to_vec(),
+                ));
+            }
+            let latest_hash = rpc_client.get_latest_blockhash().await?;
+            let mut tx = Transaction::new_with_payer(&instructions, Some(&payer));
+            tx.try_sign(&[&*self.payer], latest_hash)?;
+            rpc_client.send_and_confirm_transaction(&tx).await?;
+            if chunks.peek().is_none() {
+                return Ok(AddressLookupTableAccount { key, addresses });
+            }
+            instructions.clear();
+        }
+    }
+
+    async fn send_realloc_incremental_internal(
+        &self,
+        account: Pubkey,
+        target_len: usize,
+    ) -> Result<Vec<Signature>, ClientError> {
+        let backend = RpcBackend::new(&self.rpc_url("Incremental reallocations")?, self.options);
+        let rpc_client = backend.rpc();
+        let instructions = self.instructions_internal(&backend).await?;
+        // Every transaction but the last grows the account by the maximum
+        // permitted step, the last one runs the instruction handler.
+        let mut len = self.data_len(rpc_client, &account).await?;
+        let max_sends = (target_len.saturating_sub(len) + MAX_PERMITTED_DATA_INCREASE - 1)
+            / MAX_PERMITTED_DATA_INCREASE
+            + 1;
+        let mut signatures = Vec::new();
+        let (mut latest_hash, _) = self.blockhash_internal(rpc_client).await?;
+        loop {
+            let tx = self.signed_transaction_with_blockhash(&instructions, latest_hash)?;
+            let signature = rpc_client
+                .send_and_confirm_transaction(&tx)
+                .await
+                .map_err(|e| self.decode_error(e.into(), &program_ids(&instructions)))?;
+            signatures.push(signature);
+
+            let new_len = self.data_len(rpc_client, &account).await?;
+            if new_len >= target_len {
+                return Ok(signatures);
+            }
+            if new_len <= len {
+                return Err(ClientError::ReallocError(format!(
+                    "account {account} did not grow past {new_len} bytes"
+                )));
+            }
+            if signatures.len() >= max_sends {
+                return Err(ClientError::ReallocError(format!(
+                    "account {account} did not reach {target_len} bytes after {max_sends} transactions"
+                )));
+            }
+            len = new_len;
+
+            // Resending the same instructions requires a fresh blockhash,
+            // durable nonces being advanced by each transaction.
+            latest_hash = match &self.nonce {
+                Some((nonce_account, _)) => {
+                    retry::get_nonce(rpc_client, nonce_account, self.options).await?
+                }
+                None => rpc_client.get_new_latest_blockhash(&latest_hash).await?,
+            };
+        }
+    }
+
+    async fn data_len(
+        &self,
+        rpc_client: &AsyncRpcClient,
+        account: &Pubkey,
+    ) -> Result<usize, ClientError> {
+        Ok(rpc_client
+            .get_account_with_commitment(account, self.options)
+            .await?
+            .value
+            .ok_or(ClientError::AccountNotFound)?
+            .data
+            .len())
     }
 
     async fn send_with_spinner_and_config_internal(
         &self,
         config: RpcSendTransactionConfig,
     ) -> Result<Signature, ClientError> {
-        let rpc_client = AsyncRpcClient::new_with_commitment(self.cluster.to_owned(), self.options);
-        let latest_hash = rpc_client.get_latest_blockhash().await?;
-        let tx = self.signed_transaction_with_blockhash(latest_hash)?;
+        let backend = RpcBackend::new(&self.rpc_url("Sends with a spinner")?, self.options);
+        let rpc_client = backend.rpc();
+        let instructions = self.instructions_internal(&backend).await?;
+        let (latest_hash, _) = self.blockhash_internal(rpc_client).await?;
+        let tx = self.signed_transaction_with_blockhash(&instructions, latest_hash)?;
 
         rpc_client
             .send_and_confirm_transaction_with_spinner_and_config(
@@ -576,76 +1402,788 @@ impl<'a, C: Deref<Target = impl Signer> + Clone> RequestBuilder<'a, C> {
                 config,
             )
             .await
-            .map_err(Into::into)
+            .map_err(|e| self.decode_error(e.into(), &program_ids(&instructions)))
     }
 }
 
+// Returns the programs of `instructions`, in order.
+fn program_ids(instructions: &[Instruction]) -> Vec<Pubkey> {
+    instructions.iter().map(|ix| ix.program_id).collect()
+}
+
 fn parse_logs_response<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
     logs: RpcResponse<RpcLogsResponse>,
     program_id_str: &str,
 ) -> Vec<T> {
-    let mut logs = &logs.value.logs[..];
-    let mut events: Vec<T> = Vec::new();
-    if !logs.is_empty() {
-        if let Ok(mut execution) = Execution::new(&mut logs) {
-            for l in logs {
-                // Parse the log.
-                let (event, new_program, did_pop) = {
-                    if program_id_str == execution.program() {
-                        handle_program_log(program_id_str, l).unwrap_or_else(|e| {
-                            println!("Unable to parse log: {e}");
-                            std::process::exit(1);
-                        })
-                    } else {
-                        let (program, did_pop) = handle_system_log(program_id_str, l);
-                        (None, program, did_pop)
+    parse_logs(&logs.value.logs, program_id_str)
+}
+
+// Decodes the events of type `T` emitted by the program in `logs`.
+// Events that don't deserialize are skipped.
+fn parse_logs<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
+    logs: &[String],
+    program_id_str: &str,
+) -> Vec<T> {
+    let program_id = match Pubkey::from_str(program_id_str) {
+        Ok(program_id) => program_id,
+        Err(_) => return Vec::new(),
+    };
+    let tree = InvocationTree::parse(logs);
+    logs::emitted_events(
+        tree.iter()
+            .filter(|invocation| invocation.program_id == program_id),
+    )
+    .into_iter()
+    .filter_map(|event| {
+        event
+            .map_err(|_e| {
+                #[cfg(feature = "debug")]
+                println!("Unable to parse log: {_e}");
+            })
+            .ok()
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn parse_program_events() {
+        use anchor_lang::prelude::borsh;
+        use anchor_lang::{AnchorDeserialize, AnchorSerialize};
+
+        #[anchor_lang::event]
+        struct Counted {
+            count: u64,
+        }
+        let event_log = |count: u64| {
+            let mut data = Counted::discriminator().to_vec();
+            data.extend(Counted { count }.try_to_vec().unwrap());
+            format!(
+                "Program data: {}",
+                anchor_lang::__private::base64::encode(data)
+            )
+        };
+
+        let program = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let logs: Vec<String> = vec![
+            format!("Program {other} invoke [1]"),
+            event_log(1),
+            format!("Program {program} invoke [2]"),
+            event_log(2),
+            format!("Program {program} success"),
+            format!("Program {other} success"),
+            format!("Program {program} invoke [1]"),
+            "Program data: not base64".to_string(),
+            event_log(3),
+        ];
+        let counts: Vec<u64> = parse_logs::<Counted>(&logs, &program.to_string())
+            .iter()
+            .map(|event| event.count)
+            .collect();
+        assert_eq!(counts, vec![2, 3]);
+        assert!(parse_logs::<Counted>(&logs, "not a program").is_empty());
+    }
+
+    #[test]
+    fn versioned_transaction_with_lookup_table() {
+        use solana_sdk::signature::Keypair;
+
+        let payer = Arc::new(Keypair::new());
+        let signer = Keypair::new();
+        let program_id = Pubkey::new_unique();
+        let accounts = vec![
+            AccountMeta::new(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            AccountMeta::new(signer.pubkey(), true),
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new_readonly(program_id, false),
+        ];
+        #[cfg(not(feature = "async"))]
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let request = RequestBuilder::from(
+            program_id,
+            "http://localhost:8899",
+            payer,
+            None,
+            #[cfg(not(feature = "async"))]
+            rt.handle(),
+        )
+        .instruction(Instruction {
+            program_id,
+            accounts: accounts.clone(),
+            data: vec![],
+        })
+        .signer(&signer);
+
+        let addresses = request.lookup_table_addresses().unwrap();
+        assert_eq!(addresses, vec![accounts[0].pubkey, accounts[1].pubkey]);
+
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses,
+        };
+        let instructions = request.instructions().unwrap();
+        let tx = request
+            .lookup_table(table.clone())
+            .signed_versioned_transaction_with_blockhash(&instructions, Hash::default())
+            .unwrap();
+        match tx.message {
+            VersionedMessage::V0(message) => {
+                assert_eq!(message.address_table_lookups.len(), 1);
+                assert_eq!(message.address_table_lookups[0].account_key, table.key);
+                assert_eq!(message.address_table_lookups[0].writable_indexes, vec![0]);
+                assert_eq!(message.address_table_lookups[0].readonly_indexes, vec![1]);
+            }
+            VersionedMessage::Legacy(_) => panic!("Expected a v0 message"),
+        }
+        assert_eq!(tx.signatures.len(), 2);
+    }
+
+    #[test]
+    fn auto_compute_budget() {
+        use serde_json::json;
+        use solana_sdk::compute_budget::ComputeBudgetInstruction;
+        use solana_sdk::signature::Keypair;
+
+        let rpc = test_rpc::TestRpc::start(|method, _| match method {
+            "getLatestBlockhash" => Some(json!({
+                "context": { "slot": 1 },
+                "value": {
+                    "blockhash": Hash::new_unique().to_string(),
+                    "lastValidBlockHeight": 100,
+                },
+            })),
+            "simulateTransaction" => Some(json!({
+                "context": { "slot": 1 },
+                "value": { "err": null, "logs": [], "unitsConsumed": 10_000 },
+            })),
+            "getRecentPrioritizationFees" => Some(json!([
+                { "slot": 1, "prioritizationFee": 300 },
+                { "slot": 2, "prioritizationFee": 100 },
+                { "slot": 3, "prioritizationFee": 200 },
+            ])),
+            _ => None,
+        });
+        let payer = Arc::new(Keypair::new());
+        let program_id = Pubkey::new_unique();
+        let writable = Pubkey::new_unique();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let request = RequestBuilder::from(
+            program_id,
+            rpc.url(),
+            payer,
+            None,
+            #[cfg(not(feature = "async"))]
+            rt.handle(),
+        )
+        .instruction(Instruction {
+            program_id,
+            accounts: vec![AccountMeta::new(writable, false)],
+            data: vec![],
+        })
+        .auto_compute_budget(AutoComputeBudget::default());
+        let assert_compute_budget = |tx: &Transaction, expected: &[Instruction]| {
+            assert_eq!(tx.message.instructions.len(), expected.len() + 1);
+            for (compiled, expected) in tx.message.instructions.iter().zip(expected) {
+                assert_eq!(
+                    *compiled.program_id(&tx.message.account_keys),
+                    expected.program_id
+                );
+                assert_eq!(compiled.data, expected.data);
+            }
+        };
+
+        let tx = rt.block_on(request.signed_transaction_internal()).unwrap();
+        assert_compute_budget(
+            &tx,
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(11_000),
+                ComputeBudgetInstruction::set_compute_unit_price(200),
+            ],
+        );
+        assert_eq!(
+            rpc.requests("getRecentPrioritizationFees"),
+            vec![json!([[writable.to_string()]])]
+        );
+
+        // Explicit settings aren't estimated.
+        let request = request.compute_unit_limit(50_000).compute_unit_price(1);
+        let tx = rt.block_on(request.signed_transaction_internal()).unwrap();
+        assert_compute_budget(
+            &tx,
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(50_000),
+                ComputeBudgetInstruction::set_compute_unit_price(1),
+            ],
+        );
+        assert_eq!(rpc.requests("getRecentPrioritizationFees").len(), 1);
+
+        // The estimate is simulated without signing.
+        let simulated = rpc.requests("simulateTransaction");
+        assert_eq!(simulated.len(), 1);
+        let simulated =
+            anchor_lang::__private::base64::decode(simulated[0][0].as_str().unwrap()).unwrap();
+        let simulated: VersionedTransaction = bincode::deserialize(&simulated).unwrap();
+        assert_eq!(simulated.signatures, vec![Signature::default()]);
+    }
+
+    #[test]
+    fn retry_signs_again_once_expired() {
+        use serde_json::json;
+        use solana_sdk::signature::Keypair;
+        use std::sync::Mutex;
+        use std::time::Duration;
+
+        // The first transaction never lands, its blockhash expiring at the
+        // block height of 10.
+        let sent = Arc::new(Mutex::new(Vec::<String>::new()));
+        let rpc = {
+            let sent = sent.clone();
+            test_rpc::TestRpc::start(move |method, params| match method {
+                "getLatestBlockhash" => Some(json!({
+                    "context": { "slot": 1 },
+                    "value": {
+                        "blockhash": Hash::new_unique().to_string(),
+                        "lastValidBlockHeight": 10 * (sent.lock().unwrap().len() + 1),
+                    },
+                })),
+                "getBlockHeight" => Some(json!(11)),
+                "sendTransaction" => {
+                    let tx = anchor_lang::__private::base64::decode(params[0].as_str()?).ok()?;
+                    let signature = Signature::try_from(&tx[1..65]).ok()?.to_string();
+                    let mut sent = sent.lock().unwrap();
+                    if !sent.contains(&signature) {
+                        sent.push(signature.clone());
                     }
-                };
-                // Emit the event.
-                if let Some(e) = event {
-                    events.push(e);
+                    Some(json!(signature))
                 }
-                // Switch program context on CPI.
-                if let Some(new_program) = new_program {
-                    execution.push(new_program);
+                "getSignatureStatuses" => {
+                    let landed =
+                        sent.lock().unwrap().get(1).map(String::as_str) == params[0][0].as_str();
+                    let status = json!({
+                        "slot": 1,
+                        "confirmations": null,
+                        "err": null,
+                        "status": { "Ok": null },
+                        "confirmationStatus": "finalized",
+                    });
+                    Some(json!({
+                        "context": { "slot": 1 },
+                        "value": [if landed { status } else { JsonValue::Null }],
+                    }))
                 }
-                // Program returned.
-                if did_pop {
-                    execution.pop();
+                _ => None,
+            })
+        };
+        let payer = Arc::new(Keypair::new());
+        let program_id = Pubkey::new_unique();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let request = RequestBuilder::from(
+            program_id,
+            rpc.url(),
+            payer,
+            None,
+            #[cfg(not(feature = "async"))]
+            rt.handle(),
+        )
+        .instruction(Instruction {
+            program_id,
+            accounts: vec![],
+            data: vec![],
+        })
+        .retry(RetryConfig {
+            rebroadcast_interval: Duration::from_millis(1),
+            max_resigns: 1,
+        });
+
+        let signature = rt.block_on(request.send_internal()).unwrap();
+        let sent = sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(signature.to_string(), sent[1]);
+        // The expired transaction is checked for having landed before
+        // signing again.
+        assert!(rpc
+            .requests("getSignatureStatuses")
+            .iter()
+            .any(|params| params[0][0] == json!(sent[0])
+                && params[1]["searchTransactionHistory"] == json!(true)));
+    }
+
+    #[test]
+    fn retry_signs_again_once_nonce_advanced() {
+        use serde_json::json;
+        use solana_sdk::signature::Keypair;
+        use solana_sdk::system_program;
+        use std::sync::Mutex;
+        use std::time::Duration;
+
+        let nonce_account = Pubkey::new_unique();
+        let payer = Arc::new(Keypair::new());
+        let nonce_data = |nonce: Hash| {
+            let mut data = 1u32.to_le_bytes().to_vec();
+            data.extend_from_slice(&1u32.to_le_bytes());
+            data.extend_from_slice(payer.pubkey().as_ref());
+            data.extend_from_slice(nonce.as_ref());
+            data.extend_from_slice(&5000u64.to_le_bytes());
+            data
+        };
+        // The first transaction never lands, the finalized nonce being
+        // advanced once it's sent. The confirmed nonce differs from the
+        // finalized one throughout.
+        let nonces = [Hash::new_unique(), Hash::new_unique()];
+        let accounts = [
+            nonce_data(nonces[0]),
+            nonce_data(nonces[1]),
+            nonce_data(Hash::new_unique()),
+        ];
+        let sent = Arc::new(Mutex::new(Vec::<Transaction>::new()));
+        let rpc = {
+            let sent = sent.clone();
+            test_rpc::TestRpc::start(move |method, params| match method {
+                "getAccountInfo" => {
+                    let data = match params[1]["commitment"].as_str() {
+                        Some("finalized") => &accounts[sent.lock().unwrap().len().min(1)],
+                        _ => &accounts[2],
+                    };
+                    Some(json!({
+                        "context": { "slot": 1 },
+                        "value": {
+                            "data": [anchor_lang::__private::base64::encode(data), "base64"],
+                            "executable": false,
+                            "lamports": 1_447_680,
+                            "owner": system_program::ID.to_string(),
+                            "rentEpoch": 0,
+                        },
+                    }))
                 }
+                "sendTransaction" => {
+                    let tx = anchor_lang::__private::base64::decode(params[0].as_str()?).ok()?;
+                    let tx: Transaction = bincode::deserialize(&tx).ok()?;
+                    let signature = tx.signatures[0].to_string();
+                    let mut sent = sent.lock().unwrap();
+                    if !sent.contains(&tx) {
+                        sent.push(tx);
+                    }
+                    Some(json!(signature))
+                }
+                "getSignatureStatuses" => {
+                    let landed = sent
+                        .lock()
+                        .unwrap()
+                        .get(1)
+                        .map(|tx| tx.signatures[0].to_string())
+                        == params[0][0].as_str().map(str::to_string);
+                    let status = json!({
+                        "slot": 1,
+                        "confirmations": null,
+                        "err": null,
+                        "status": { "Ok": null },
+                        "confirmationStatus": "finalized",
+                    });
+                    Some(json!({
+                        "context": { "slot": 1 },
+                        "value": [if landed { status } else { JsonValue::Null }],
+                    }))
+                }
+                _ => None,
+            })
+        };
+        let program_id = Pubkey::new_unique();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let request = RequestBuilder::from(
+            program_id,
+            rpc.url(),
+            payer.clone(),
+            Some(CommitmentConfig::confirmed()),
+            #[cfg(not(feature = "async"))]
+            rt.handle(),
+        )
+        .instruction(Instruction {
+            program_id,
+            accounts: vec![],
+            data: vec![],
+        })
+        .nonce(nonce_account, payer.pubkey())
+        .retry(RetryConfig {
+            rebroadcast_interval: Duration::from_millis(1),
+            max_resigns: 1,
+        });
+
+        let signature = rt.block_on(request.send_internal()).unwrap();
+        let sent = sent.lock().unwrap().clone();
+        let blockhashes: Vec<Hash> = sent.iter().map(|tx| tx.message.recent_blockhash).collect();
+        assert_eq!(blockhashes, nonces);
+        assert_eq!(signature, sent[1].signatures[0]);
+    }
+
+    #[test]
+    fn nonce_transaction() {
+        use serde_json::json;
+        use solana_sdk::signature::Keypair;
+        use solana_sdk::system_program;
+
+        let nonce_account = Pubkey::new_unique();
+        let nonce = Hash::new_unique();
+        let payer = Arc::new(Keypair::new());
+        // A current, initialized nonce account.
+        let mut data = 1u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(payer.pubkey().as_ref());
+        data.extend_from_slice(nonce.as_ref());
+        data.extend_from_slice(&5000u64.to_le_bytes());
+        let rpc = test_rpc::TestRpc::start(move |method, _| match method {
+            "getAccountInfo" => Some(json!({
+                "context": { "slot": 1 },
+                "value": {
+                    "data": [anchor_lang::__private::base64::encode(&data), "base64"],
+                    "executable": false,
+                    "lamports": 1_447_680,
+                    "owner": system_program::ID.to_string(),
+                    "rentEpoch": 0,
+                },
+            })),
+            _ => None,
+        });
+        let program_id = Pubkey::new_unique();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let request = RequestBuilder::from(
+            program_id,
+            rpc.url(),
+            payer.clone(),
+            None,
+            #[cfg(not(feature = "async"))]
+            rt.handle(),
+        )
+        .instruction(Instruction {
+            program_id,
+            accounts: vec![],
+            data: vec![],
+        })
+        .nonce(nonce_account, payer.pubkey());
+
+        let tx = rt.block_on(request.signed_transaction_internal()).unwrap();
+        assert_eq!(tx.message.recent_blockhash, nonce);
+        let advance_ix = system_instruction::advance_nonce_account(&nonce_account, &payer.pubkey());
+        let compiled = &tx.message.instructions[0];
+        assert_eq!(
+            *compiled.program_id(&tx.message.account_keys),
+            system_program::ID
+        );
+        assert_eq!(compiled.data, advance_ix.data);
+        assert_eq!(
+            tx.message.account_keys[compiled.accounts[0] as usize],
+            nonce_account
+        );
+        tx.verify().unwrap();
+    }
+
+    #[test]
+    fn decode_account_notifications() {
+        struct Counter(u64);
+        impl AccountDeserialize for Counter {
+            fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+                anchor_lang::AnchorDeserialize::deserialize(buf)
+                    .map(Counter)
+                    .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize.into())
             }
         }
+
+        let pubkey = Pubkey::new_unique();
+        let encode = |lamports: u64, data: Vec<u8>| {
+            let account = Account {
+                lamports,
+                data,
+                ..Account::default()
+            };
+            UiAccount::encode(&pubkey, &account, UiAccountEncoding::Base64, None, None)
+        };
+        let counter: Option<Counter> =
+            decode_ui_account(&encode(1, 7u64.to_le_bytes().to_vec())).unwrap();
+        assert_eq!(counter.map(|counter| counter.0), Some(7));
+        // Closed accounts are passed as `None`, others must deserialize.
+        assert!(decode_ui_account::<Counter>(&encode(0, vec![]))
+            .unwrap()
+            .is_none());
+        assert!(decode_ui_account::<Counter>(&encode(1, vec![1, 2])).is_err());
     }
-    events
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
     #[test]
-    fn new_execution() {
-        let mut logs: &[String] =
-            &["Program 7Y8VDzehoewALqJfyxZYMgYCnMTCDhWuGfJKUvjYWATw invoke [1]".to_string()];
-        let exe = Execution::new(&mut logs).unwrap();
+    #[cfg(not(feature = "async"))]
+    fn account_and_program_subscriptions() {
+        use serde_json::json;
+        use solana_sdk::signature::Keypair;
+        use std::sync::Mutex;
+        use std::time::{Duration, Instant};
+
+        struct Counter(u64);
+        impl AccountDeserialize for Counter {
+            fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+                anchor_lang::AnchorDeserialize::deserialize(buf)
+                    .map(Counter)
+                    .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize.into())
+            }
+        }
+        impl Discriminator for Counter {
+            const DISCRIMINATOR: [u8; 8] = [0; 8];
+        }
+
+        // The account is updated, overwritten with garbage, then closed.
+        let pubkey = Pubkey::new_unique();
+        let accounts: Vec<JsonValue> =
+            [(1, 7u64.to_le_bytes().to_vec()), (1, vec![1]), (0, vec![])]
+                .into_iter()
+                .map(|(lamports, data)| {
+                    let account = Account {
+                        lamports,
+                        data,
+                        ..Account::default()
+                    };
+                    let account =
+                        UiAccount::encode(&pubkey, &account, UiAccountEncoding::Base64, None, None);
+                    serde_json::to_value(account).unwrap()
+                })
+                .collect();
+        let pubsub = test_rpc::TestPubsub::start(move |operation| {
+            accounts
+                .iter()
+                .map(|account| {
+                    let value = match operation {
+                        "program" => json!({ "pubkey": pubkey.to_string(), "account": account }),
+                        _ => account.clone(),
+                    };
+                    json!({ "context": { "slot": 1 }, "value": value })
+                })
+                .collect()
+        });
+        let cluster = Cluster::Custom("http://127.0.0.1:1".to_string(), pubsub.url().to_string());
+        let client = Client::new(cluster, Arc::new(Keypair::new()));
+        let program = client.program(Pubkey::new_unique()).unwrap();
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let record = |received: &Arc<Mutex<Vec<_>>>| {
+            let received = received.clone();
+            move |ctx: &AccountContext, account: Result<Option<Counter>, ClientError>| {
+                assert_eq!(ctx.pubkey, pubkey);
+                let account = account.map(|account| account.map(|counter| counter.0));
+                received.lock().unwrap().push(account.ok());
+            }
+        };
+        let wait_for = |count: usize| {
+            let start = Instant::now();
+            while received.lock().unwrap().len() < count {
+                assert!(start.elapsed() < Duration::from_secs(10));
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        };
+
+        let unsubscriber = program
+            .account_subscribe::<Counter>(pubkey, record(&received))
+            .unwrap();
+        wait_for(3);
+        unsubscriber.unsubscribe();
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![Some(Some(7)), None, Some(None)]
+        );
+        assert_eq!(pubsub.requests("accountUnsubscribe"), vec![json!([1])]);
+
+        received.lock().unwrap().clear();
+        let unsubscriber = program
+            .program_subscribe::<Counter>(vec![], record(&received))
+            .unwrap();
+        wait_for(3);
+        unsubscriber.unsubscribe();
         assert_eq!(
-            exe.stack[0],
-            "7Y8VDzehoewALqJfyxZYMgYCnMTCDhWuGfJKUvjYWATw".to_string()
+            *received.lock().unwrap(),
+            vec![Some(Some(7)), None, Some(None)]
         );
+        assert_eq!(pubsub.requests("programUnsubscribe"), vec![json!([2])]);
     }
 
     #[test]
-    fn handle_system_log_pop() {
-        let log = "Program 7Y8VDzehoewALqJfyxZYMgYCnMTCDhWuGfJKUvjYWATw success";
-        let (program, did_pop) = handle_system_log("asdf", log);
-        assert_eq!(program, None);
-        assert!(did_pop);
+    fn send_with_backend() {
+        struct Counter(u64);
+        impl AccountDeserialize for Counter {
+            fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+                anchor_lang::AnchorDeserialize::deserialize(buf)
+                    .map(Counter)
+                    .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize.into())
+            }
+        }
+
+        let program_id = Pubkey::new_unique();
+        let counter = Pubkey::new_unique();
+        // Stores the count of the instruction data, failing for 0.
+        let backend = Arc::new(MockBackend::with_executor(move |tx, accounts| {
+            let data = tx.message.instructions()[0].data.clone();
+            if data == 0u64.to_le_bytes() {
+                return MockExecution {
+                    err: Some(TransactionError::InstructionError(
+                        0,
+                        solana_sdk::instruction::InstructionError::Custom(6000),
+                    )),
+                    logs: vec![
+                        format!("Program {program_id} invoke [1]"),
+                        "Program log: AnchorError occurred. Error Code: Zero. Error Number: 6000. Error Message: Zero count.".to_string(),
+                        format!("Program {program_id} failed: custom program error: 0x1770"),
+                    ],
+                    ..MockExecution::default()
+                };
+            }
+            let account = Account {
+                lamports: 1,
+                data,
+                owner: program_id,
+                ..Account::default()
+            };
+            accounts.insert(counter, account);
+            MockExecution::default()
+        }));
+        let client = Client::new(
+            Cluster::Localnet,
+            Arc::new(solana_sdk::signature::Keypair::new()),
+        )
+        .with_backend(backend.clone());
+        let program = client.program(program_id).unwrap();
+        let count = |count: u64| {
+            program.request().instruction(Instruction::new_with_bytes(
+                program_id,
+                &count.to_le_bytes(),
+                vec![AccountMeta::new(counter, false)],
+            ))
+        };
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(count(7).send_internal()).unwrap();
+        let account: Counter = rt.block_on(program.account_internal(counter)).unwrap();
+        assert_eq!(account.0, 7);
+
+        match rt.block_on(count(0).send_internal()) {
+            Err(ClientError::Program { code, name, .. }) => {
+                assert_eq!(code, 6000);
+                assert_eq!(name, "Zero");
+            }
+            result => panic!("Unexpected result: {result:?}"),
+        }
+        let account: Counter = rt.block_on(program.account_internal(counter)).unwrap();
+        assert_eq!(account.0, 7);
+        assert_eq!(backend.transactions().len(), 1);
     }
 
     #[test]
-    fn handle_system_log_no_pop() {
-        let log = "Program 7swsTUiQ6KUK4uFYquQKg4epFRsBnvbrTf2fZQCa2sTJ qwer";
-        let (program, did_pop) = handle_system_log("asdf", log);
-        assert_eq!(program, None);
-        assert!(!did_pop);
+    fn partial_signing() {
+        use solana_sdk::signature::{Keypair, NullSigner};
+
+        let program_id = Pubkey::new_unique();
+        let offline = Keypair::new();
+        let backend = Arc::new(MockBackend::new());
+        let client =
+            Client::new(Cluster::Localnet, Arc::new(Keypair::new())).with_backend(backend.clone());
+        let program = client.program(program_id).unwrap();
+        let null_signer = NullSigner::new(&offline.pubkey());
+        let request = program
+            .request()
+            .instruction(Instruction::new_with_bytes(
+                program_id,
+                &[1],
+                vec![AccountMeta::new_readonly(offline.pubkey(), true)],
+            ))
+            .signer(&null_signer);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let partial = rt.block_on(request.partial_transaction_internal()).unwrap();
+        assert_eq!(partial.missing_signers(), vec![offline.pubkey()]);
+        assert!(matches!(
+            rt.block_on(request.send_partial_transaction_internal(&partial)),
+            Err(ClientError::SignerError(SignerError::NotEnoughSigners))
+        ));
+
+        let encoded = partial.encode(TransactionEncoding::Base64).unwrap();
+        let mut signed = PartialTransaction::decode(&encoded, TransactionEncoding::Base64).unwrap();
+        signed.sign(&offline).unwrap();
+        rt.block_on(request.send_partial_transaction_internal(&signed))
+            .unwrap();
+        assert_eq!(backend.transactions(), vec![signed.into_transaction()]);
+    }
+
+    #[test]
+    fn rpc_requests_with_other_backends() {
+        use solana_sdk::signature::Keypair;
+
+        let program_id = Pubkey::new_unique();
+        let backend = Arc::new(MockBackend::new());
+        let client =
+            Client::new(Cluster::Localnet, Arc::new(Keypair::new())).with_backend(backend.clone());
+        let program = client.program(program_id).unwrap();
+        let request = || {
+            program
+                .request()
+                .instruction(Instruction::new_with_bytes(program_id, &[1], vec![]))
+        };
+
+        // Requests relying on RPC methods fail rather than reaching the
+        // cluster, unsent.
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        assert!(matches!(
+            rt.block_on(request().retry(RetryConfig::default()).send_internal()),
+            Err(ClientError::BackendError(_))
+        ));
+        assert!(matches!(
+            rt.block_on(request().send_realloc_incremental_internal(Pubkey::new_unique(), 1)),
+            Err(ClientError::BackendError(_))
+        ));
+        assert!(matches!(
+            rt.block_on(
+                request()
+                    .send_with_spinner_and_config_internal(RpcSendTransactionConfig::default())
+            ),
+            Err(ClientError::BackendError(_))
+        ));
+        assert!(matches!(
+            rt.block_on(request().create_lookup_table_internal()),
+            Err(ClientError::BackendError(_))
+        ));
+        assert!(backend.transactions().is_empty());
+    }
+
+    #[test]
+    fn signer_errors() {
+        struct Unreachable(Pubkey);
+        impl Signer for Unreachable {
+            fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+                Ok(self.0)
+            }
+
+            fn try_sign_message(
+                &self,
+                _message: &[u8],
+            ) -> Result<solana_sdk::signature::Signature, SignerError> {
+                Err(SignerError::Connection("unreachable".to_string()))
+            }
+
+            fn is_interactive(&self) -> bool {
+                false
+            }
+        }
+
+        let program_id = Pubkey::new_unique();
+        let backend = Arc::new(MockBackend::new());
+        let payer = Arc::new(Unreachable(Pubkey::new_unique()));
+        let client = Client::new(Cluster::Localnet, payer).with_backend(backend.clone());
+        let program = client.program(program_id).unwrap();
+        let request =
+            program
+                .request()
+                .instruction(Instruction::new_with_bytes(program_id, &[1], vec![]));
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        assert!(matches!(
+            rt.block_on(request.send_internal()),
+            Err(ClientError::SignerError(SignerError::Connection(_)))
+        ));
+        assert!(backend.transactions().is_empty());
     }
 }
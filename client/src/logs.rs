@@ -0,0 +1,337 @@
+//! Parsing of the log messages of transactions into the tree of their
+//! program invocations, see `InvocationTree`.
+
+use crate::{ClientError, PROGRAM_DATA, PROGRAM_LOG};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{AnchorDeserialize, Event};
+use regex::Regex;
+use std::str::FromStr;
+
+pub(crate) const LOG_TRUNCATED: &str = "Log truncated";
+const PROGRAM_RETURN: &str = "Program return: ";
+
+/// The program invocations of a transaction, parsed from its logs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InvocationTree {
+    /// The invocations of the instructions of the transaction, with their
+    /// CPIs.
+    pub invocations: Vec<Invocation>,
+    /// Whether the logs were truncated by the log limit. The invocations
+    /// interrupted by the truncation are kept, without a result.
+    pub truncated: bool,
+}
+
+/// A program invocation parsed from the logs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    pub program_id: Pubkey,
+    /// The depth of the invocation, 1 for instructions of the transaction
+    /// and 2 for their CPIs.
+    pub depth: usize,
+    /// The compute units consumed by the invocation, CPIs included.
+    pub units_consumed: Option<u64>,
+    /// The compute units that were left to the invocation when it started.
+    pub units_total: Option<u64>,
+    /// Whether the invocation succeeded, `None` if the logs don't say.
+    pub success: Option<bool>,
+    /// The error of a failed invocation, as logged by the runtime.
+    pub error: Option<String>,
+    /// The `msg!` logs of the program, without their `Program log: ` prefix.
+    pub logs: Vec<String>,
+    /// The data logged with `sol_log_data`, e.g. by `emit!`.
+    pub data: Vec<Vec<u8>>,
+    /// The data returned by the program, if set before it returned.
+    pub return_data: Option<Vec<u8>>,
+    /// The programs invoked through CPI.
+    pub invocations: Vec<Invocation>,
+    // The data and base64 `msg!` logs that may be events, with the position
+    // of their log in the transaction.
+    emitted: Vec<(usize, Vec<u8>)>,
+}
+
+impl InvocationTree {
+    /// Parses the logs of a transaction. Lines that can't be parsed are
+    /// skipped, and invocations left unfinished, e.g. by malformed logs, are
+    /// kept without a result.
+    pub fn parse(logs: &[String]) -> Self {
+        let invoke_re = Regex::new(r"^Program (\w+) invoke \[(\d+)\]$").unwrap();
+        let consumed_re =
+            Regex::new(r"^Program (\w+) consumed (\d+) of (\d+) compute units$").unwrap();
+        let success_re = Regex::new(r"^Program (\w+) success$").unwrap();
+        let failed_re = Regex::new(r"^Program (\w+) failed: (.*)$").unwrap();
+
+        let mut tree = InvocationTree::default();
+        let mut stack: Vec<Invocation> = Vec::new();
+        for (i, log) in logs.iter().enumerate() {
+            if log == LOG_TRUNCATED {
+                tree.truncated = true;
+                break;
+            } else if let Some(captures) = invoke_re.captures(log) {
+                let (program_id, depth) =
+                    match (Pubkey::from_str(&captures[1]), captures[2].parse::<usize>()) {
+                        (Ok(program_id), Ok(depth)) if depth > 0 => (program_id, depth),
+                        _ => continue,
+                    };
+                // Invocations at this depth or deeper have returned without
+                // it being logged.
+                while stack.len() >= depth {
+                    tree.finish(&mut stack);
+                }
+                stack.push(Invocation::new(program_id, depth));
+            } else if let Some(msg) = log.strip_prefix(PROGRAM_LOG) {
+                if let Some(invocation) = stack.last_mut() {
+                    invocation.logs.push(msg.to_string());
+                    // Events logged in base64 with `msg!` by older programs.
+                    if let Ok(data) = anchor_lang::__private::base64::decode(msg) {
+                        invocation.emitted.push((i, data));
+                    }
+                }
+            } else if let Some(data) = log.strip_prefix(PROGRAM_DATA) {
+                if let Some(invocation) = stack.last_mut() {
+                    // Data is logged as space separated base64 fields.
+                    if let Ok(data) = data
+                        .split(' ')
+                        .map(anchor_lang::__private::base64::decode)
+                        .collect::<Result<Vec<_>, _>>()
+                    {
+                        invocation.data.push(data.concat());
+                        invocation.emitted.push((i, data.concat()));
+                    }
+                }
+            } else if let Some(return_data) = log.strip_prefix(PROGRAM_RETURN) {
+                if let Some((_, data)) = return_data.split_once(' ') {
+                    if let (Some(invocation), Ok(data)) = (
+                        stack.last_mut(),
+                        anchor_lang::__private::base64::decode(data),
+                    ) {
+                        invocation.return_data = Some(data);
+                    }
+                }
+            } else if let Some(captures) = consumed_re.captures(log) {
+                if let Some(invocation) = stack.last_mut() {
+                    invocation.units_consumed = captures[2].parse().ok();
+                    invocation.units_total = captures[3].parse().ok();
+                }
+            } else if success_re.is_match(log) {
+                if let Some(invocation) = stack.last_mut() {
+                    invocation.success = Some(true);
+                }
+                tree.finish(&mut stack);
+            } else if let Some(captures) = failed_re.captures(log) {
+                if let Some(invocation) = stack.last_mut() {
+                    invocation.success = Some(false);
+                    invocation.error = Some(captures[2].to_string());
+                }
+                tree.finish(&mut stack);
+            }
+        }
+        while !stack.is_empty() {
+            tree.finish(&mut stack);
+        }
+
+        tree
+    }
+
+    /// Returns all invocations, CPIs included, in the order they started.
+    pub fn iter(&self) -> impl Iterator<Item = &Invocation> {
+        self.invocations.iter().flat_map(Invocation::iter)
+    }
+
+    /// Decodes the events of type `T` emitted by `program_id`, in the order
+    /// they were emitted.
+    pub fn events<T: Event + AnchorDeserialize>(
+        &self,
+        program_id: &Pubkey,
+    ) -> Result<Vec<T>, ClientError> {
+        emitted_events(self.iter().filter(|inv| inv.program_id == *program_id))
+            .into_iter()
+            .collect()
+    }
+
+    // Moves the current invocation into its parent, or the roots.
+    fn finish(&mut self, stack: &mut Vec<Invocation>) {
+        if let Some(invocation) = stack.pop() {
+            match stack.last_mut() {
+                Some(parent) => parent.invocations.push(invocation),
+                None => self.invocations.push(invocation),
+            }
+        }
+    }
+}
+
+impl Invocation {
+    fn new(program_id: Pubkey, depth: usize) -> Self {
+        Self {
+            program_id,
+            depth,
+            units_consumed: None,
+            units_total: None,
+            success: None,
+            error: None,
+            logs: Vec::new(),
+            data: Vec::new(),
+            return_data: None,
+            invocations: Vec::new(),
+            emitted: Vec::new(),
+        }
+    }
+
+    /// Returns this invocation and all its CPIs, in the order they started.
+    pub fn iter(&self) -> impl Iterator<Item = &Invocation> {
+        let mut invocations = vec![self];
+        for invocation in &self.invocations {
+            invocations.extend(invocation.iter());
+        }
+        invocations.into_iter()
+    }
+
+    /// Decodes the events of type `T` emitted by this invocation, CPIs
+    /// excluded. Events logged in base64 with `msg!` by older programs are
+    /// decoded too.
+    pub fn events<T: Event + AnchorDeserialize>(&self) -> Result<Vec<T>, ClientError> {
+        emitted_events(std::iter::once(self)).into_iter().collect()
+    }
+}
+
+// Decodes the events of type `T` emitted by `invocations`, in the order they
+// were emitted across invocations, e.g. around self-CPIs.
+pub(crate) fn emitted_events<'a, T: Event + AnchorDeserialize>(
+    invocations: impl Iterator<Item = &'a Invocation>,
+) -> Vec<Result<T, ClientError>> {
+    let mut emitted: Vec<&(usize, Vec<u8>)> = invocations
+        .flat_map(|invocation| &invocation.emitted)
+        .filter(|(_, data)| data.starts_with(&T::discriminator()))
+        .collect();
+    emitted.sort_by_key(|(i, _)| *i);
+    emitted
+        .into_iter()
+        .map(|(_, data)| {
+            T::deserialize(&mut &data[8..]).map_err(|e| ClientError::LogParseError(e.to_string()))
+        })
+        .collect()
+}
+
+// The event and log helpers of the tests parsing logs.
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use anchor_lang::prelude::borsh;
+    use anchor_lang::{AnchorSerialize, Discriminator};
+
+    #[anchor_lang::event]
+    #[derive(Debug, PartialEq, Eq)]
+    pub(crate) struct Counted {
+        pub(crate) count: u64,
+    }
+
+    pub(crate) fn to_logs(logs: &[&str]) -> Vec<String> {
+        logs.iter().map(|log| log.to_string()).collect()
+    }
+
+    // Returns the base64 data of a `Counted` event.
+    pub(crate) fn event_data(count: u64) -> String {
+        let mut data = Counted::discriminator().to_vec();
+        data.extend(Counted { count }.try_to_vec().unwrap());
+        anchor_lang::__private::base64::encode(data)
+    }
+
+    #[test]
+    fn invocation_tree() {
+        let program = Pubkey::new_unique();
+        let system = anchor_lang::solana_program::system_program::ID;
+        let logs = to_logs(&[
+            &format!("Program {program} invoke [1]"),
+            "Program log: Instruction: Increment",
+            &format!("Program {system} invoke [2]"),
+            &format!("Program {system} success"),
+            &format!("Program data: {}", event_data(1)),
+            &format!("Program return: {program} KgAAAAAAAAA="),
+            &format!("Program {program} consumed 5000 of 200000 compute units"),
+            &format!("Program {program} success"),
+            &format!("Program {program} invoke [1]"),
+            &format!("Program {program} invoke [2]"),
+            &format!("Program log: {}", event_data(2)),
+            &format!("Program {program} failed: custom program error: 0x1770"),
+            &format!("Program {program} consumed 300 of 195000 compute units"),
+            &format!("Program {program} failed: custom program error: 0x1770"),
+        ]);
+        let tree = InvocationTree::parse(&logs);
+        assert!(!tree.truncated);
+        assert_eq!(tree.invocations.len(), 2);
+
+        let first = &tree.invocations[0];
+        assert_eq!(first.depth, 1);
+        assert_eq!(first.units_consumed, Some(5000));
+        assert_eq!(first.units_total, Some(200000));
+        assert_eq!(first.success, Some(true));
+        assert_eq!(first.logs, vec!["Instruction: Increment".to_string()]);
+        assert_eq!(first.return_data, Some(42u64.to_le_bytes().to_vec()));
+        assert_eq!(first.invocations[0].program_id, system);
+        assert_eq!(first.invocations[0].depth, 2);
+        assert_eq!(
+            first.events::<Counted>().unwrap(),
+            vec![Counted { count: 1 }]
+        );
+
+        let second = &tree.invocations[1];
+        assert_eq!(second.success, Some(false));
+        assert_eq!(
+            second.error.as_deref(),
+            Some("custom program error: 0x1770")
+        );
+        assert_eq!(second.invocations[0].success, Some(false));
+        assert_eq!(
+            tree.events::<Counted>(&program).unwrap(),
+            vec![Counted { count: 1 }, Counted { count: 2 }]
+        );
+        assert_eq!(tree.iter().count(), 4);
+    }
+
+    #[test]
+    fn events_in_emission_order() {
+        let program = Pubkey::new_unique();
+        let logs = to_logs(&[
+            &format!("Program {program} invoke [1]"),
+            &format!("Program log: {}", event_data(1)),
+            &format!("Program data: {}", event_data(2)),
+            // A self-CPI, e.g. of `emit_cpi!`, between events of the parent.
+            &format!("Program {program} invoke [2]"),
+            &format!("Program data: {}", event_data(3)),
+            &format!("Program {program} success"),
+            &format!("Program log: {}", event_data(4)),
+            &format!("Program data: {}", event_data(5)),
+            &format!("Program {program} success"),
+        ]);
+        let tree = InvocationTree::parse(&logs);
+        let counts = |events: Vec<Counted>| events.into_iter().map(|e| e.count).collect::<Vec<_>>();
+        assert_eq!(counts(tree.events(&program).unwrap()), vec![1, 2, 3, 4, 5]);
+        assert_eq!(
+            counts(tree.invocations[0].events().unwrap()),
+            vec![1, 2, 4, 5]
+        );
+    }
+
+    #[test]
+    fn malformed_logs() {
+        let program = Pubkey::new_unique();
+        let logs = to_logs(&[
+            "Program log: Before any invocation",
+            &format!("Program {program} invoke [1]"),
+            &format!("Program {program} invoke [2]"),
+            // The CPI and the instruction returned without it being logged.
+            &format!("Program {program} invoke [1]"),
+            "Program not-a-program invoke [1]",
+            "Log truncated",
+            &format!("Program {program} success"),
+        ]);
+        let tree = InvocationTree::parse(&logs);
+        assert!(tree.truncated);
+        assert_eq!(tree.invocations.len(), 2);
+        assert_eq!(tree.invocations[0].invocations.len(), 1);
+        assert_eq!(tree.invocations[0].success, None);
+        assert_eq!(tree.invocations[1].success, None);
+        assert!(tree.invocations.iter().all(|inv| inv.logs.is_empty()));
+
+        assert_eq!(InvocationTree::parse(&[]), InvocationTree::default());
+    }
+}
@@ -0,0 +1,204 @@
+//! An in-memory backend, see `MockBackend`.
+
+use crate::backend::{preflight_failure, Backend};
+use crate::{ClientError, Simulation};
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use async_trait::async_trait;
+use solana_client::client_error::ClientError as SolanaClientError;
+use solana_client::rpc_filter::RpcFilterType;
+use solana_client::rpc_request::MAX_MULTIPLE_ACCOUNTS;
+use solana_sdk::account::{Account, AccountSharedData};
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+type Executor =
+    dyn Fn(&VersionedTransaction, &mut BTreeMap<Pubkey, Account>) -> MockExecution + Send + Sync;
+
+/// The outcome of a transaction executed by a [`MockBackend`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockExecution {
+    pub err: Option<TransactionError>,
+    /// The logs, as the runtime logs them, e.g. with the invocations of
+    /// the programs.
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    pub return_data: Option<(Pubkey, Vec<u8>)>,
+}
+
+/// An in-memory backend for tests, holding accounts and executing
+/// transactions with a closure instead of programs.
+///
+/// Sent transactions must be signed by all their signers. The accounts
+/// changed by the executor are only kept when the transaction succeeds.
+pub struct MockBackend {
+    accounts: Mutex<BTreeMap<Pubkey, Account>>,
+    transactions: Mutex<Vec<VersionedTransaction>>,
+    blockhash: Hash,
+    execute: Box<Executor>,
+}
+
+impl Default for MockBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockBackend {
+    /// Creates a backend executing every transaction successfully, without
+    /// changing any account.
+    pub fn new() -> Self {
+        Self::with_executor(|_, _| MockExecution::default())
+    }
+
+    /// Creates a backend executing transactions with `execute`, given the
+    /// accounts to change.
+    pub fn with_executor(
+        execute: impl Fn(&VersionedTransaction, &mut BTreeMap<Pubkey, Account>) -> MockExecution
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Self {
+            accounts: Mutex::new(BTreeMap::new()),
+            transactions: Mutex::new(Vec::new()),
+            blockhash: Hash::new_unique(),
+            execute: Box::new(execute),
+        }
+    }
+
+    pub fn set_account(&self, address: Pubkey, account: Account) {
+        self.accounts.lock().unwrap().insert(address, account);
+    }
+
+    /// Returns the transactions sent successfully so far, oldest first.
+    pub fn transactions(&self) -> Vec<VersionedTransaction> {
+        self.transactions.lock().unwrap().clone()
+    }
+
+    // Executes `tx` on a copy of the accounts, returned with the outcome.
+    fn execute(&self, tx: &VersionedTransaction) -> (MockExecution, BTreeMap<Pubkey, Account>) {
+        let mut accounts = self.accounts.lock().unwrap().clone();
+        let execution = (self.execute)(tx, &mut accounts);
+        (execution, accounts)
+    }
+}
+
+#[async_trait]
+impl Backend for MockBackend {
+    async fn get_latest_blockhash(&self) -> Result<Hash, ClientError> {
+        Ok(self.blockhash)
+    }
+
+    async fn send_transaction(&self, tx: &VersionedTransaction) -> Result<Signature, ClientError> {
+        if tx.verify_with_results().contains(&false) {
+            return Err(SolanaClientError::from(TransactionError::SignatureFailure).into());
+        }
+        let (execution, accounts) = self.execute(tx);
+        if let Some(err) = execution.err {
+            return Err(preflight_failure(
+                err,
+                execution.logs,
+                execution.units_consumed,
+            ));
+        }
+        *self.accounts.lock().unwrap() = accounts;
+        self.transactions.lock().unwrap().push(tx.clone());
+
+        Ok(tx.signatures[0])
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &VersionedTransaction,
+    ) -> Result<Simulation, ClientError> {
+        let (execution, _) = self.execute(tx);
+
+        Ok(Simulation::new(
+            execution.err,
+            execution.logs,
+            execution.units_consumed,
+            execution.return_data,
+        ))
+    }
+
+    async fn get_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        Ok(self.accounts.lock().unwrap().get(address).cloned())
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+        _min_context_slot: Option<u64>,
+    ) -> Result<Vec<Option<Account>>, ClientError> {
+        // As limited by the RPC.
+        if addresses.len() > MAX_MULTIPLE_ACCOUNTS {
+            return Err(ClientError::BackendError(format!(
+                "Too many accounts requested: {}",
+                addresses.len()
+            )));
+        }
+        let accounts = self.accounts.lock().unwrap();
+        Ok(addresses
+            .iter()
+            .map(|address| accounts.get(address).cloned())
+            .collect())
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, Account)>, ClientError> {
+        Ok(self
+            .accounts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, account)| account.owner == *program_id)
+            .filter(|(_, account)| {
+                let account = AccountSharedData::from((*account).clone());
+                filters.iter().all(|filter| filter.allows(&account))
+            })
+            .map(|(address, account)| (*address, account.clone()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_filter::Memcmp;
+
+    #[test]
+    fn program_accounts() {
+        let program_id = Pubkey::new_unique();
+        let backend = MockBackend::new();
+        let account = |owner: Pubkey, data: Vec<u8>| Account {
+            lamports: 1,
+            data,
+            owner,
+            ..Account::default()
+        };
+        let first = Pubkey::new_unique();
+        backend.set_account(first, account(program_id, vec![1, 2, 3]));
+        backend.set_account(Pubkey::new_unique(), account(program_id, vec![2, 2]));
+        backend.set_account(Pubkey::new_unique(), account(Pubkey::new_unique(), vec![1]));
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let get = |filters| {
+            rt.block_on(backend.get_program_accounts(&program_id, filters))
+                .unwrap()
+        };
+        assert_eq!(get(vec![]).len(), 2);
+        let filtered = get(vec![
+            RpcFilterType::DataSize(3),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &[1])),
+        ]);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].0, first);
+        assert!(get(vec![RpcFilterType::DataSize(4)]).is_empty());
+    }
+}
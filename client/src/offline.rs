@@ -0,0 +1,165 @@
+//! Offline and partial signing of requests, see
+//! `RequestBuilder::partial_transaction`.
+
+use crate::ClientError;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::signer::presigner::PresignerError;
+use solana_sdk::signer::SignerError;
+use solana_sdk::transaction::VersionedTransaction;
+
+/// The text encoding of an exported transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionEncoding {
+    Base58,
+    Base64,
+}
+
+/// A transaction missing some of its signatures, to be signed elsewhere,
+/// e.g. by an air-gapped key or the members of a multisig.
+///
+/// Transactions signed offline should use a durable nonce, see
+/// `RequestBuilder::nonce`, their blockhash expiring otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialTransaction {
+    tx: VersionedTransaction,
+}
+
+impl PartialTransaction {
+    pub(crate) fn new(tx: VersionedTransaction) -> Self {
+        Self { tx }
+    }
+
+    pub fn transaction(&self) -> &VersionedTransaction {
+        &self.tx
+    }
+
+    pub fn into_transaction(self) -> VersionedTransaction {
+        self.tx
+    }
+
+    /// Returns the signers whose signature is still missing, in the order
+    /// of the message.
+    pub fn missing_signers(&self) -> Vec<Pubkey> {
+        self.signers()
+            .iter()
+            .zip(&self.tx.signatures)
+            .filter(|(_, signature)| **signature == Signature::default())
+            .map(|(pubkey, _)| *pubkey)
+            .collect()
+    }
+
+    /// Returns whether all signatures are present.
+    pub fn is_signed(&self) -> bool {
+        self.missing_signers().is_empty()
+    }
+
+    /// Signs the transaction with `signer`, one of its signers.
+    pub fn sign(&mut self, signer: &dyn Signer) -> Result<(), ClientError> {
+        let signature = signer.try_sign_message(&self.tx.message.serialize())?;
+        self.add_signature(signer.try_pubkey()?, signature)
+    }
+
+    /// Adds the signature of `pubkey`, one of the signers, after verifying
+    /// it.
+    pub fn add_signature(
+        &mut self,
+        pubkey: Pubkey,
+        signature: Signature,
+    ) -> Result<(), ClientError> {
+        let index = self
+            .signers()
+            .iter()
+            .position(|signer| *signer == pubkey)
+            .ok_or(SignerError::KeypairPubkeyMismatch)?;
+        if !signature.verify(pubkey.as_ref(), &self.tx.message.serialize()) {
+            return Err(SignerError::PresignerError(PresignerError::VerificationFailure).into());
+        }
+        self.tx.signatures[index] = signature;
+
+        Ok(())
+    }
+
+    /// Encodes the transaction, e.g. to print it.
+    pub fn encode(&self, encoding: TransactionEncoding) -> Result<String, ClientError> {
+        let data = bincode::serialize(&self.tx)
+            .map_err(|e| ClientError::InvalidTransaction(e.to_string()))?;
+        Ok(match encoding {
+            TransactionEncoding::Base58 => solana_sdk::bs58::encode(data).into_string(),
+            TransactionEncoding::Base64 => anchor_lang::__private::base64::encode(data),
+        })
+    }
+
+    /// Decodes a transaction encoded with [`encode`](Self::encode).
+    pub fn decode(data: &str, encoding: TransactionEncoding) -> Result<Self, ClientError> {
+        let data = match encoding {
+            TransactionEncoding::Base58 => solana_sdk::bs58::decode(data)
+                .into_vec()
+                .map_err(|e| ClientError::InvalidTransaction(e.to_string()))?,
+            TransactionEncoding::Base64 => anchor_lang::__private::base64::decode(data)
+                .map_err(|e| ClientError::InvalidTransaction(e.to_string()))?,
+        };
+        let tx: VersionedTransaction = bincode::deserialize(&data)
+            .map_err(|e| ClientError::InvalidTransaction(e.to_string()))?;
+        if tx.signatures.len() != usize::from(tx.message.header().num_required_signatures) {
+            return Err(ClientError::InvalidTransaction(
+                "Wrong number of signatures".to_string(),
+            ));
+        }
+
+        Ok(Self { tx })
+    }
+
+    // The signers of the transaction, in the order of their signatures.
+    fn signers(&self) -> &[Pubkey] {
+        let num_required = usize::from(self.tx.message.header().num_required_signatures);
+        let keys = self.tx.message.static_account_keys();
+        &keys[..num_required.min(keys.len())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::hash::Hash;
+    use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::{Message, VersionedMessage};
+    use solana_sdk::signature::Keypair;
+
+    #[test]
+    fn sign_offline() {
+        let payer = Keypair::new();
+        let offline = Keypair::new();
+        let ix = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[1],
+            vec![AccountMeta::new_readonly(offline.pubkey(), true)],
+        );
+        let message =
+            Message::new_with_blockhash(&[ix], Some(&payer.pubkey()), &Hash::new_unique());
+        let mut tx = VersionedTransaction {
+            signatures: vec![Signature::default(); 2],
+            message: VersionedMessage::Legacy(message),
+        };
+        tx.signatures[0] = payer.sign_message(&tx.message.serialize());
+        let partial = PartialTransaction::new(tx);
+        assert_eq!(partial.missing_signers(), vec![offline.pubkey()]);
+
+        for encoding in [TransactionEncoding::Base58, TransactionEncoding::Base64] {
+            let encoded = partial.encode(encoding).unwrap();
+            let mut decoded = PartialTransaction::decode(&encoded, encoding).unwrap();
+            assert_eq!(decoded, partial);
+
+            let other = Keypair::new();
+            assert!(decoded.sign(&other).is_err());
+            let forged = other.sign_message(&decoded.tx.message.serialize());
+            assert!(decoded.add_signature(offline.pubkey(), forged).is_err());
+            assert!(!decoded.is_signed());
+
+            decoded.sign(&offline).unwrap();
+            assert!(decoded.is_signed());
+            assert!(decoded.tx.verify_with_results().iter().all(|ok| *ok));
+        }
+        assert!(PartialTransaction::decode("not base58", TransactionEncoding::Base58).is_err());
+    }
+}
@@ -0,0 +1,354 @@
+//! Splitting of instruction batches across transactions, see
+//! `TransactionPlanner`.
+
+use crate::compute_budget::{
+    ComputeBudget, DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT, MAX_COMPUTE_UNIT_LIMIT,
+};
+use crate::program_error::{decode_program_error, message_program_ids};
+use crate::{Backend, ClientError};
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use futures::future::join_all;
+use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
+use solana_sdk::message::{v0, Message, VersionedMessage};
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+use std::ops::Deref;
+use std::sync::Arc;
+#[cfg(not(feature = "async"))]
+use tokio::runtime::Handle;
+
+// Instructions to send in the same transaction, in order.
+struct Group {
+    instructions: Vec<Instruction>,
+    units: u32,
+}
+
+/// Packs batches of instructions into as few transactions as possible.
+///
+/// Instructions are added in groups, sent in order within a single
+/// transaction, e.g. instructions depending on each other. Groups are
+/// packed in order, each transaction holding as many groups as fit within
+/// the packet size and the compute unit limit. A compute unit limit
+/// instruction of the units of its groups is prepended to every
+/// transaction. With lookup tables, v0 transactions are compiled with them.
+pub struct TransactionPlanner<'a, C> {
+    payer: C,
+    signers: Vec<&'a dyn Signer>,
+    groups: Vec<Group>,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+    compute_unit_price: Option<u64>,
+    backend: Arc<dyn Backend>,
+    #[cfg(not(feature = "async"))]
+    pub(crate) handle: &'a Handle,
+}
+
+impl<'a, C: Deref<Target = impl Signer> + Clone> TransactionPlanner<'a, C> {
+    pub(crate) fn new(
+        payer: C,
+        backend: Arc<dyn Backend>,
+        #[cfg(not(feature = "async"))] handle: &'a Handle,
+    ) -> Self {
+        Self {
+            payer,
+            signers: Vec::new(),
+            groups: Vec::new(),
+            lookup_tables: Vec::new(),
+            compute_unit_price: None,
+            backend,
+            #[cfg(not(feature = "async"))]
+            handle,
+        }
+    }
+
+    /// Adds an instruction, in a group of its own.
+    #[must_use]
+    pub fn instruction(self, ix: Instruction) -> Self {
+        self.group(vec![ix])
+    }
+
+    /// Adds a group of instructions to send in the same transaction,
+    /// consuming up to the default limit of each instruction.
+    #[must_use]
+    pub fn group(self, instructions: Vec<Instruction>) -> Self {
+        let units = (instructions.len() as u64 * u64::from(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT))
+            .min(u64::from(MAX_COMPUTE_UNIT_LIMIT)) as u32;
+        self.group_with_compute_units(instructions, units)
+    }
+
+    /// Adds a group of instructions to send in the same transaction,
+    /// consuming up to `units` compute units.
+    #[must_use]
+    pub fn group_with_compute_units(mut self, instructions: Vec<Instruction>, units: u32) -> Self {
+        self.groups.push(Group {
+            instructions,
+            units,
+        });
+        self
+    }
+
+    /// Adds a signer, signing the transactions of the instructions it signs.
+    #[must_use]
+    pub fn signer(mut self, signer: &'a dyn Signer) -> Self {
+        self.signers.push(signer);
+        self
+    }
+
+    /// Adds an address lookup table to compile v0 transactions with.
+    #[must_use]
+    pub fn lookup_table(mut self, table: AddressLookupTableAccount) -> Self {
+        self.lookup_tables.push(table);
+        self
+    }
+
+    /// Sets the priority fee of the transactions, in micro-lamports per
+    /// compute unit.
+    #[must_use]
+    pub fn compute_unit_price(mut self, micro_lamports: u64) -> Self {
+        self.compute_unit_price = Some(micro_lamports);
+        self
+    }
+
+    /// Returns the instructions of each planned transaction, compute budget
+    /// instructions included. Fails if a group doesn't fit in a transaction
+    /// on its own.
+    pub fn plan(&self) -> Result<Vec<Vec<Instruction>>, ClientError> {
+        let mut transactions = Vec::new();
+        let mut start = 0;
+        for end in 1..=self.groups.len() {
+            if self.fits(&self.groups[start..end]) {
+                continue;
+            }
+            if end - 1 == start || !self.fits(&self.groups[end - 1..end]) {
+                return Err(ClientError::PlanError(format!(
+                    "Group {} doesn't fit in a transaction",
+                    end - 1
+                )));
+            }
+            transactions.push(self.transaction_instructions(&self.groups[start..end - 1]));
+            start = end - 1;
+        }
+        if start < self.groups.len() {
+            transactions.push(self.transaction_instructions(&self.groups[start..]));
+        }
+
+        Ok(transactions)
+    }
+
+    /// Signs the planned transactions with `latest_hash`.
+    pub fn signed_transactions(
+        &self,
+        latest_hash: Hash,
+    ) -> Result<Vec<VersionedTransaction>, ClientError> {
+        self.plan()?
+            .iter()
+            .map(|instructions| {
+                let message = self.message(instructions, latest_hash)?;
+                let required = &message.static_account_keys()
+                    [..message.header().num_required_signatures as usize];
+                let mut signers: Vec<&dyn Signer> = vec![&*self.payer];
+                for signer in &self.signers {
+                    let pubkey = signer.pubkey();
+                    if required.contains(&pubkey) && !signers.iter().any(|s| s.pubkey() == pubkey) {
+                        signers.push(*signer);
+                    }
+                }
+                VersionedTransaction::try_new(message, &signers).map_err(Into::into)
+            })
+            .collect()
+    }
+
+    // Sends the planned transactions one after the other, stopping at the
+    // first failure.
+    pub(crate) async fn send_internal(
+        &self,
+    ) -> Result<Vec<Result<Signature, ClientError>>, ClientError> {
+        let latest_hash = self.backend.get_latest_blockhash().await?;
+        let mut results = Vec::new();
+        for tx in self.signed_transactions(latest_hash)? {
+            let result = self.send_transaction_internal(&tx).await;
+            let failed = result.is_err();
+            results.push(result);
+            if failed {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+
+    // Sends all planned transactions at once.
+    pub(crate) async fn send_parallel_internal(
+        &self,
+    ) -> Result<Vec<Result<Signature, ClientError>>, ClientError> {
+        let latest_hash = self.backend.get_latest_blockhash().await?;
+        let transactions = self.signed_transactions(latest_hash)?;
+
+        Ok(join_all(
+            transactions
+                .iter()
+                .map(|tx| self.send_transaction_internal(tx)),
+        )
+        .await)
+    }
+
+    async fn send_transaction_internal(
+        &self,
+        tx: &VersionedTransaction,
+    ) -> Result<Signature, ClientError> {
+        self.backend
+            .send_transaction(tx)
+            .await
+            .map_err(|err| match err {
+                ClientError::SolanaClientError(err) => {
+                    decode_program_error(err, &message_program_ids(&tx.message), None, None)
+                }
+                err => err,
+            })
+    }
+
+    fn transaction_instructions(&self, groups: &[Group]) -> Vec<Instruction> {
+        let units: u64 = groups.iter().map(|group| u64::from(group.units)).sum();
+        let compute_budget = ComputeBudget {
+            unit_limit: Some(units.min(u64::from(MAX_COMPUTE_UNIT_LIMIT)) as u32),
+            unit_price: self.compute_unit_price,
+        };
+        let mut instructions = compute_budget.instructions();
+        for group in groups {
+            instructions.extend(group.instructions.iter().cloned());
+        }
+        instructions
+    }
+
+    // Returns whether `groups` fit in a single transaction.
+    fn fits(&self, groups: &[Group]) -> bool {
+        let units: u64 = groups.iter().map(|group| u64::from(group.units)).sum();
+        if units > u64::from(MAX_COMPUTE_UNIT_LIMIT) {
+            return false;
+        }
+        match self.message(&self.transaction_instructions(groups), Hash::default()) {
+            // Signatures are prefixed by their count, on a single byte for
+            // any transaction that fits.
+            Ok(message) => {
+                let signatures = usize::from(message.header().num_required_signatures);
+                1 + signatures * 64 + message.serialize().len() <= PACKET_DATA_SIZE
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn message(
+        &self,
+        instructions: &[Instruction],
+        latest_hash: Hash,
+    ) -> Result<VersionedMessage, ClientError> {
+        let payer = self.payer.pubkey();
+        if self.lookup_tables.is_empty() {
+            return Ok(VersionedMessage::Legacy(Message::new_with_blockhash(
+                instructions,
+                Some(&payer),
+                &latest_hash,
+            )));
+        }
+        let message =
+            v0::Message::try_compile(&payer, instructions, &self.lookup_tables, latest_hash)?;
+
+        Ok(VersionedMessage::V0(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockBackend, MockExecution};
+    use anchor_lang::solana_program::pubkey::Pubkey;
+    use solana_sdk::instruction::InstructionError;
+    use solana_sdk::signature::Keypair;
+    use solana_sdk::transaction::TransactionError;
+
+    fn instruction(program_id: Pubkey, data_len: usize) -> Instruction {
+        Instruction::new_with_bytes(program_id, &vec![1; data_len], vec![])
+    }
+
+    #[test]
+    fn plan_and_send() {
+        let program_id = Pubkey::new_unique();
+        // Fails the transactions of instructions with data `[2]`.
+        let backend = Arc::new(MockBackend::with_executor(|tx, _| {
+            let failed = tx.message.instructions().iter().any(|ix| ix.data == [2]);
+            MockExecution {
+                err: failed
+                    .then(|| TransactionError::InstructionError(1, InstructionError::Custom(1))),
+                ..MockExecution::default()
+            }
+        }));
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let planner = || {
+            TransactionPlanner::new(
+                Arc::new(Keypair::new()),
+                backend.clone(),
+                #[cfg(not(feature = "async"))]
+                rt.handle(),
+            )
+        };
+
+        // Two instructions of 500 bytes fit in a transaction, not three.
+        let mut sized = planner();
+        for _ in 0..5 {
+            sized = sized.instruction(instruction(program_id, 500));
+        }
+        let transactions = sized.plan().unwrap();
+        assert_eq!(
+            transactions.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![3, 3, 2]
+        );
+        for tx in sized.signed_transactions(Hash::default()).unwrap() {
+            assert!(serialized_len(&tx) <= PACKET_DATA_SIZE);
+        }
+        let too_large = planner().group(vec![
+            instruction(program_id, 500),
+            instruction(program_id, 500),
+            instruction(program_id, 500),
+        ]);
+        assert!(matches!(too_large.plan(), Err(ClientError::PlanError(_))));
+
+        // Compute units are summed up to the maximum limit.
+        let planned = planner()
+            .group_with_compute_units(vec![instruction(program_id, 1)], 600_000)
+            .group_with_compute_units(vec![instruction(program_id, 1)], 600_000)
+            .group_with_compute_units(vec![instruction(program_id, 1)], 600_000)
+            .plan()
+            .unwrap();
+        assert_eq!(planned.len(), 2);
+        assert_eq!(
+            planned[0][0],
+            ComputeBudget {
+                unit_limit: Some(1_200_000),
+                unit_price: None,
+            }
+            .instructions()[0]
+        );
+
+        // Sequential sends stop at the first failure.
+        let failing = planner()
+            .group_with_compute_units(vec![instruction(program_id, 1)], 1_000_000)
+            .group_with_compute_units(
+                vec![Instruction::new_with_bytes(program_id, &[2], vec![])],
+                1_000_000,
+            )
+            .group_with_compute_units(vec![instruction(program_id, 1)], 1_000_000);
+        let results = rt.block_on(failing.send_internal()).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        let results = rt.block_on(failing.send_parallel_internal()).unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results[2].is_ok());
+        assert_eq!(backend.transactions().len(), 3);
+    }
+
+    fn serialized_len(tx: &VersionedTransaction) -> usize {
+        1 + tx.signatures.len() * 64 + tx.message.serialize().len()
+    }
+}
@@ -0,0 +1,293 @@
+//! Decoding of the errors of failed transactions into
+//! `ClientError::Program`, see `RequestBuilder::error_code` and
+//! `RequestBuilder::idl_errors`.
+
+use crate::idl::types::IdlErrorCode;
+use crate::logs::{Invocation, InvocationTree};
+use crate::simulation::parse_logged_error;
+use crate::ClientError;
+use anchor_lang::error::{ErrorCode, ProgramErrorCode, ERROR_CODE_OFFSET};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use solana_client::client_error::{ClientError as SolanaClientError, ClientErrorKind};
+use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::transaction::TransactionError;
+
+// Finds the name and message of the program's custom errors.
+#[derive(Debug, Clone)]
+pub(crate) enum ErrorLookup {
+    Type(fn(u32) -> Option<(String, String)>),
+    Idl(Vec<IdlErrorCode>),
+}
+
+impl ErrorLookup {
+    pub fn of_type<E: ProgramErrorCode>() -> Self {
+        ErrorLookup::Type(|code| E::from_code(code).map(|e| (e.name(), e.to_string())))
+    }
+
+    fn lookup(&self, code: u32) -> Option<(String, String)> {
+        match self {
+            ErrorLookup::Type(lookup) => lookup(code),
+            ErrorLookup::Idl(errors) => errors.iter().find(|e| e.code == code).map(|e| {
+                let msg = e.msg.clone().unwrap_or_else(|| e.name.clone());
+                (e.name.clone(), msg)
+            }),
+        }
+    }
+}
+
+// Decodes the custom error a transaction failed with, when raised by
+// `program_id`. `program_ids` are the programs of the instructions of the
+// transaction, in order. When the logs are known, i.e. when the preflight
+// simulation failed, the error is raised by the deepest failed invocation of
+// the failing instruction, and its `AnchorError` log is used. Otherwise it's
+// raised by the program of the failing instruction, codes below
+// `ERROR_CODE_OFFSET` being Anchor errors and codes above being looked up
+// with `lookup`. Without `program_id`, errors of any program are decoded.
+// Errors that can't be decoded are returned as is.
+pub(crate) fn decode_program_error(
+    err: SolanaClientError,
+    program_ids: &[Pubkey],
+    program_id: Option<&Pubkey>,
+    lookup: Option<&ErrorLookup>,
+) -> ClientError {
+    let (index, code) = match err.get_transaction_error() {
+        Some(TransactionError::InstructionError(index, InstructionError::Custom(code))) => {
+            (usize::from(index), code)
+        }
+        _ => return err.into(),
+    };
+
+    let logs = match err.kind() {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+            ..
+        }) => result.logs.as_deref(),
+        _ => None,
+    };
+    let failed = logs.and_then(|logs| failed_invocation(logs, index, program_ids));
+    let raised_by = match &failed {
+        Some(invocation) => Some(invocation.program_id),
+        None => program_ids.get(index).copied(),
+    };
+    if program_id.is_some() && raised_by.as_ref() != program_id {
+        return err.into();
+    }
+
+    let msgs: Vec<&str> = failed
+        .iter()
+        .flat_map(|invocation| &invocation.logs)
+        .map(String::as_str)
+        .collect();
+    if let Some(logged) = parse_logged_error(&msgs).filter(|e| e.code == code) {
+        return ClientError::Program {
+            code,
+            name: logged.name,
+            msg: logged.msg,
+            origin: logged.origin,
+            compared: logged.compared,
+        };
+    }
+
+    let found = if code < ERROR_CODE_OFFSET {
+        ErrorCode::from_code(code).map(|e| (e.name(), e.to_string()))
+    } else {
+        lookup.and_then(|lookup| lookup.lookup(code))
+    };
+    match found {
+        Some((name, msg)) => ClientError::Program {
+            code,
+            name,
+            msg,
+            origin: None,
+            compared: None,
+        },
+        None => err.into(),
+    }
+}
+
+// Returns the programs of the instructions of a message, in order.
+pub(crate) fn message_program_ids(message: &VersionedMessage) -> Vec<Pubkey> {
+    message
+        .instructions()
+        .iter()
+        .map(|ix| *ix.program_id(message.static_account_keys()))
+        .collect()
+}
+
+// Returns the invocation raising the error of the instruction at `index`,
+// i.e. its deepest failed invocation. `None` if the logs don't match the
+// instructions, e.g. when truncated.
+fn failed_invocation(logs: &[String], index: usize, program_ids: &[Pubkey]) -> Option<Invocation> {
+    let instruction = InvocationTree::parse(logs)
+        .invocations
+        .into_iter()
+        .nth(index)?;
+    if program_ids.get(index) != Some(&instruction.program_id) {
+        return None;
+    }
+    instruction
+        .iter()
+        .filter(|invocation| invocation.success == Some(false))
+        .last()
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LoggedErrorOrigin;
+    use solana_client::rpc_response::RpcSimulateTransactionResult;
+
+    fn failed(index: u8, code: u32, logs: Option<Vec<String>>) -> SolanaClientError {
+        let err = TransactionError::InstructionError(index, InstructionError::Custom(code));
+        match logs {
+            Some(logs) => RpcError::RpcResponseError {
+                code: -32002,
+                message: "Transaction simulation failed".to_string(),
+                data: RpcResponseErrorData::SendTransactionPreflightFailure(
+                    RpcSimulateTransactionResult {
+                        err: Some(err),
+                        logs: Some(logs),
+                        accounts: None,
+                        units_consumed: None,
+                        return_data: None,
+                    },
+                ),
+            }
+            .into(),
+            None => err.into(),
+        }
+    }
+
+    #[test]
+    fn anchor_error_codes() {
+        let program = Pubkey::new_unique();
+        match decode_program_error(failed(0, 2003, None), &[program], Some(&program), None) {
+            ClientError::Program {
+                code, name, origin, ..
+            } => {
+                assert_eq!(code, 2003);
+                assert_eq!(name, "ConstraintRaw");
+                assert_eq!(origin, None);
+            }
+            err => panic!("Unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    fn program_error_codes() {
+        let program = Pubkey::new_unique();
+        let lookup = ErrorLookup::Idl(vec![IdlErrorCode {
+            code: 6003,
+            name: "TooLarge".to_string(),
+            msg: Some("Too large".to_string()),
+        }]);
+        match decode_program_error(
+            failed(0, 6003, None),
+            &[program],
+            Some(&program),
+            Some(&lookup),
+        ) {
+            ClientError::Program { name, msg, .. } => {
+                assert_eq!(name, "TooLarge");
+                assert_eq!(msg, "Too large");
+            }
+            err => panic!("Unexpected error: {err}"),
+        }
+        // Unknown codes are kept as is.
+        assert!(matches!(
+            decode_program_error(failed(0, 6003, None), &[program], Some(&program), None),
+            ClientError::SolanaClientError(_)
+        ));
+
+        // Errors logged by the program are decoded without a lookup.
+        let logs = vec![
+            format!("Program {program} invoke [1]"),
+            "Program log: AnchorError thrown in programs/counter/src/lib.rs:42. Error Code: TooLarge. Error Number: 6003. Error Message: Too large.".to_string(),
+            "Program log: Left: 11".to_string(),
+            "Program log: Right: 10".to_string(),
+            format!("Program {program} failed: custom program error: 0x1773"),
+        ];
+        match decode_program_error(
+            failed(0, 6003, Some(logs.clone())),
+            &[program],
+            Some(&program),
+            None,
+        ) {
+            ClientError::Program {
+                code,
+                name,
+                origin,
+                compared,
+                ..
+            } => {
+                assert_eq!(code, 6003);
+                assert_eq!(name, "TooLarge");
+                assert_eq!(
+                    origin,
+                    Some(LoggedErrorOrigin::Source {
+                        file: "programs/counter/src/lib.rs".to_string(),
+                        line: 42,
+                    })
+                );
+                assert_eq!(compared, Some(("11".to_string(), "10".to_string())));
+            }
+            err => panic!("Unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    fn errors_of_other_programs() {
+        let program = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let lookup = ErrorLookup::Idl(vec![IdlErrorCode {
+            code: 6003,
+            name: "TooLarge".to_string(),
+            msg: Some("Too large".to_string()),
+        }]);
+        let decode = |err, program_ids: &[Pubkey]| {
+            decode_program_error(err, program_ids, Some(&program), Some(&lookup))
+        };
+
+        // The failing instruction is of another program.
+        assert!(matches!(
+            decode(failed(1, 6003, None), &[program, other]),
+            ClientError::SolanaClientError(_)
+        ));
+        match decode(failed(1, 6003, None), &[other, program]) {
+            ClientError::Program { name, .. } => assert_eq!(name, "TooLarge"),
+            err => panic!("Unexpected error: {err}"),
+        }
+
+        // The error is raised by a program invoked through CPI.
+        let anchor_error = |name: &str| {
+            format!("Program log: AnchorError occurred. Error Code: {name}. Error Number: 6003. Error Message: {name}.")
+        };
+        let logs = vec![
+            format!("Program {program} invoke [1]"),
+            format!("Program {other} invoke [2]"),
+            anchor_error("OtherError"),
+            format!("Program {other} failed: custom program error: 0x1773"),
+            format!("Program {program} failed: custom program error: 0x1773"),
+        ];
+        assert!(matches!(
+            decode(failed(0, 6003, Some(logs)), &[program]),
+            ClientError::SolanaClientError(_)
+        ));
+
+        // Errors are read from the logs of the failed invocation only.
+        let logs = vec![
+            format!("Program {other} invoke [1]"),
+            anchor_error("OtherError"),
+            format!("Program {other} success"),
+            format!("Program {program} invoke [1]"),
+            format!("Program {program} failed: custom program error: 0x1773"),
+        ];
+        match decode(failed(1, 6003, Some(logs)), &[other, program]) {
+            ClientError::Program { name, .. } => assert_eq!(name, "TooLarge"),
+            err => panic!("Unexpected error: {err}"),
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Signing with keys held by a signing service, see `RemoteSigner`.
+//!
+//! The service is called over HTTP with JSON bodies:
+//!
+//! - `GET <url>/pubkey` returns `{"pubkey": "<base58 pubkey>"}`.
+//! - `POST <url>/sign` with `{"pubkey": "<base58 pubkey>", "message":
+//!   "<base64 message>"}` returns `{"signature": "<base58 signature>"}`.
+//!
+//! Requests carry an `Authorization: Bearer <token>` header when the signer
+//! is given a token.
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use serde_json::{json, Value as JsonValue};
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::signer::presigner::PresignerError;
+use solana_sdk::signer::SignerError;
+use std::fmt;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A signer whose key is held by a signing service, see the
+/// [module](self) for its protocol.
+///
+/// Requests are sent from a thread of their own, the signer being usable
+/// from within an async runtime.
+#[derive(Clone)]
+pub struct RemoteSigner {
+    url: String,
+    pubkey: Pubkey,
+    auth_token: Option<String>,
+}
+
+// The token is redacted, signers being printed in logs.
+impl fmt::Debug for RemoteSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteSigner")
+            .field("url", &self.url)
+            .field("pubkey", &self.pubkey)
+            .field(
+                "auth_token",
+                &self.auth_token.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+impl RemoteSigner {
+    /// Connects to the signing service at `url`, fetching its pubkey.
+    pub fn connect(url: &str) -> Result<Self, SignerError> {
+        Self::connect_with_auth_token(url, None)
+    }
+
+    /// Connects to the signing service at `url`, authenticated by
+    /// `auth_token` if given.
+    pub fn connect_with_auth_token(
+        url: &str,
+        auth_token: Option<String>,
+    ) -> Result<Self, SignerError> {
+        let mut signer = Self::new(url, Pubkey::default());
+        signer.auth_token = auth_token;
+        let response = signer.request("pubkey", None)?;
+        signer.pubkey = response
+            .get("pubkey")
+            .and_then(JsonValue::as_str)
+            .and_then(|pubkey| Pubkey::from_str(pubkey).ok())
+            .ok_or_else(|| SignerError::Protocol(format!("Invalid pubkey response: {response}")))?;
+
+        Ok(signer)
+    }
+
+    /// Creates a signer for the key `pubkey` of the signing service at `url`,
+    /// without connecting to it.
+    pub fn new(url: &str, pubkey: Pubkey) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            pubkey,
+            auth_token: None,
+        }
+    }
+
+    #[must_use]
+    pub fn auth_token(mut self, auth_token: impl Into<String>) -> Self {
+        self.auth_token = Some(auth_token.into());
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    // Sends a request to `path`, a `POST` of `body` if given, a `GET`
+    // otherwise.
+    fn request(&self, path: &str, body: Option<JsonValue>) -> Result<JsonValue, SignerError> {
+        let url = format!("{}/{path}", self.url);
+        let auth_token = self.auth_token.clone();
+        // The blocking client can't be used, nor dropped, within a runtime.
+        thread::spawn(move || {
+            let client = reqwest::blocking::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .map_err(|e| SignerError::Connection(e.to_string()))?;
+            let mut request = match &body {
+                Some(body) => client
+                    .post(&url)
+                    .header("Content-Type", "application/json")
+                    .body(body.to_string()),
+                None => client.get(&url),
+            };
+            if let Some(auth_token) = auth_token {
+                request = request.bearer_auth(auth_token);
+            }
+            let response = request
+                .send()
+                .map_err(|e| SignerError::Connection(e.to_string()))?;
+            let status = response.status();
+            let text = response
+                .text()
+                .map_err(|e| SignerError::Connection(e.to_string()))?;
+            if !status.is_success() {
+                return Err(SignerError::Protocol(format!(
+                    "Signing service returned {status}: {text}"
+                )));
+            }
+            serde_json::from_str(&text).map_err(|e| SignerError::Protocol(e.to_string()))
+        })
+        .join()
+        .map_err(|_| SignerError::Custom("Signing request panicked".to_string()))?
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let body = json!({
+            "pubkey": self.pubkey.to_string(),
+            "message": anchor_lang::__private::base64::encode(message),
+        });
+        let response = self.request("sign", Some(body))?;
+        let signature = response
+            .get("signature")
+            .and_then(JsonValue::as_str)
+            .and_then(|signature| Signature::from_str(signature).ok())
+            .ok_or_else(|| {
+                SignerError::Protocol(format!("Invalid signature response: {response}"))
+            })?;
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            return Err(PresignerError::VerificationFailure.into());
+        }
+
+        Ok(signature)
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Client, Cluster, LocalSigningServer, MockBackend};
+    use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+    use solana_sdk::signature::Keypair;
+    use std::sync::Arc;
+
+    #[test]
+    fn remote_signing() {
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+        let server = LocalSigningServer::start(keypair).unwrap();
+        let signer = RemoteSigner::connect(server.url()).unwrap();
+        assert_eq!(signer.pubkey(), pubkey);
+        let signature = signer.try_sign_message(b"message").unwrap();
+        assert!(signature.verify(pubkey.as_ref(), b"message"));
+
+        // The key of another signer can't be used.
+        let unknown = RemoteSigner::new(server.url(), Pubkey::new_unique());
+        let printed = format!("{:?}", unknown.clone().auth_token("secret-token"));
+        assert!(printed.contains("<redacted>") && !printed.contains("secret-token"));
+        assert!(matches!(
+            unknown.try_sign_message(b"message"),
+            Err(SignerError::Protocol(_))
+        ));
+
+        // Signing as the payer, from within the runtime of the client.
+        let program_id = Pubkey::new_unique();
+        let backend = Arc::new(MockBackend::new());
+        let client = Client::new(Cluster::Localnet, Arc::new(signer)).with_backend(backend.clone());
+        let program = client.program(program_id).unwrap();
+        let request = program.request().instruction(Instruction::new_with_bytes(
+            program_id,
+            &[1],
+            vec![AccountMeta::new(pubkey, true)],
+        ));
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(request.send_internal()).unwrap();
+        assert_eq!(backend.transactions().len(), 1);
+    }
+}
@@ -0,0 +1,98 @@
+//! Retries of sent requests, see `RequestBuilder::retry`.
+
+use crate::ClientError;
+use anchor_lang::error::ErrorCode;
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::account_utils::StateMut;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::nonce::state::{State, Versions};
+use solana_sdk::system_program;
+use std::time::Duration;
+
+/// Sends a request until it lands, instead of relying on the confirmation
+/// of the RPC client.
+///
+/// The transaction is rebroadcast until it's confirmed or its blockhash
+/// expires, i.e. the finalized block height passes its last valid block
+/// height. Only then, once the transaction can no longer land, it's signed
+/// again with a fresh blockhash. Transactions using a durable nonce expire
+/// once the nonce is advanced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// The time between rebroadcasts of the transaction.
+    pub rebroadcast_interval: Duration,
+    /// The maximum number of times the transaction is signed again after
+    /// expiring.
+    pub max_resigns: usize,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            rebroadcast_interval: Duration::from_secs(2),
+            max_resigns: 3,
+        }
+    }
+}
+
+// Until when a signed transaction can land.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Lifetime {
+    // The last valid block height of the blockhash.
+    BlockHeight(u64),
+    // The durable nonce, until the nonce account is advanced.
+    Nonce { nonce_account: Pubkey, nonce: Hash },
+}
+
+impl Lifetime {
+    // Returns whether a transaction signed with this lifetime can no longer
+    // land, on any fork.
+    pub async fn is_expired(&self, rpc_client: &AsyncRpcClient) -> Result<bool, ClientError> {
+        let finalized = CommitmentConfig::finalized();
+        match self {
+            Lifetime::BlockHeight(last_valid_block_height) => {
+                let block_height = rpc_client
+                    .get_block_height_with_commitment(finalized)
+                    .await?;
+                Ok(block_height > *last_valid_block_height)
+            }
+            Lifetime::Nonce {
+                nonce_account,
+                nonce,
+            } => Ok(get_nonce(rpc_client, nonce_account, finalized).await? != *nonce),
+        }
+    }
+}
+
+// Returns the durable nonce stored in `nonce_account`.
+pub(crate) async fn get_nonce(
+    rpc_client: &AsyncRpcClient,
+    nonce_account: &Pubkey,
+    commitment: CommitmentConfig,
+) -> Result<Hash, ClientError> {
+    let account = rpc_client
+        .get_account_with_commitment(nonce_account, commitment)
+        .await?
+        .value
+        .ok_or(ClientError::AccountNotFound)?;
+    nonce_of(&account)
+}
+
+// Returns the durable nonce stored in a nonce account.
+pub(crate) fn nonce_of(account: &Account) -> Result<Hash, ClientError> {
+    if account.owner != system_program::ID {
+        return Err(anchor_lang::error::Error::from(ErrorCode::AccountOwnedByWrongProgram).into());
+    }
+    let versions: Versions = account
+        .state()
+        .map_err(|_| anchor_lang::error::Error::from(ErrorCode::AccountDidNotDeserialize))?;
+    match versions.state() {
+        State::Initialized(data) => Ok(data.blockhash()),
+        State::Uninitialized => {
+            Err(anchor_lang::error::Error::from(ErrorCode::AccountNotInitialized).into())
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! A signing service for tests of [`RemoteSigner`](crate::RemoteSigner),
+//! available with the `test-utils` feature.
+
+use serde_json::{json, Value as JsonValue};
+use solana_sdk::signature::{Keypair, Signer};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// A signing service holding a single keypair, served on localhost until
+/// dropped, for tests of [`RemoteSigner`](crate::RemoteSigner).
+pub struct LocalSigningServer {
+    url: String,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl LocalSigningServer {
+    /// Serves `keypair` on a free port.
+    pub fn start(keypair: Keypair) -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let shutdown = shutdown.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        // Failures only concern the client of the request.
+                        let _ = serve(stream, &keypair);
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            url,
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Drop for LocalSigningServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wakes the server up to stop.
+        let _ = TcpStream::connect(self.url.trim_start_matches("http://"));
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+// Answers a single request, the connection being closed after.
+fn serve(stream: TcpStream, keypair: &Keypair) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/pubkey")) => Ok(json!({ "pubkey": keypair.pubkey().to_string() })),
+        (Some("POST"), Some("/sign")) => sign(&body, keypair),
+        _ => Err(("404 Not Found", "Not found".to_string())),
+    };
+    let (status, body) = match response {
+        Ok(body) => ("200 OK", body.to_string()),
+        Err((status, msg)) => (status, json!({ "error": msg }).to_string()),
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+fn sign(body: &[u8], keypair: &Keypair) -> Result<JsonValue, (&'static str, String)> {
+    let bad_request = |msg: String| ("400 Bad Request", msg);
+    let body: JsonValue = serde_json::from_slice(body).map_err(|e| bad_request(e.to_string()))?;
+    if body.get("pubkey").and_then(JsonValue::as_str) != Some(&keypair.pubkey().to_string()) {
+        return Err(bad_request("Unknown pubkey".to_string()));
+    }
+    let message = body
+        .get("message")
+        .and_then(JsonValue::as_str)
+        .and_then(|message| anchor_lang::__private::base64::decode(message).ok())
+        .ok_or_else(|| bad_request("Invalid message".to_string()))?;
+
+    Ok(json!({ "signature": keypair.sign_message(&message).to_string() }))
+}
@@ -0,0 +1,258 @@
+//! Results of simulating a request, see `RequestBuilder::simulate`.
+
+use crate::logs::{emitted_events, Invocation, InvocationTree};
+use crate::ClientError;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{AnchorDeserialize, Event};
+use regex::Regex;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+
+/// The outcome of a simulated transaction.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    /// The error the transaction failed with, if any.
+    pub err: Option<TransactionError>,
+    /// The raw log messages.
+    pub logs: Vec<String>,
+    /// The compute units consumed by the whole transaction.
+    pub units_consumed: Option<u64>,
+    /// The top level program invocations, with their CPIs.
+    pub invocations: Vec<Invocation>,
+    /// Whether the logs were truncated by the log limit.
+    pub logs_truncated: bool,
+    /// The last Anchor error logged by a program, if any.
+    pub error: Option<LoggedError>,
+    /// The program setting the return data, and the data.
+    pub return_data: Option<(Pubkey, Vec<u8>)>,
+    /// The program of the simulated request, whose events and return data
+    /// are decoded.
+    pub program_id: Pubkey,
+}
+
+/// An error logged by an Anchor program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggedError {
+    pub code: u32,
+    pub name: String,
+    pub msg: String,
+    pub origin: Option<LoggedErrorOrigin>,
+    /// The left and right values of failed `require_*!` comparisons.
+    pub compared: Option<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoggedErrorOrigin {
+    Source { file: String, line: u32 },
+    AccountName(String),
+}
+
+impl Simulation {
+    /// Parses the outcome of a simulated transaction, e.g. for backends.
+    pub fn new(
+        err: Option<TransactionError>,
+        logs: Vec<String>,
+        units_consumed: Option<u64>,
+        return_data: Option<(Pubkey, Vec<u8>)>,
+    ) -> Self {
+        let tree = InvocationTree::parse(&logs);
+        let error = parse_error(&logs);
+        Self {
+            err,
+            logs,
+            units_consumed,
+            invocations: tree.invocations,
+            logs_truncated: tree.truncated,
+            error,
+            return_data,
+            program_id: Pubkey::default(),
+        }
+    }
+
+    /// Returns the custom error code the transaction failed with, if any.
+    pub fn error_code(&self) -> Option<u32> {
+        match &self.err {
+            Some(TransactionError::InstructionError(_, InstructionError::Custom(code))) => {
+                Some(*code)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the Anchor framework error the transaction failed with, if
+    /// any. Program specific errors can be found with `from_code` on their
+    /// `#[error_code]` enum.
+    pub fn anchor_error_code(&self) -> Option<anchor_lang::error::ErrorCode> {
+        self.error_code()
+            .and_then(anchor_lang::error::ErrorCode::from_code)
+    }
+
+    /// Decodes the events of type `T` emitted by the request's program
+    /// during the simulation, in the order they were emitted.
+    pub fn events<T: Event + AnchorDeserialize>(&self) -> Result<Vec<T>, ClientError> {
+        emitted_events(
+            self.invocations
+                .iter()
+                .flat_map(Invocation::iter)
+                .filter(|invocation| invocation.program_id == self.program_id),
+        )
+        .into_iter()
+        .collect()
+    }
+
+    /// Decodes the return data, e.g. the value returned by an instruction
+    /// with a non unit return type. `None` if the request's program returned
+    /// nothing.
+    pub fn return_data<T: AnchorDeserialize>(&self) -> Result<Option<T>, ClientError> {
+        match &self.return_data {
+            Some((program_id, data)) if *program_id == self.program_id => {
+                Ok(Some(T::deserialize(&mut &data[..])?))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+// Parses the last `AnchorError` logged, along with its compared values.
+pub(crate) fn parse_error(logs: &[String]) -> Option<LoggedError> {
+    let msgs: Vec<&str> = logs
+        .iter()
+        .filter_map(|log| log.strip_prefix(crate::PROGRAM_LOG))
+        .collect();
+    parse_logged_error(&msgs)
+}
+
+// Parses the last `AnchorError` of `msg!` logs, without their `Program log: `
+// prefix.
+pub(crate) fn parse_logged_error(msgs: &[&str]) -> Option<LoggedError> {
+    let error_re = Regex::new(
+        r"^AnchorError (?:thrown in (?P<file>.+):(?P<line>\d+)|caused by account: (?P<account>.+)|occurred)\. Error Code: (?P<name>.+)\. Error Number: (?P<code>\d+)\. Error Message: (?P<msg>.*)\.$",
+    )
+    .unwrap();
+
+    let (i, captures) = msgs
+        .iter()
+        .enumerate()
+        .rev()
+        .find_map(|(i, msg)| error_re.captures(msg).map(|captures| (i, captures)))?;
+
+    let origin = if let (Some(file), Some(line)) = (captures.name("file"), captures.name("line")) {
+        Some(LoggedErrorOrigin::Source {
+            file: file.as_str().to_string(),
+            line: line.as_str().parse().ok()?,
+        })
+    } else {
+        captures
+            .name("account")
+            .map(|account| LoggedErrorOrigin::AccountName(account.as_str().to_string()))
+    };
+    // Values are logged as `Left: <value>`, pubkeys as `Left:` followed by
+    // the pubkey on its own line.
+    let compared = match &msgs[i + 1..] {
+        ["Left:", left, "Right:", right, ..] => Some((left.to_string(), right.to_string())),
+        [left, right, ..] => match (left.strip_prefix("Left: "), right.strip_prefix("Right: ")) {
+            (Some(left), Some(right)) => Some((left.to_string(), right.to_string())),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    Some(LoggedError {
+        code: captures["code"].parse().ok()?,
+        name: captures["name"].to_string(),
+        msg: captures["msg"].to_string(),
+        origin,
+        compared,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logs::tests::{event_data, to_logs, Counted};
+
+    #[test]
+    fn program_events_and_return_data() {
+        let program = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let logs = to_logs(&[
+            &format!("Program {program} invoke [1]"),
+            &format!("Program data: {}", event_data(1)),
+            &format!("Program {other} invoke [2]"),
+            &format!("Program data: {}", event_data(2)),
+            &format!("Program {other} success"),
+            &format!("Program {program} invoke [2]"),
+            &format!("Program data: {}", event_data(3)),
+            &format!("Program {program} success"),
+            &format!("Program data: {}", event_data(4)),
+            &format!("Program {program} success"),
+        ]);
+        let mut simulation = Simulation::new(None, logs, None, Some((other, vec![7, 0])));
+        simulation.program_id = program;
+
+        let counts: Vec<u64> = simulation
+            .events::<Counted>()
+            .unwrap()
+            .into_iter()
+            .map(|event| event.count)
+            .collect();
+        // Events of other programs are ignored, self-CPIs kept in order.
+        assert_eq!(counts, vec![1, 3, 4]);
+        assert_eq!(simulation.return_data::<u16>().unwrap(), None);
+
+        simulation.return_data = Some((program, vec![7, 0]));
+        assert_eq!(simulation.return_data::<u16>().unwrap(), Some(7));
+    }
+
+    #[test]
+    fn anchor_errors() {
+        let key = Pubkey::new_unique();
+        let program = Pubkey::new_unique();
+        let err = Some(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(2001),
+        ));
+        let logs = to_logs(&[
+            &format!("Program {program} invoke [1]"),
+            "Program log: AnchorError caused by account: counter. Error Code: ConstraintHasOne. Error Number: 2001. Error Message: A has one constraint was violated.",
+            "Program log: Left:",
+            &format!("Program log: {key}"),
+            "Program log: Right:",
+            &format!("Program log: {program}"),
+            &format!("Program {program} failed: custom program error: 0x7d1"),
+        ]);
+        let simulation = Simulation::new(err, logs, None, None);
+        assert_eq!(
+            simulation.error,
+            Some(LoggedError {
+                code: 2001,
+                name: "ConstraintHasOne".to_string(),
+                msg: "A has one constraint was violated".to_string(),
+                origin: Some(LoggedErrorOrigin::AccountName("counter".to_string())),
+                compared: Some((key.to_string(), program.to_string())),
+            })
+        );
+        assert_eq!(simulation.error_code(), Some(2001));
+        assert!(matches!(
+            simulation.anchor_error_code(),
+            Some(anchor_lang::error::ErrorCode::ConstraintHasOne)
+        ));
+        assert_eq!(simulation.invocations[0].success, Some(false));
+
+        let logs = to_logs(&[
+            "Program log: AnchorError thrown in programs/counter/src/lib.rs:42. Error Code: TooLarge. Error Number: 6000. Error Message: Too large.",
+            "Program log: Left: 11",
+            "Program log: Right: 10",
+        ]);
+        let error = Simulation::new(None, logs, None, None).error.unwrap();
+        assert_eq!(
+            error.origin,
+            Some(LoggedErrorOrigin::Source {
+                file: "programs/counter/src/lib.rs".to_string(),
+                line: 42,
+            })
+        );
+        assert_eq!(error.compared, Some(("11".to_string(), "10".to_string())));
+        assert_eq!(error.code, 6000);
+    }
+}
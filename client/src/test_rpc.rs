@@ -0,0 +1,215 @@
+//! Local stand-ins for a cluster's JSON RPC and websocket, answering
+//! requests with a handler, to test requests without a validator.
+
+use serde_json::{json, Value as JsonValue};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+#[cfg(not(feature = "async"))]
+use tungstenite::Message;
+
+type Handler = dyn Fn(&str, &JsonValue) -> Option<JsonValue> + Send + Sync;
+#[cfg(not(feature = "async"))]
+type NotificationsHandler = dyn Fn(&str) -> Vec<JsonValue> + Send + Sync;
+type Requests = Arc<Mutex<Vec<(String, JsonValue)>>>;
+
+pub struct TestRpc {
+    url: String,
+    requests: Requests,
+}
+
+impl TestRpc {
+    /// Starts serving requests in the background. `handler` returns the
+    /// result of a method given its params, `None` for unsupported methods.
+    /// `getVersion` is answered if the handler doesn't.
+    pub fn start(
+        handler: impl Fn(&str, &JsonValue) -> Option<JsonValue> + Send + Sync + 'static,
+    ) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+        {
+            let requests = requests.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let requests = requests.clone();
+                    let handler = handler.clone();
+                    thread::spawn(move || serve(stream, &*handler, &requests));
+                }
+            });
+        }
+
+        Self { url, requests }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the params of the requests of `method` received so far.
+    pub fn requests(&self, method: &str) -> Vec<JsonValue> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(name, _)| name == method)
+            .map(|(_, params)| params.clone())
+            .collect()
+    }
+}
+
+// Serves the requests of a kept alive connection.
+fn serve(stream: TcpStream, handler: &Handler, requests: &Mutex<Vec<(String, JsonValue)>>) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut content_len = 0;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_len = value.trim().parse().unwrap();
+                }
+            }
+        }
+        let mut body = vec![0; content_len];
+        if reader.read_exact(&mut body).is_err() {
+            return;
+        }
+
+        let request: JsonValue = serde_json::from_slice(&body).unwrap();
+        let method = request["method"].as_str().unwrap_or_default().to_string();
+        let params = request["params"].clone();
+        // The version is queried by the RPC client for some requests.
+        let result = handler(&method, &params).or_else(|| match method.as_str() {
+            "getVersion" => Some(json!({ "solana-core": "1.16.0", "feature-set": 0 })),
+            _ => None,
+        });
+        let response = match result {
+            Some(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+            None => json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": -32601, "message": "Method not found" },
+            }),
+        };
+        requests.lock().unwrap().push((method, params));
+
+        let response = response.to_string();
+        let written = write!(
+            writer,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            response.len(),
+            response
+        );
+        if written.is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(not(feature = "async"))]
+pub struct TestPubsub {
+    url: String,
+    requests: Requests,
+}
+
+#[cfg(not(feature = "async"))]
+impl TestPubsub {
+    /// Starts serving subscriptions in the background. `handler` returns
+    /// the results of the notifications sent on subscribing, given the
+    /// subscribed operation, e.g. `account`.
+    pub fn start(handler: impl Fn(&str) -> Vec<JsonValue> + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<NotificationsHandler> = Arc::new(handler);
+        {
+            let requests = requests.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let requests = requests.clone();
+                    let handler = handler.clone();
+                    thread::spawn(move || serve_pubsub(stream, &*handler, &requests));
+                }
+            });
+        }
+
+        Self { url, requests }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the params of the requests of `method` received so far.
+    pub fn requests(&self, method: &str) -> Vec<JsonValue> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(name, _)| name == method)
+            .map(|(_, params)| params.clone())
+            .collect()
+    }
+}
+
+// Serves the subscriptions of a websocket connection, numbered from 1.
+#[cfg(not(feature = "async"))]
+fn serve_pubsub(stream: TcpStream, handler: &NotificationsHandler, requests: &Requests) {
+    let mut ws = match tungstenite::accept(stream) {
+        Ok(ws) => ws,
+        Err(_) => return,
+    };
+    let mut subscriptions = 0;
+    while let Ok(message) = ws.read_message() {
+        let request: JsonValue = match message {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            Message::Close(_) => return,
+            _ => continue,
+        };
+        let method = request["method"].as_str().unwrap_or_default().to_string();
+        requests
+            .lock()
+            .unwrap()
+            .push((method.clone(), request["params"].clone()));
+
+        let response = |result| json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+        let mut messages = Vec::new();
+        if method == "getVersion" {
+            messages.push(response(
+                json!({ "solana-core": "1.16.0", "feature-set": 0 }),
+            ));
+        } else if let Some(operation) = method.strip_suffix("Subscribe") {
+            subscriptions += 1;
+            messages.push(response(json!(subscriptions)));
+            for result in handler(operation) {
+                messages.push(json!({
+                    "jsonrpc": "2.0",
+                    "method": format!("{operation}Notification"),
+                    "params": { "result": result, "subscription": subscriptions },
+                }));
+            }
+        } else {
+            // Unsubscribing.
+            messages.push(response(json!(true)));
+        }
+        for message in messages {
+            if ws
+                .write_message(Message::Text(message.to_string()))
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Sends and simulates requests through the `BanksBackend`, against a
+//! builtin program of `ProgramTest`.
+#![cfg(not(feature = "async"))]
+
+use anchor_client::solana_sdk::account::Account;
+use anchor_client::solana_sdk::transaction::TransactionError;
+use anchor_client::{Backend, BanksBackend, Client, ClientError, Cluster};
+use anchor_lang::solana_program::account_info::{next_account_info, AccountInfo};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use anchor_lang::solana_program::msg;
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use solana_program_test::{processor, ProgramTest};
+use std::sync::Arc;
+
+// Stores the instruction data in the account and returns it, failing
+// without data.
+fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if data.is_empty() {
+        return Err(ProgramError::Custom(6000));
+    }
+    let account = next_account_info(&mut accounts.iter())?;
+    account.try_borrow_mut_data()?.copy_from_slice(data);
+    msg!("Stored");
+    set_return_data(data);
+    Ok(())
+}
+
+#[test]
+fn send_and_simulate() {
+    let program_id = Pubkey::new_unique();
+    let counter = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new("counter", program_id, processor!(process_instruction));
+    program_test.add_account(
+        counter,
+        Account {
+            lamports: 1_000_000_000,
+            data: vec![0; 8],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    // The bank is served by this runtime, the client blocking on its own.
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let (banks_client, payer, _) = rt.block_on(program_test.start());
+    let backend = Arc::new(BanksBackend::new(banks_client));
+    let client = Client::new(Cluster::Localnet, Arc::new(payer)).with_backend(backend.clone());
+    let program = client.program(program_id).unwrap();
+    let store = |data: &[u8]| {
+        program.request().instruction(Instruction::new_with_bytes(
+            program_id,
+            data,
+            vec![AccountMeta::new(counter, false)],
+        ))
+    };
+    let stored = || {
+        rt.block_on(backend.get_account(&counter))
+            .unwrap()
+            .unwrap()
+            .data
+    };
+
+    store(&7u64.to_le_bytes()).send().unwrap();
+    assert_eq!(stored(), 7u64.to_le_bytes());
+
+    // Simulations don't change the account.
+    let simulation = store(&9u64.to_le_bytes()).simulate().unwrap();
+    assert_eq!(simulation.err, None);
+    assert!(simulation.logs.contains(&"Program log: Stored".to_string()));
+    assert!(simulation.units_consumed.is_some());
+    assert_eq!(simulation.return_data::<u64>().unwrap(), Some(9));
+    assert_eq!(stored(), 7u64.to_le_bytes());
+
+    match store(&[]).send() {
+        Err(ClientError::SolanaClientError(err)) => assert_eq!(
+            err.get_transaction_error(),
+            Some(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(6000)
+            ))
+        ),
+        result => panic!("Unexpected result: {result:?}"),
+    }
+    assert_eq!(stored(), 7u64.to_le_bytes());
+}
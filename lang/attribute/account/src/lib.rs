@@ -2,7 +2,7 @@ extern crate proc_macro;
 
 #[cfg(feature = "idl-build")]
 use anchor_syn::idl::build::*;
-use quote::quote;
+use quote::{format_ident, quote};
 use syn::parse_macro_input;
 
 mod id;
@@ -27,6 +27,16 @@ mod id;
 /// check this discriminator. If it doesn't match, an invalid account was given,
 /// and the account deserialization will exit with an error.
 ///
+/// # Field Offsets
+///
+/// For every field at a fixed offset in the account data, an associated
+/// [`FieldOffset`](./struct.FieldOffset.html) constant named after the field
+/// is generated, e.g. `Vault::authority`, to filter accounts by field from
+/// clients. With Borsh, only the fields preceded by fixed size fields
+/// (integers, `bool`, `Pubkey` and arrays of them) are at a fixed offset. With
+/// zero copy, all fields are. Inherent methods can't share the name of a
+/// field as a result.
+///
 /// # Zero Copy Deserialization
 ///
 /// **WARNING**: Zero copy deserialization is an experimental feature. It's
@@ -158,6 +168,8 @@ pub fn account(
         }
     };
 
+    let field_offsets = gen_field_offsets(&account_strct, is_zero_copy, unsafe_bytemuck);
+
     proc_macro::TokenStream::from({
         if is_zero_copy {
             quote! {
@@ -199,6 +211,8 @@ pub fn account(
                 }
 
                 #owner_impl
+
+                #field_offsets
             }
         } else {
             quote! {
@@ -245,11 +259,125 @@ pub fn account(
                 }
 
                 #owner_impl
+
+                #field_offsets
             }
         }
     })
 }
 
+// Generates the `FieldOffset` constants of the fields of an account. Borsh
+// accounts only get the fields preceded by fixed size fields, zero-copy
+// accounts all of them, padding included unless packed.
+fn gen_field_offsets(
+    account_strct: &syn::ItemStruct,
+    is_zero_copy: bool,
+    unsafe_bytemuck: bool,
+) -> proc_macro2::TokenStream {
+    let fields = match &account_strct.fields {
+        syn::Fields::Named(fields) => &fields.named,
+        _ => return quote! {},
+    };
+    let account_name = &account_strct.ident;
+    let (impl_gen, type_gen, where_clause) = account_strct.generics.split_for_impl();
+
+    // `#[zero_copy(unsafe)]` packs the struct unless given another repr.
+    let packed = match account_strct
+        .attrs
+        .iter()
+        .find(|attr| anchor_syn::parser::tts_to_string(&attr.path) == "repr")
+    {
+        Some(attr) => attr.tokens.to_string().contains("packed"),
+        None => unsafe_bytemuck,
+    };
+
+    let mut consts = Vec::new();
+    let mut prev: Option<&syn::Ident> = None;
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let end = match prev {
+            Some(prev) => quote! { Self::#prev.offset + Self::#prev.size },
+            None => quote! { 8 },
+        };
+        let (offset, size) = if is_zero_copy {
+            let align = if packed {
+                quote! { 1 }
+            } else {
+                quote! { ::core::mem::align_of::<#ty>() }
+            };
+            (
+                quote! { anchor_lang::__private::field_offset(#end, #align) },
+                quote! { ::core::mem::size_of::<#ty>() },
+            )
+        } else {
+            match borsh_fixed_size(ty) {
+                Some(size) => (end, size),
+                None => break,
+            }
+        };
+        let vis = &field.vis;
+        let doc = format!("The offset of the `{ident}` field in the account data.");
+        consts.push(quote! {
+            #[doc = #doc]
+            #vis const #ident: anchor_lang::FieldOffset<#account_name #type_gen, #ty> =
+                anchor_lang::FieldOffset::new(#offset, #size);
+        });
+        prev = Some(ident);
+    }
+    if consts.is_empty() {
+        return quote! {};
+    }
+
+    // The offsets are kept apart from the account, for them not to collide
+    // with its own associated items.
+    let vis = &account_strct.vis;
+    let generics = &account_strct.generics;
+    let offsets_name = format_ident!("{}Offsets", account_name);
+    let doc = format!("The offsets of the fields of [`{account_name}`] in its account data.");
+    quote! {
+        #[doc = #doc]
+        #[allow(dead_code)]
+        #vis struct #offsets_name #generics (
+            ::core::marker::PhantomData<fn() -> #account_name #type_gen>
+        ) #where_clause;
+
+        #[automatically_derived]
+        #[allow(dead_code, non_upper_case_globals)]
+        impl #impl_gen #offsets_name #type_gen #where_clause {
+            #(#consts)*
+        }
+    }
+}
+
+// Returns the Borsh serialized size of `ty` if it's known to be fixed.
+fn borsh_fixed_size(ty: &syn::Type) -> Option<proc_macro2::TokenStream> {
+    match ty {
+        syn::Type::Path(path) if path.qself.is_none() => {
+            let segment = path.path.segments.last()?;
+            if !segment.arguments.is_empty() {
+                return None;
+            }
+            let size: usize = match segment.ident.to_string().as_str() {
+                "u8" | "i8" | "bool" => 1,
+                "u16" | "i16" => 2,
+                "u32" | "i32" | "f32" => 4,
+                "u64" | "i64" | "f64" => 8,
+                "u128" | "i128" => 16,
+                "Pubkey" => 32,
+                _ => return None,
+            };
+            Some(quote! { #size })
+        }
+        syn::Type::Array(array) => {
+            let elem = borsh_fixed_size(&array.elem)?;
+            let len = &array.len;
+            Some(quote! { (#len) * (#elem) })
+        }
+        _ => None,
+    }
+}
+
 #[proc_macro_derive(ZeroCopyAccessor, attributes(accessor))]
 pub fn derive_zero_copy_accessor(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let account_strct = parse_macro_input!(item as syn::ItemStruct);
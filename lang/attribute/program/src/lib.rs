@@ -14,3 +14,47 @@ pub fn program(
         .to_token_stream()
         .into()
 }
+
+/// Declares a program from its IDL, for programs without a crate to depend on.
+///
+/// The path of the IDL is relative to the root of the crate. It generates a
+/// module named after the program, containing everything the `#[program]`
+/// macro generates for clients:
+///
+/// - `ID` and a `program::<ProgramName>` type to use with `Program<'info, T>`
+/// - The types, accounts, events and `ErrorCode` of the program
+/// - The `instruction`, `accounts` and `cpi` modules
+///
+/// Since the IDL doesn't keep the name of `Accounts` structs, the structs of
+/// the `accounts` and `cpi::accounts` modules are named after the
+/// instructions. The IDL must have a `metadata.address` field, which
+/// `anchor build` and `anchor deploy` set.
+///
+/// # Example
+///
+/// ```ignore
+/// declare_program!("idls/counter.json");
+///
+/// pub fn increment(ctx: Context<Increment>) -> Result<()> {
+///     let cpi_ctx = CpiContext::new(
+///         ctx.accounts.counter_program.to_account_info(),
+///         counter::cpi::accounts::Increment {
+///             counter: ctx.accounts.counter.to_account_info(),
+///         },
+///     );
+///     counter::cpi::increment(cpi_ctx, 1)
+/// }
+///
+/// #[derive(Accounts)]
+/// pub struct Increment<'info> {
+///     #[account(mut)]
+///     pub counter: Account<'info, counter::Counter>,
+///     pub counter_program: Program<'info, counter::program::Counter>,
+/// }
+/// ```
+#[proc_macro]
+pub fn declare_program(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    parse_macro_input!(input as anchor_syn::DeclaredProgram)
+        .to_token_stream()
+        .into()
+}
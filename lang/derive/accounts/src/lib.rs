@@ -110,7 +110,11 @@ use syn::parse_macro_input;
 ///                         and be called <code>system_program</code>.
 ///                     </li>
 ///                     <li>
-///                         Requires that the <code>space</code> constraint is specified.
+///                         If the <code>space</code> constraint is omitted, it defaults to the size of the
+///                         discriminator plus <code>T::INIT_SPACE</code> for an <code>Account&lt;T&gt;</code> or
+///                         <code>InterfaceAccount&lt;T&gt;</code> (see <code>#[derive(InitSpace)]</code>),
+///                         or plus <code>size_of::&lt;T&gt;()</code> for an <code>AccountLoader&lt;T&gt;</code>.
+///                         Any other account type requires the <code>space</code> constraint.
 ///                         When using the <code>space</code> constraint, one must remember to add 8 to it
 ///                         which is the size of the account discriminator. This only has to be done
 ///                         for accounts owned by anchor programs.<br>
@@ -383,15 +387,19 @@ use syn::parse_macro_input;
 ///         </tr>
 ///         <tr>
 ///             <td>
-///                 <code>#[account(close = &lt;target_account&gt;)]</code>
+///                 <code>#[account(close = &lt;target_account&gt;)]</code><br><br>
+///                 <code>#[account(close = &lt;target_account&gt;, tombstone)]</code>
 ///             </td>
 ///             <td>
 ///                 Marks the account as closed at the end of the instruction’s execution
-///                 (sets its discriminator to the <code>CLOSED_ACCOUNT_DISCRIMINATOR</code>)
+///                 (assigns it to the system program and clears its data)
 ///                 and sends its lamports to the specified account.<br>
-///                 Setting the discriminator to a special variant
-///                 makes account revival attacks (where a subsequent instruction
-///                 adds the rent exemption lamports again) impossible.<br>
+///                 With <code>tombstone</code>, the account stays owned by the program and its
+///                 data is replaced by the <code>CLOSED_ACCOUNT_DISCRIMINATOR</code> instead.
+///                 This makes account revival attacks (where a subsequent instruction
+///                 adds the rent exemption lamports again) impossible, since <code>Account</code>
+///                 rejects tombstoned data. Lamports sent to a tombstone can be reclaimed
+///                 with <code>anchor_lang::force_defund</code>.<br>
 ///                 Requires <code>mut</code> to exist on the account.
 ///                 <br><br>
 ///                 Example:
@@ -441,6 +449,11 @@ use syn::parse_macro_input;
 ///                 to prevent reallocation over the `MAX_PERMITTED_DATA_INCREASE` limit (which can unintentionally cause account data overwrite other accounts).
 ///                 The constraint group also ensure account reallocation idempotency but checking and restricting duplicate account reallocation within a single ix.
 ///                 <br><br>
+///                 Adding <code>realloc::incremental</code> lifts the <code>MAX_PERMITTED_DATA_INCREASE</code> limit: each instruction grows the account
+///                 by at most that many bytes towards the target size. Until the target is reached the instruction handler is skipped and the
+///                 instruction only performs the growth step, so the instruction must be repeated (see <code>RequestBuilder::send_realloc_incremental</code>
+///                 in <code>anchor-client</code>) before the handler runs with the fully grown account.
+///                 <br><br>
 ///                 Example:
 ///                 <pre>
 /// #[derive(Accounts)]
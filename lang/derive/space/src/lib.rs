@@ -4,8 +4,9 @@ use proc_macro::TokenStream;
 use proc_macro2::{Ident, TokenStream as TokenStream2, TokenTree};
 use quote::{quote, quote_spanned, ToTokens};
 use syn::{
-    parse::ParseStream, parse2, parse_macro_input, Attribute, DeriveInput, Fields, GenericArgument,
-    LitInt, PathArguments, Type, TypeArray,
+    parse::ParseStream, parse2, parse_macro_input, spanned::Spanned, Attribute, DeriveInput, Expr,
+    Fields, GenericArgument, LitInt, PathArguments, Type, TypeArray, TypeGroup, TypeParen,
+    TypeTuple,
 };
 
 /// Implements a [`Space`](./trait.Space.html) trait on the given
@@ -13,6 +14,13 @@ use syn::{
 ///
 /// For types that have a variable size like String and Vec, it is necessary to indicate the size by the `max_len` attribute.
 /// For nested types, it is necessary to specify a size for each variable type (see example).
+/// Maps take the maximum number of entries first, followed by the lengths of the key and then
+/// the value type.
+///
+/// The size of any field can be set explicitly with the `space` attribute, which takes
+/// precedence over the computed size.
+///
+/// Enums are sized as their largest variant plus the one byte tag.
 ///
 /// # Example
 /// ```ignore
@@ -24,6 +32,10 @@ use syn::{
 ///     pub string_one: String,
 ///     #[max_len(10, 5)]
 ///     pub nested: Vec<Vec<u8>>,
+///     #[max_len(4, 32)]
+///     pub labels: BTreeMap<String, u64>,
+///     #[space(64)]
+///     pub opaque: ExternalType,
 /// }
 ///
 /// #[derive(Accounts)]
@@ -35,35 +47,26 @@ use syn::{
 ///    pub data: Account<'info, ExampleAccount>,
 /// }
 /// ```
-#[proc_macro_derive(InitSpace, attributes(max_len))]
+#[proc_macro_derive(InitSpace, attributes(max_len, space))]
 pub fn derive_init_space(item: TokenStream) -> TokenStream {
     let input = parse_macro_input!(item as DeriveInput);
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
     let name = input.ident;
 
     let expanded: TokenStream2 = match input.data {
-        syn::Data::Struct(strct) => match strct.fields {
-            Fields::Named(named) => {
-                let recurse = named.named.into_iter().map(|f| {
-                    let mut max_len_args = get_max_len_args(&f.attrs);
-                    len_from_type(f.ty, &mut max_len_args)
-                });
+        syn::Data::Struct(strct) => {
+            let recurse = fields_len(strct.fields);
 
-                quote! {
-                    #[automatically_derived]
-                    impl #impl_generics anchor_lang::Space for #name #ty_generics #where_clause {
-                        const INIT_SPACE: usize = 0 #(+ #recurse)*;
-                    }
+            quote! {
+                #[automatically_derived]
+                impl #impl_generics anchor_lang::Space for #name #ty_generics #where_clause {
+                    const INIT_SPACE: usize = 0 #(+ #recurse)*;
                 }
             }
-            _ => panic!("Please use named fields in account structure"),
-        },
+        }
         syn::Data::Enum(enm) => {
             let variants = enm.variants.into_iter().map(|v| {
-                let len = v.fields.into_iter().map(|f| {
-                    let mut max_len_args = get_max_len_args(&f.attrs);
-                    len_from_type(f.ty, &mut max_len_args)
-                });
+                let len = fields_len(v.fields);
 
                 quote! {
                     0 #(+ #len)*
@@ -74,17 +77,31 @@ pub fn derive_init_space(item: TokenStream) -> TokenStream {
 
             quote! {
                 #[automatically_derived]
-                impl anchor_lang::Space for #name {
+                impl #impl_generics anchor_lang::Space for #name #ty_generics #where_clause {
                     const INIT_SPACE: usize = 1 + #max;
                 }
             }
         }
-        _ => unimplemented!(),
+        syn::Data::Union(_) => {
+            quote_spanned!(name.span() => compile_error!("InitSpace cannot be derived for unions");)
+        }
     };
 
     TokenStream::from(expanded)
 }
 
+fn fields_len(fields: Fields) -> impl Iterator<Item = TokenStream2> {
+    fields
+        .into_iter()
+        .map(|f| match get_space_override(&f.attrs) {
+            Some(space) => space,
+            None => {
+                let mut max_len_args = get_max_len_args(&f.attrs);
+                len_from_type(f.ty, &mut max_len_args)
+            }
+        })
+}
+
 fn gen_max<T: Iterator<Item = TokenStream2>>(mut iter: T) -> TokenStream2 {
     if let Some(item) = iter.next() {
         let next_item = gen_max(iter);
@@ -127,7 +144,14 @@ fn len_from_type(ty: Type, attrs: &mut Option<VecDeque<TokenStream2>>) -> TokenS
                         quote_spanned!(ident.span() => compile_error!("Invalid argument in Vec"))
                     }
                 }
-                "Vec" => {
+                "Box" => {
+                    if let Some(ty) = first_ty {
+                        len_from_type(ty, attrs)
+                    } else {
+                        quote_spanned!(ident.span() => compile_error!("Invalid argument in Box"))
+                    }
+                }
+                "Vec" | "VecDeque" | "BTreeSet" | "HashSet" => {
                     if let Some(ty) = first_ty {
                         let max_len = get_next_arg(ident, attrs);
                         let type_len = len_from_type(ty, attrs);
@@ -137,24 +161,50 @@ fn len_from_type(ty: Type, attrs: &mut Option<VecDeque<TokenStream2>>) -> TokenS
                         quote_spanned!(ident.span() => compile_error!("Invalid argument in Vec"))
                     }
                 }
+                "BTreeMap" | "HashMap" => {
+                    let mut ty_args = get_ty_args(&path_segment.arguments);
+                    if let (Some(key_ty), Some(value_ty)) = (ty_args.next(), ty_args.next()) {
+                        let max_len = get_next_arg(ident, attrs);
+                        let key_len = len_from_type(key_ty, attrs);
+                        let value_len = len_from_type(value_ty, attrs);
+
+                        quote!((4 + (#key_len + #value_len) * #max_len))
+                    } else {
+                        quote_spanned!(ident.span() => compile_error!("Invalid arguments in map"))
+                    }
+                }
                 _ => {
                     let ty = &ty_path.path;
                     quote!(<#ty as anchor_lang::Space>::INIT_SPACE)
                 }
             }
         }
-        _ => panic!("Type {ty:?} is not supported"),
+        Type::Tuple(TypeTuple { elems, .. }) => {
+            let elems_len = elems.into_iter().map(|ty| len_from_type(ty, attrs));
+            quote!((0 #(+ #elems_len)*))
+        }
+        Type::Group(TypeGroup { elem, .. }) | Type::Paren(TypeParen { elem, .. }) => {
+            len_from_type(*elem, attrs)
+        }
+        ty => {
+            quote_spanned!(ty.span() => compile_error!("Type is not supported, use the space attribute to provide its size"))
+        }
     }
 }
 
 fn get_first_ty_arg(args: &PathArguments) -> Option<Type> {
-    match args {
-        PathArguments::AngleBracketed(bracket) => bracket.args.iter().find_map(|el| match el {
-            GenericArgument::Type(ty) => Some(ty.to_owned()),
-            _ => None,
-        }),
+    get_ty_args(args).next()
+}
+
+fn get_ty_args(args: &PathArguments) -> impl Iterator<Item = Type> + '_ {
+    let args = match args {
+        PathArguments::AngleBracketed(bracket) => Some(bracket.args.iter()),
         _ => None,
-    }
+    };
+    args.into_iter().flatten().filter_map(|el| match el {
+        GenericArgument::Type(ty) => Some(ty.to_owned()),
+        _ => None,
+    })
 }
 
 fn parse_len_arg(item: ParseStream) -> Result<VecDeque<TokenStream2>, syn::Error> {
@@ -181,6 +231,16 @@ fn get_max_len_args(attributes: &[Attribute]) -> Option<VecDeque<TokenStream2>>
         .and_then(|a| a.parse_args_with(parse_len_arg).ok())
 }
 
+fn get_space_override(attributes: &[Attribute]) -> Option<TokenStream2> {
+    attributes
+        .iter()
+        .find(|a| a.path.is_ident("space"))
+        .map(|a| match a.parse_args::<Expr>() {
+            Ok(space) => quote!((#space as usize)),
+            Err(e) => e.to_compile_error(),
+        })
+}
+
 fn get_next_arg(ident: &Ident, args: &mut Option<VecDeque<TokenStream2>>) -> TokenStream2 {
     if let Some(arg_list) = args {
         if let Some(arg) = arg_list.pop_back() {
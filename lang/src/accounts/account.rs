@@ -310,6 +310,9 @@ impl<'a, T: AccountSerialize + AccountDeserialize + Owner + Clone> Account<'a, T
             return Err(Error::from(ErrorCode::AccountOwnedByWrongProgram)
                 .with_pubkeys((*info.owner, T::owner())));
         }
+        if crate::common::is_tombstoned(info) {
+            return Err(ErrorCode::AccountClosed.into());
+        }
         let mut data: &[u8] = &info.try_borrow_data()?;
         Ok(Account::new(info.clone(), T::try_deserialize(&mut data)?))
     }
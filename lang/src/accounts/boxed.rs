@@ -32,6 +32,29 @@ impl<'info, T: Accounts<'info>> Accounts<'info> for Box<T> {
     ) -> Result<Self> {
         T::try_accounts(program_id, accounts, ix_data, bumps, seeds, state, reallocs).map(Box::new)
     }
+
+    fn try_accounts_with_realloc_targets(
+        program_id: &Pubkey,
+        accounts: &mut &[AccountInfo<'info>],
+        ix_data: &[u8],
+        bumps: &mut BTreeMap<String, u8>,
+        seeds: &mut BTreeMap<String, Vec<u8>>,
+        state: &mut BTreeMap<String, Vec<u8>>,
+        reallocs: &mut BTreeSet<Pubkey>,
+        realloc_targets: &mut BTreeMap<Pubkey, usize>,
+    ) -> Result<Self> {
+        T::try_accounts_with_realloc_targets(
+            program_id,
+            accounts,
+            ix_data,
+            bumps,
+            seeds,
+            state,
+            reallocs,
+            realloc_targets,
+        )
+        .map(Box::new)
+    }
 }
 
 impl<'info, T: AccountsExit<'info>> AccountsExit<'info> for Box<T> {
@@ -27,6 +27,28 @@ impl<'info, T: Accounts<'info>> Accounts<'info> for Option<T> {
         seeds: &mut BTreeMap<String, Vec<u8>>,
         state: &mut BTreeMap<String, Vec<u8>>,
         reallocs: &mut BTreeSet<Pubkey>,
+    ) -> Result<Self> {
+        Self::try_accounts_with_realloc_targets(
+            program_id,
+            accounts,
+            ix_data,
+            bumps,
+            seeds,
+            state,
+            reallocs,
+            &mut BTreeMap::new(),
+        )
+    }
+
+    fn try_accounts_with_realloc_targets(
+        program_id: &Pubkey,
+        accounts: &mut &[AccountInfo<'info>],
+        ix_data: &[u8],
+        bumps: &mut BTreeMap<String, u8>,
+        seeds: &mut BTreeMap<String, Vec<u8>>,
+        state: &mut BTreeMap<String, Vec<u8>>,
+        reallocs: &mut BTreeSet<Pubkey>,
+        realloc_targets: &mut BTreeMap<Pubkey, usize>,
     ) -> Result<Self> {
         if accounts.is_empty() {
             return if cfg!(feature = "allow-missing-optionals") {
@@ -52,7 +74,17 @@ impl<'info, T: Accounts<'info>> Accounts<'info> for Option<T> {
             // If the program_id doesn't equal the account key, we default to
             // the try_accounts implementation for the inner type and then wrap that with
             // Some. This should handle all possible valid cases.
-            T::try_accounts(program_id, accounts, ix_data, bumps, seeds, state, reallocs).map(Some)
+            T::try_accounts_with_realloc_targets(
+                program_id,
+                accounts,
+                ix_data,
+                bumps,
+                seeds,
+                state,
+                reallocs,
+                realloc_targets,
+            )
+            .map(Some)
         }
     }
 }
@@ -0,0 +1,324 @@
+//! CPI wrappers for the address lookup table program, and the
+//! [`AddressLookupTable`] account to read tables with.
+//!
+//! The program's instructions and accounts are bincode encoded, and encoded
+//! by hand here to not depend on the program's crate.
+
+use crate::error::ErrorCode;
+use crate::prelude::*;
+use solana_program::instruction::Instruction;
+
+solana_program::declare_id!("AddressLookupTab1e1111111111111111111111111");
+
+/// The maximum number of addresses of a lookup table.
+pub const LOOKUP_TABLE_MAX_ADDRESSES: usize = 256;
+
+/// The size of the metadata of a lookup table, preceding its addresses.
+pub const LOOKUP_TABLE_META_SIZE: usize = 56;
+
+#[derive(Debug, Clone)]
+pub struct AddressLookupTableProgram;
+
+impl anchor_lang::Id for AddressLookupTableProgram {
+    fn id() -> Pubkey {
+        ID
+    }
+}
+
+/// Derives the address of the lookup table of `authority` created with
+/// `recent_slot`.
+pub fn derive_lookup_table_address(authority: &Pubkey, recent_slot: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[authority.as_ref(), &recent_slot.to_le_bytes()], &ID)
+}
+
+/// Creates the lookup table of `authority` at the address derived with
+/// [`derive_lookup_table_address`]. `recent_slot` must be in the
+/// `SlotHashes` sysvar. The authority doesn't need to sign.
+pub fn create_lookup_table<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, CreateLookupTable<'info>>,
+    recent_slot: u64,
+    bump: u8,
+) -> Result<()> {
+    let mut data = instruction_data(0);
+    data.extend_from_slice(&recent_slot.to_le_bytes());
+    data.push(bump);
+    let ix = Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(*ctx.accounts.lookup_table.key, false),
+            AccountMeta::new_readonly(*ctx.accounts.authority.key, false),
+            AccountMeta::new(*ctx.accounts.payer.key, true),
+            AccountMeta::new_readonly(*ctx.accounts.system_program.key, false),
+        ],
+        data,
+    };
+    crate::solana_program::program::invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.lookup_table,
+            ctx.accounts.authority,
+            ctx.accounts.payer,
+            ctx.accounts.system_program,
+        ],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct CreateLookupTable<'info> {
+    pub lookup_table: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+/// Appends `new_addresses` to a lookup table, with `payer` funding the
+/// rent of the grown table.
+pub fn extend_lookup_table<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, ExtendLookupTable<'info>>,
+    new_addresses: Vec<Pubkey>,
+) -> Result<()> {
+    let mut data = instruction_data(2);
+    data.extend_from_slice(&(new_addresses.len() as u64).to_le_bytes());
+    for address in &new_addresses {
+        data.extend_from_slice(address.as_ref());
+    }
+    let ix = Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(*ctx.accounts.lookup_table.key, false),
+            AccountMeta::new_readonly(*ctx.accounts.authority.key, true),
+            AccountMeta::new(*ctx.accounts.payer.key, true),
+            AccountMeta::new_readonly(*ctx.accounts.system_program.key, false),
+        ],
+        data,
+    };
+    crate::solana_program::program::invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.lookup_table,
+            ctx.accounts.authority,
+            ctx.accounts.payer,
+            ctx.accounts.system_program,
+        ],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct ExtendLookupTable<'info> {
+    pub lookup_table: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+/// Makes a lookup table immutable, it can then no longer be extended nor
+/// closed.
+pub fn freeze<'info>(ctx: CpiContext<'_, '_, '_, 'info, Freeze<'info>>) -> Result<()> {
+    authority_instruction(
+        1,
+        ctx.accounts.lookup_table,
+        ctx.accounts.authority,
+        ctx.signer_seeds,
+    )
+}
+
+#[derive(Accounts)]
+pub struct Freeze<'info> {
+    pub lookup_table: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+}
+
+/// Deactivates a lookup table, it can be closed once the deactivation slot
+/// is no longer in the `SlotHashes` sysvar.
+pub fn deactivate<'info>(ctx: CpiContext<'_, '_, '_, 'info, Deactivate<'info>>) -> Result<()> {
+    authority_instruction(
+        3,
+        ctx.accounts.lookup_table,
+        ctx.accounts.authority,
+        ctx.signer_seeds,
+    )
+}
+
+#[derive(Accounts)]
+pub struct Deactivate<'info> {
+    pub lookup_table: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+}
+
+/// Closes a deactivated lookup table, sending its lamports to `recipient`.
+pub fn close<'info>(ctx: CpiContext<'_, '_, '_, 'info, Close<'info>>) -> Result<()> {
+    let ix = Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(*ctx.accounts.lookup_table.key, false),
+            AccountMeta::new_readonly(*ctx.accounts.authority.key, true),
+            AccountMeta::new(*ctx.accounts.recipient.key, false),
+        ],
+        data: instruction_data(4),
+    };
+    crate::solana_program::program::invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.lookup_table,
+            ctx.accounts.authority,
+            ctx.accounts.recipient,
+        ],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct Close<'info> {
+    pub lookup_table: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+    pub recipient: AccountInfo<'info>,
+}
+
+// Bincode encodes the variant index of enums as a `u32`.
+fn instruction_data(variant: u32) -> Vec<u8> {
+    variant.to_le_bytes().to_vec()
+}
+
+fn authority_instruction<'info>(
+    variant: u32,
+    lookup_table: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let ix = Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(*lookup_table.key, false),
+            AccountMeta::new_readonly(*authority.key, true),
+        ],
+        data: instruction_data(variant),
+    };
+    crate::solana_program::program::invoke_signed(&ix, &[lookup_table, authority], signer_seeds)
+        .map_err(Into::into)
+}
+
+/// An address lookup table, to be used as `Account<'info, AddressLookupTable>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressLookupTable {
+    /// The slot the table was deactivated at, `u64::MAX` while active.
+    pub deactivation_slot: u64,
+    /// The slot the table was last extended at. Addresses added in this
+    /// slot can only be looked up from the next one.
+    pub last_extended_slot: u64,
+    /// The index of the first address added in `last_extended_slot`.
+    pub last_extended_slot_start_index: u8,
+    /// The authority of the table, `None` once frozen.
+    pub authority: Option<Pubkey>,
+    pub addresses: Vec<Pubkey>,
+}
+
+impl AddressLookupTable {
+    /// Returns whether the table hasn't been deactivated. Deactivated tables
+    /// remain usable until their deactivation slot leaves the `SlotHashes`
+    /// sysvar, after which they can be closed.
+    pub fn is_active(&self) -> bool {
+        self.deactivation_slot == u64::MAX
+    }
+}
+
+impl AccountDeserialize for AddressLookupTable {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        AddressLookupTable::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        let data = *buf;
+        if data.len() < LOOKUP_TABLE_META_SIZE {
+            return Err(ErrorCode::AccountDidNotDeserialize.into());
+        }
+        match u32::from_le_bytes(data[..4].try_into().unwrap()) {
+            0 => return Err(ErrorCode::AccountNotInitialized.into()),
+            1 => {}
+            _ => return Err(ErrorCode::AccountDidNotDeserialize.into()),
+        }
+        let authority = match data[21] {
+            0 => None,
+            1 => Some(Pubkey::try_from(&data[22..54]).unwrap()),
+            _ => return Err(ErrorCode::AccountDidNotDeserialize.into()),
+        };
+        let addresses = &data[LOOKUP_TABLE_META_SIZE..];
+        if addresses.len() % 32 != 0 {
+            return Err(ErrorCode::AccountDidNotDeserialize.into());
+        }
+        let table = AddressLookupTable {
+            deactivation_slot: u64::from_le_bytes(data[4..12].try_into().unwrap()),
+            last_extended_slot: u64::from_le_bytes(data[12..20].try_into().unwrap()),
+            last_extended_slot_start_index: data[20],
+            authority,
+            addresses: addresses
+                .chunks(32)
+                .map(|address| Pubkey::try_from(address).unwrap())
+                .collect(),
+        };
+        *buf = &data[data.len()..];
+        Ok(table)
+    }
+}
+
+impl AccountSerialize for AddressLookupTable {
+    fn try_serialize<W: std::io::Write>(&self, _writer: &mut W) -> Result<()> {
+        // no-op
+        Ok(())
+    }
+}
+
+impl Owner for AddressLookupTable {
+    fn owner() -> Pubkey {
+        ID
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_lookup_table() {
+        let authority = Pubkey::new_unique();
+        let addresses = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut data = 1u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&u64::MAX.to_le_bytes());
+        data.extend_from_slice(&7u64.to_le_bytes());
+        data.push(1);
+        data.push(1);
+        data.extend_from_slice(authority.as_ref());
+        data.resize(LOOKUP_TABLE_META_SIZE, 0);
+        for address in &addresses {
+            data.extend_from_slice(address.as_ref());
+        }
+
+        let table = AddressLookupTable::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(
+            table,
+            AddressLookupTable {
+                deactivation_slot: u64::MAX,
+                last_extended_slot: 7,
+                last_extended_slot_start_index: 1,
+                authority: Some(authority),
+                addresses: addresses.to_vec(),
+            }
+        );
+        assert!(table.is_active());
+
+        // Frozen tables have no authority.
+        data[21] = 0;
+        let table = AddressLookupTable::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(table.authority, None);
+
+        data[..4].copy_from_slice(&0u32.to_le_bytes());
+        assert!(AddressLookupTable::try_deserialize(&mut &data[..]).is_err());
+        assert!(
+            AddressLookupTable::try_deserialize(&mut &data[..LOOKUP_TABLE_META_SIZE - 1]).is_err()
+        );
+    }
+}
@@ -1,19 +1,181 @@
+use crate::__private::CLOSED_ACCOUNT_DISCRIMINATOR;
+use crate::error::{Error, ErrorCode};
 use crate::prelude::{Id, System};
 use crate::Result;
 use solana_program::account_info::AccountInfo;
+use solana_program::pubkey::Pubkey;
 use solana_program::system_program;
 
 pub fn close<'info>(info: AccountInfo<'info>, sol_destination: AccountInfo<'info>) -> Result<()> {
+    drain_lamports(&info, &sol_destination);
+
+    info.assign(&system_program::ID);
+    info.realloc(0, false).map_err(Into::into)
+}
+
+pub fn close_tombstone<'info>(
+    info: AccountInfo<'info>,
+    sol_destination: AccountInfo<'info>,
+) -> Result<()> {
+    drain_lamports(&info, &sol_destination);
+
+    // Keep the account owned by the program, so that refunding it within the
+    // same transaction cannot revive it as a valid account.
+    info.realloc(CLOSED_ACCOUNT_DISCRIMINATOR.len(), false)?;
+    info.try_borrow_mut_data()?
+        .copy_from_slice(&CLOSED_ACCOUNT_DISCRIMINATOR);
+    Ok(())
+}
+
+pub fn is_closed(info: &AccountInfo) -> bool {
+    (info.owner == &System::id() && info.data_is_empty()) || is_tombstoned(info)
+}
+
+pub fn is_tombstoned(info: &AccountInfo) -> bool {
+    info.try_borrow_data()
+        .map(|data| data.starts_with(&CLOSED_ACCOUNT_DISCRIMINATOR))
+        .unwrap_or(false)
+}
+
+/// Sends all lamports of an account closed with `close = <target>, tombstone`
+/// to `sol_destination`.
+///
+/// A tombstoned account that was refunded after being closed is never garbage
+/// collected. Programs using tombstones should expose an instruction calling
+/// this function, so that anyone can reclaim those lamports.
+///
+/// # Example
+/// ```ignore
+/// pub fn force_defund(ctx: Context<ForceDefund>) -> Result<()> {
+///     anchor_lang::force_defund(
+///         &ctx.accounts.account,
+///         &ctx.accounts.destination,
+///         ctx.program_id,
+///     )
+/// }
+///
+/// #[derive(Accounts)]
+/// pub struct ForceDefund<'info> {
+///     /// CHECK: Verified to be a tombstone by `force_defund`.
+///     #[account(mut)]
+///     pub account: UncheckedAccount<'info>,
+///     /// CHECK: Receives the lamports.
+///     #[account(mut)]
+///     pub destination: UncheckedAccount<'info>,
+/// }
+/// ```
+pub fn force_defund<'info>(
+    info: &AccountInfo<'info>,
+    sol_destination: &AccountInfo<'info>,
+    program_id: &Pubkey,
+) -> Result<()> {
+    if info.owner != program_id {
+        return Err(Error::from(ErrorCode::AccountOwnedByWrongProgram)
+            .with_pubkeys((*info.owner, *program_id)));
+    }
+    if !is_tombstoned(info) {
+        return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+    }
+    drain_lamports(info, sol_destination);
+    Ok(())
+}
+
+fn drain_lamports(info: &AccountInfo, sol_destination: &AccountInfo) {
     // Transfer tokens from the account to the sol_destination.
     let dest_starting_lamports = sol_destination.lamports();
     **sol_destination.lamports.borrow_mut() =
         dest_starting_lamports.checked_add(info.lamports()).unwrap();
     **info.lamports.borrow_mut() = 0;
-
-    info.assign(&system_program::ID);
-    info.realloc(0, false).map_err(Into::into)
 }
 
-pub fn is_closed(info: &AccountInfo) -> bool {
-    info.owner == &System::id() && info.data_is_empty()
+#[cfg(test)]
+mod tests {
+    use solana_program::clock::Epoch;
+
+    use super::*;
+    use crate::accounts::account::Account;
+    use crate::{AccountDeserialize, AccountSerialize, Owner};
+
+    #[derive(Clone)]
+    struct Data;
+
+    impl AccountSerialize for Data {}
+
+    impl AccountDeserialize for Data {
+        fn try_deserialize_unchecked(_buf: &mut &[u8]) -> Result<Self> {
+            Ok(Data)
+        }
+    }
+
+    impl Owner for Data {
+        fn owner() -> Pubkey {
+            Pubkey::new_from_array([1; 32])
+        }
+    }
+
+    #[test]
+    fn test_force_defund_tombstone() {
+        let key = Pubkey::new_unique();
+        let program_id = Data::owner();
+        let mut lamports = 100;
+        let mut data = CLOSED_ACCOUNT_DISCRIMINATOR.to_vec();
+        let info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &program_id,
+            false,
+            Epoch::default(),
+        );
+        let dest_key = Pubkey::new_unique();
+        let mut dest_lamports = 5;
+        let mut dest_data = vec![];
+        let dest = AccountInfo::new(
+            &dest_key,
+            false,
+            true,
+            &mut dest_lamports,
+            &mut dest_data,
+            &system_program::ID,
+            false,
+            Epoch::default(),
+        );
+
+        assert!(is_closed(&info));
+        assert_eq!(
+            Account::<Data>::try_from(&info).err().unwrap(),
+            ErrorCode::AccountClosed.into()
+        );
+        assert!(force_defund(&info, &dest, &Pubkey::new_unique()).is_err());
+
+        force_defund(&info, &dest, &program_id).unwrap();
+        assert_eq!(info.lamports(), 0);
+        assert_eq!(dest.lamports(), 105);
+    }
+
+    #[test]
+    fn test_force_defund_live_account() {
+        let key = Pubkey::new_unique();
+        let program_id = Data::owner();
+        let mut lamports = 100;
+        let mut data = vec![0; 16];
+        let info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &program_id,
+            false,
+            Epoch::default(),
+        );
+        let dest = info.clone();
+
+        assert!(!is_closed(&info));
+        assert!(Account::<Data>::try_from(&info).is_ok());
+        assert!(force_defund(&info, &dest, &program_id).is_err());
+        assert_eq!(info.lamports(), 100);
+    }
 }
@@ -205,6 +205,9 @@ pub enum ErrorCode {
     /// 3017 - The account was duplicated for more than one reallocation
     #[msg("The account was duplicated for more than one reallocation")]
     AccountDuplicateReallocs,
+    /// 3018 - The account was closed and can no longer be used
+    #[msg("The account was closed and can no longer be used")]
+    AccountClosed,
 
     // Compression
     /// 3050 - The compressed account state failed to deserialize
@@ -217,6 +220,11 @@ pub enum ErrorCode {
     #[msg("The account state hash does not match existing state hash")]
     CompressedStateMismatch,
 
+    // Zero copy collections
+    /// 3100 - The zero copy collection is at capacity
+    #[msg("The zero copy collection is at capacity")]
+    ZeroCopyCollectionFull = 3100,
+
     // Miscellaneous
     /// 4100 - The declared program id does not match actual program id
     #[msg("The declared program id does not match the actual program id")]
@@ -231,6 +239,16 @@ pub enum ErrorCode {
     Deprecated = 5000,
 }
 
+/// Implemented by the enums of [`#[error_code]`](crate::error_code), to
+/// get an error back from its code, e.g. off chain.
+pub trait ProgramErrorCode: Display + Sized {
+    /// Gets the error of an error code, offset included.
+    fn from_code(code: u32) -> Option<Self>;
+
+    /// Gets the name of the error.
+    fn name(&self) -> String;
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error {
     AnchorError(Box<AnchorError>),
@@ -32,6 +32,7 @@ use std::io::Write;
 
 mod account_meta;
 pub mod accounts;
+pub mod address_lookup_table;
 mod bpf_upgradeable_state;
 mod bpf_writer;
 mod common;
@@ -43,15 +44,17 @@ pub mod event;
 #[doc(hidden)]
 pub mod idl;
 pub mod system_program;
+pub mod zero_copy;
 
 mod vec;
 pub use crate::bpf_upgradeable_state::*;
+pub use crate::common::force_defund;
 pub use anchor_attribute_access_control::access_control;
 pub use anchor_attribute_account::{account, declare_id, zero_copy};
 pub use anchor_attribute_constant::constant;
 pub use anchor_attribute_error::*;
 pub use anchor_attribute_event::{emit, event};
-pub use anchor_attribute_program::program;
+pub use anchor_attribute_program::{declare_program, program};
 pub use anchor_derive_accounts::Accounts;
 pub use compressed_state::CompressedState;
 pub use anchor_derive_serde::{AnchorDeserialize, AnchorSerialize};
@@ -97,6 +100,25 @@ pub trait Accounts<'info>: ToAccountMetas + ToAccountInfos<'info> + Sized {
         state: &mut BTreeMap<String, Vec<u8>>,
         reallocs: &mut BTreeSet<Pubkey>,
     ) -> Result<Self>;
+
+    /// Like [`try_accounts`](Self::try_accounts), additionally recording the
+    /// target size of every account reallocated with `realloc::incremental`
+    /// in `realloc_targets`. Implemented by the
+    /// [`Accounts`](./derive.Accounts.html) derive macro, other
+    /// implementations have no incremental reallocs and need not override it.
+    #[allow(clippy::too_many_arguments)]
+    fn try_accounts_with_realloc_targets(
+        program_id: &Pubkey,
+        accounts: &mut &[AccountInfo<'info>],
+        ix_data: &[u8],
+        bumps: &mut BTreeMap<String, u8>,
+        seeds: &mut BTreeMap<String, Vec<u8>>,
+        state: &mut BTreeMap<String, Vec<u8>>,
+        reallocs: &mut BTreeSet<Pubkey>,
+        _realloc_targets: &mut BTreeMap<Pubkey, usize>,
+    ) -> Result<Self> {
+        Self::try_accounts(program_id, accounts, ix_data, bumps, seeds, state, reallocs)
+    }
 }
 
 /// The exit procedure for an account. Any cleanup or persistence to storage
@@ -113,6 +135,15 @@ pub trait AccountsExit<'info>: ToAccountMetas + ToAccountInfos<'info> {
 /// one to retrieve the rent exemption.
 pub trait AccountsClose<'info>: ToAccountInfos<'info> {
     fn close(&self, sol_destination: AccountInfo<'info>) -> Result<()>;
+
+    /// Closes the account like [`close`](Self::close), but keeps it owned by
+    /// the program and marks its data with the closed account discriminator.
+    fn close_tombstone(&self, sol_destination: AccountInfo<'info>) -> Result<()> {
+        match self.to_account_infos().into_iter().next() {
+            Some(info) => crate::common::close_tombstone(info, sol_destination),
+            None => Ok(()),
+        }
+    }
 }
 
 /// Transformation to
@@ -269,7 +300,71 @@ pub trait Discriminator {
     }
 }
 
+/// The position of a field in the data of an account of type `A`, holding a
+/// value of type `T`. The offset includes the 8 byte discriminator.
+///
+/// Generated by [`#[account]`](./attr.account.html) as the associated
+/// constants of an `<Account>Offsets` struct named after the fields, e.g.
+/// `VaultOffsets::authority`, for all fields of zero-copy accounts and for
+/// the fields of other accounts preceded only by fixed size fields.
+pub struct FieldOffset<A, T> {
+    pub offset: usize,
+    pub size: usize,
+    _marker: std::marker::PhantomData<fn() -> (A, T)>,
+}
+
+impl<A, T> FieldOffset<A, T> {
+    pub const fn new(offset: usize, size: usize) -> Self {
+        Self {
+            offset,
+            size,
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<A, T> Clone for FieldOffset<A, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A, T> Copy for FieldOffset<A, T> {}
+
+impl<A, T> std::fmt::Debug for FieldOffset<A, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FieldOffset")
+            .field("offset", &self.offset)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
 /// Defines the space of an account for initialization.
+///
+/// Accounts initialized without a `space` constraint must implement it,
+/// e.g. with `#[derive(InitSpace)]`:
+///
+/// ```compile_fail,E0277
+/// use anchor_lang::prelude::*;
+///
+/// declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+///
+/// #[account]
+/// pub struct Data {
+///     value: u64,
+/// }
+///
+/// #[derive(Accounts)]
+/// pub struct Initialize<'info> {
+///     // Fails without `#[derive(InitSpace)]` on `Data`.
+///     #[account(init, payer = payer)]
+///     pub data: Account<'info, Data>,
+///     #[account(mut)]
+///     pub payer: Signer<'info>,
+///     pub system_program: Program<'info, System>,
+/// }
+/// ```
 pub trait Space {
     const INIT_SPACE: usize;
 }
@@ -359,7 +454,8 @@ pub mod prelude {
         accounts::migration::Migration, 
         // accounts::compressed_account::CompressedAccount,
         accounts::sysvar::Sysvar, accounts::unchecked_account::UncheckedAccount, constant,
-        context::Context, context::CpiContext, declare_id, emit, err, error, event, program,
+        context::Context, context::CpiContext, declare_id, declare_program, emit, err, error, event,
+        program,
         require, require_eq, require_gt, require_gte, require_keys_eq, require_keys_neq, seeds,
         require_neq, solana_program::bpf_loader_upgradeable::UpgradeableLoaderState, source,
         system_program::System, zero_copy, AccountDeserialize, AccountSerialize, Accounts,
@@ -408,6 +504,25 @@ pub mod __private {
 
     use solana_program::pubkey::Pubkey;
 
+    // The space of accounts initialized without a `space` constraint, the
+    // error naming the fix for accounts without `Space` on compilers
+    // supporting custom diagnostics.
+    #[doc(hidden)]
+    #[rustversion::attr(
+        since(1.78),
+        diagnostic::on_unimplemented(
+            message = "`{Self}` doesn't implement `Space`, required by `init` without `space`",
+            label = "add `#[derive(InitSpace)]` to `{Self}` or specify `space = ...`"
+        )
+    )]
+    pub trait DefaultSpace {
+        const INIT_SPACE: usize;
+    }
+
+    impl<T: crate::Space> DefaultSpace for T {
+        const INIT_SPACE: usize = T::INIT_SPACE;
+    }
+
     // Used to calculate the maximum between two expressions.
     // It is necessary for the calculation of the enum space.
     #[doc(hidden)]
@@ -415,6 +530,14 @@ pub mod __private {
         [a, b][(a < b) as usize]
     }
 
+    // Returns the offset of a field of a zero-copy account starting at or
+    // after `end`, given its alignment within the account struct.
+    #[doc(hidden)]
+    pub const fn field_offset(end: usize, align: usize) -> usize {
+        let start = end - 8;
+        8 + (start + align - 1) / align * align
+    }
+
     // Very experimental trait.
     #[doc(hidden)]
     pub trait ZeroCopyAccessor<Ty> {
@@ -35,6 +35,31 @@ impl<'info, T: Accounts<'info>> Accounts<'info> for Vec<T> {
             .map(|item| vec.push(item))?;
         Ok(vec)
     }
+
+    fn try_accounts_with_realloc_targets(
+        program_id: &Pubkey,
+        accounts: &mut &[AccountInfo<'info>],
+        ix_data: &[u8],
+        bumps: &mut BTreeMap<String, u8>,
+        seeds: &mut BTreeMap<String, Vec<u8>>,
+        state: &mut BTreeMap<String, Vec<u8>>,
+        reallocs: &mut BTreeSet<Pubkey>,
+        realloc_targets: &mut BTreeMap<Pubkey, usize>,
+    ) -> Result<Self> {
+        let mut vec: Vec<T> = Vec::new();
+        T::try_accounts_with_realloc_targets(
+            program_id,
+            accounts,
+            ix_data,
+            bumps,
+            seeds,
+            state,
+            reallocs,
+            realloc_targets,
+        )
+        .map(|item| vec.push(item))?;
+        Ok(vec)
+    }
 }
 
 #[cfg(test)]
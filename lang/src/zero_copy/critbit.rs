@@ -0,0 +1,404 @@
+//! Fixed-capacity ordered map keyed by `u128`.
+
+use super::slab::Slab;
+use super::ZeroCopyElement;
+use crate::error::ErrorCode;
+use crate::Result;
+use bytemuck::{Pod, Zeroable};
+use std::mem;
+
+// Node handles with this bit set refer to leaves, all others to inner nodes.
+const LEAF_BIT: u32 = 1 << 31;
+
+#[derive(Copy, Clone, Pod, Zeroable)]
+#[repr(C)]
+struct InnerNode {
+    // Number of leading key bits shared by every leaf below this node.
+    prefix_len: u32,
+    _padding: u32,
+    children: [u32; 2],
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+struct LeafNode<V: ZeroCopyElement> {
+    key: [u64; 2],
+    value: V,
+}
+
+// Safety: `V` starts at offset 16 and its size is a multiple of 8 bytes with an
+// alignment of at most 8, so the leaf has no padding and satisfies the same.
+unsafe impl<V: ZeroCopyElement> Zeroable for LeafNode<V> {}
+unsafe impl<V: ZeroCopyElement> Pod for LeafNode<V> {}
+unsafe impl<V: ZeroCopyElement> ZeroCopyElement for LeafNode<V> {}
+
+// Safety: 16 bytes with an alignment of 4.
+unsafe impl ZeroCopyElement for InnerNode {}
+
+impl<V: ZeroCopyElement> LeafNode<V> {
+    fn key(&self) -> u128 {
+        (self.key[0] as u128) << 64 | self.key[1] as u128
+    }
+}
+
+/// A crit-bit tree mapping `u128` keys to values of type `V`, holding up to
+/// `N` entries.
+///
+/// Entries are kept ordered by key, which makes the tree a natural fit for
+/// order book sides where the key encodes price and sequence number.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct CritbitTree<V: ZeroCopyElement, const N: usize> {
+    root: u32,
+    _padding: u32,
+    inner: Slab<InnerNode, N>,
+    leaves: Slab<LeafNode<V>, N>,
+}
+
+// Safety: both slabs are padding free and start at offsets that are multiples
+// of 8 bytes.
+unsafe impl<V: ZeroCopyElement, const N: usize> Zeroable for CritbitTree<V, N> {}
+unsafe impl<V: ZeroCopyElement, const N: usize> Pod for CritbitTree<V, N> {}
+
+impl<V: ZeroCopyElement, const N: usize> CritbitTree<V, N> {
+    /// The maximum number of entries the tree can hold.
+    pub const CAPACITY: usize = Slab::<LeafNode<V>, N>::CAPACITY;
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == Self::CAPACITY
+    }
+
+    /// Inserts `value` under `key`, returning the previous value if the key
+    /// was already present.
+    pub fn insert(&mut self, key: u128, value: V) -> Result<Option<V>> {
+        if self.is_empty() {
+            self.root = self.new_leaf(key, value)? | LEAF_BIT;
+            return Ok(None);
+        }
+
+        // Find the closest existing key.
+        let closest = self.find_leaf(key);
+        let leaf = self.leaf_mut(closest);
+        let closest_key = leaf.key();
+        if closest_key == key {
+            return Ok(Some(mem::replace(&mut leaf.value, value)));
+        }
+        if self.is_full() {
+            return Err(ErrorCode::ZeroCopyCollectionFull.into());
+        }
+
+        // Splice a new inner node above the first node on the search path
+        // that discriminates on a bit past the new crit bit.
+        let crit_bit = (closest_key ^ key).leading_zeros();
+        let mut parent: Option<(u32, usize)> = None;
+        let mut node = self.root;
+        while node & LEAF_BIT == 0 {
+            let inner = self.inner_node(node);
+            if inner.prefix_len > crit_bit {
+                break;
+            }
+            let dir = direction(key, inner.prefix_len);
+            parent = Some((node, dir));
+            node = inner.children[dir];
+        }
+
+        let new_leaf = self.new_leaf(key, value)? | LEAF_BIT;
+        let mut children = [node, node];
+        children[direction(key, crit_bit)] = new_leaf;
+        let new_inner = self.inner.insert(InnerNode {
+            prefix_len: crit_bit,
+            _padding: 0,
+            children,
+        })?;
+        self.replace_child(parent, new_inner);
+        Ok(None)
+    }
+
+    /// Removes the entry under `key`, returning its value.
+    pub fn remove(&mut self, key: u128) -> Option<V> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut grandparent: Option<(u32, usize)> = None;
+        let mut parent: Option<(u32, usize)> = None;
+        let mut node = self.root;
+        while node & LEAF_BIT == 0 {
+            let dir = direction(key, self.inner_node(node).prefix_len);
+            grandparent = parent;
+            parent = Some((node, dir));
+            node = self.inner_node(node).children[dir];
+        }
+        if self.leaf(node).key() != key {
+            return None;
+        }
+
+        let value = self.leaves.remove(node & !LEAF_BIT)?.value;
+        match parent {
+            None => self.root = 0,
+            Some((parent, dir)) => {
+                let sibling = self.inner_node(parent).children[1 - dir];
+                self.inner.remove(parent);
+                self.replace_child(grandparent, sibling);
+            }
+        }
+        Some(value)
+    }
+
+    pub fn contains_key(&self, key: u128) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn get(&self, key: u128) -> Option<&V> {
+        if self.is_empty() {
+            return None;
+        }
+        let leaf = self.leaf(self.find_leaf(key));
+        (leaf.key() == key).then(|| &leaf.value)
+    }
+
+    pub fn get_mut(&mut self, key: u128) -> Option<&mut V> {
+        if self.is_empty() {
+            return None;
+        }
+        let leaf = self.leaf_mut(self.find_leaf(key));
+        if leaf.key() == key {
+            Some(&mut leaf.value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the entry with the smallest key.
+    pub fn min(&self) -> Option<(u128, &V)> {
+        self.extreme(0)
+    }
+
+    /// Returns the entry with the largest key.
+    pub fn max(&self) -> Option<(u128, &V)> {
+        self.extreme(1)
+    }
+
+    /// Removes and returns the entry with the smallest key.
+    pub fn pop_min(&mut self) -> Option<(u128, V)> {
+        let key = self.min()?.0;
+        self.remove(key).map(|value| (key, value))
+    }
+
+    /// Removes and returns the entry with the largest key.
+    pub fn pop_max(&mut self) -> Option<(u128, V)> {
+        let key = self.max()?.0;
+        self.remove(key).map(|value| (key, value))
+    }
+
+    /// Iterates over all entries in ascending key order.
+    pub fn iter(&self) -> Iter<'_, V, N> {
+        Iter::new(self, 0)
+    }
+
+    /// Iterates over all entries in descending key order.
+    pub fn iter_rev(&self) -> Iter<'_, V, N> {
+        Iter::new(self, 1)
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        *self = Self::zeroed();
+    }
+
+    fn extreme(&self, dir: usize) -> Option<(u128, &V)> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut node = self.root;
+        while node & LEAF_BIT == 0 {
+            node = self.inner_node(node).children[dir];
+        }
+        let leaf = self.leaf(node);
+        Some((leaf.key(), &leaf.value))
+    }
+
+    // Descends to the leaf sharing the longest prefix with `key`. The tree
+    // must not be empty.
+    fn find_leaf(&self, key: u128) -> u32 {
+        let mut node = self.root;
+        while node & LEAF_BIT == 0 {
+            let inner = self.inner_node(node);
+            node = inner.children[direction(key, inner.prefix_len)];
+        }
+        node
+    }
+
+    fn new_leaf(&mut self, key: u128, value: V) -> Result<u32> {
+        self.leaves.insert(LeafNode {
+            key: [(key >> 64) as u64, key as u64],
+            value,
+        })
+    }
+
+    fn replace_child(&mut self, parent: Option<(u32, usize)>, child: u32) {
+        match parent {
+            None => self.root = child,
+            Some((parent, dir)) => {
+                self.inner
+                    .get_mut(parent)
+                    .expect("dangling critbit node")
+                    .children[dir] = child;
+            }
+        }
+    }
+
+    fn inner_node(&self, handle: u32) -> &InnerNode {
+        self.inner.get(handle).expect("dangling critbit node")
+    }
+
+    fn leaf(&self, handle: u32) -> &LeafNode<V> {
+        self.leaves
+            .get(handle & !LEAF_BIT)
+            .expect("dangling critbit leaf")
+    }
+
+    fn leaf_mut(&mut self, handle: u32) -> &mut LeafNode<V> {
+        self.leaves
+            .get_mut(handle & !LEAF_BIT)
+            .expect("dangling critbit leaf")
+    }
+}
+
+// Returns which child to follow for `key` at an inner node discriminating on
+// bit `prefix_len`, counted from the most significant bit.
+fn direction(key: u128, prefix_len: u32) -> usize {
+    ((key >> (127 - prefix_len)) & 1) as usize
+}
+
+/// In-order iterator over a [`CritbitTree`].
+pub struct Iter<'a, V: ZeroCopyElement, const N: usize> {
+    tree: &'a CritbitTree<V, N>,
+    stack: Vec<u32>,
+    // 0 for ascending, 1 for descending order.
+    dir: usize,
+}
+
+impl<'a, V: ZeroCopyElement, const N: usize> Iter<'a, V, N> {
+    fn new(tree: &'a CritbitTree<V, N>, dir: usize) -> Self {
+        let stack = if tree.is_empty() {
+            vec![]
+        } else {
+            vec![tree.root]
+        };
+        Self { tree, stack, dir }
+    }
+}
+
+impl<'a, V: ZeroCopyElement, const N: usize> Iterator for Iter<'a, V, N> {
+    type Item = (u128, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut node = self.stack.pop()?;
+        while node & LEAF_BIT == 0 {
+            let children = self.tree.inner_node(node).children;
+            self.stack.push(children[1 - self.dir]);
+            node = children[self.dir];
+        }
+        let leaf = self.tree.leaf(node);
+        Some((leaf.key(), &leaf.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys<const N: usize>(tree: &CritbitTree<u64, N>) -> Vec<u128> {
+        tree.iter().map(|(k, _)| k).collect()
+    }
+
+    #[test]
+    fn insert_get_and_order() {
+        let mut tree = CritbitTree::<u64, 8>::zeroed();
+        assert!(tree.is_empty());
+        assert_eq!(tree.min(), None);
+
+        for k in [50u128, 10, 70, 30, u128::MAX, 0, 1 << 100] {
+            assert_eq!(tree.insert(k, k as u64).unwrap(), None);
+        }
+        assert_eq!(tree.len(), 7);
+        assert_eq!(keys(&tree), vec![0, 10, 30, 50, 70, 1 << 100, u128::MAX]);
+        assert_eq!(
+            tree.iter_rev().map(|(k, _)| k).collect::<Vec<_>>(),
+            vec![u128::MAX, 1 << 100, 70, 50, 30, 10, 0]
+        );
+        assert_eq!(tree.min(), Some((0, &0)));
+        assert_eq!(tree.max().unwrap().0, u128::MAX);
+        assert_eq!(tree.get(30), Some(&30));
+        assert_eq!(tree.get(31), None);
+
+        assert_eq!(tree.insert(30, 300).unwrap(), Some(30));
+        *tree.get_mut(30).unwrap() += 1;
+        assert_eq!(tree.get(30), Some(&301));
+        assert_eq!(tree.len(), 7);
+    }
+
+    #[test]
+    fn remove_and_reuse() {
+        let mut tree = CritbitTree::<u64, 4>::zeroed();
+        for k in [4u128, 2, 8, 6] {
+            tree.insert(k, 0).unwrap();
+        }
+        assert!(tree.is_full());
+        assert!(tree.insert(5, 0).is_err());
+        // Replacing an existing key still works when full.
+        assert!(tree.insert(4, 1).is_ok());
+
+        assert_eq!(tree.remove(3), None);
+        assert_eq!(tree.remove(4), Some(1));
+        assert!(!tree.contains_key(4));
+        assert_eq!(keys(&tree), vec![2, 6, 8]);
+
+        tree.insert(5, 0).unwrap();
+        assert_eq!(tree.pop_min(), Some((2, 0)));
+        assert_eq!(tree.pop_max(), Some((8, 0)));
+        assert_eq!(keys(&tree), vec![5, 6]);
+
+        assert_eq!(tree.remove(5), Some(0));
+        assert_eq!(tree.remove(6), Some(0));
+        assert!(tree.is_empty());
+        assert_eq!(tree.iter().count(), 0);
+
+        tree.insert(9, 9).unwrap();
+        assert_eq!(keys(&tree), vec![9]);
+    }
+
+    #[test]
+    fn matches_btree_map() {
+        use std::collections::BTreeMap;
+
+        let mut tree = CritbitTree::<u64, 64>::zeroed();
+        let mut expected = BTreeMap::new();
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        for i in 0..500u64 {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            let key = (seed % 97) as u128 * 0x1_0000_0001;
+            if seed % 3 == 0 {
+                assert_eq!(tree.remove(key), expected.remove(&key));
+            } else if tree.len() < 64 || tree.contains_key(key) {
+                assert_eq!(tree.insert(key, i).unwrap(), expected.insert(key, i));
+            }
+            assert_eq!(tree.len(), expected.len());
+        }
+        assert_eq!(
+            tree.iter().map(|(k, v)| (k, *v)).collect::<Vec<_>>(),
+            expected.into_iter().collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Fixed-capacity data structures for zero copy accounts.
+//!
+//! Every collection in this module is plain old data whose all-zero bit
+//! pattern is a valid, empty collection. They can therefore be embedded
+//! directly into a struct marked with `#[account(zero_copy)]` and mutated
+//! in place through an [`AccountLoader`](crate::accounts::account_loader::AccountLoader)
+//! without any (de)serialization step.
+//!
+//! # Example
+//! ```ignore
+//! use anchor_lang::prelude::*;
+//! use anchor_lang::zero_copy::{CritbitTree, RingBuffer};
+//!
+//! #[zero_copy]
+//! pub struct FillEvent {
+//!     pub maker: Pubkey,
+//!     pub quantity: u64,
+//! }
+//!
+//! #[account(zero_copy)]
+//! pub struct Market {
+//!     pub bids: CritbitTree<u64, 1024>,
+//!     pub events: RingBuffer<FillEvent, 512>,
+//! }
+//!
+//! pub fn place(ctx: Context<Place>, key: u128, quantity: u64) -> Result<()> {
+//!     let market = &mut ctx.accounts.market.load_mut()?;
+//!     market.bids.insert(key, quantity)?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Elements must implement [`ZeroCopyElement`], which guarantees the
+//! collections never contain padding bytes. `u64`, `i64`, `f64` and `Pubkey`
+//! implement it out of the box, custom types opt in with
+//! [`zero_copy_element!`](crate::zero_copy_element):
+//!
+//! ```ignore
+//! anchor_lang::zero_copy_element!(FillEvent);
+//! ```
+
+pub mod critbit;
+pub mod ring_buffer;
+pub mod slab;
+
+pub use critbit::CritbitTree;
+pub use ring_buffer::RingBuffer;
+pub use slab::Slab;
+
+use bytemuck::Pod;
+use solana_program::pubkey::Pubkey;
+
+/// A type that can be stored in the zero copy collections.
+///
+/// # Safety
+///
+/// The size of the type must be a multiple of 8 bytes and its alignment must
+/// be at most 8 bytes. Together with `Pod` this rules out padding in every
+/// collection layout. Prefer [`zero_copy_element!`](crate::zero_copy_element),
+/// which checks both at compile time.
+pub unsafe trait ZeroCopyElement: Pod {}
+
+/// Implements [`ZeroCopyElement`] for the given types, failing to compile if
+/// a type's layout would introduce padding into the collections.
+///
+/// ```
+/// # use anchor_lang::zero_copy::{RingBuffer, ZeroCopyElement};
+/// #[derive(Copy, Clone)]
+/// #[repr(C)]
+/// struct Fill([u8; 8]);
+/// unsafe impl bytemuck::Zeroable for Fill {}
+/// unsafe impl bytemuck::Pod for Fill {}
+///
+/// anchor_lang::zero_copy_element!(Fill);
+/// fn assert_pod<P: bytemuck::Pod>() {}
+/// assert_pod::<RingBuffer<Fill, 4>>();
+/// ```
+///
+/// A type whose size is not a multiple of 8 bytes is rejected:
+///
+/// ```compile_fail
+/// #[derive(Copy, Clone)]
+/// #[repr(C)]
+/// struct Fill([u8; 3]);
+/// unsafe impl bytemuck::Zeroable for Fill {}
+/// unsafe impl bytemuck::Pod for Fill {}
+///
+/// anchor_lang::zero_copy_element!(Fill);
+/// ```
+#[macro_export]
+macro_rules! zero_copy_element {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            const _: () = assert!(
+                ::std::mem::size_of::<$ty>() % 8 == 0 && ::std::mem::align_of::<$ty>() <= 8,
+                "zero copy elements must be a multiple of 8 bytes and aligned to at most 8 bytes"
+            );
+            unsafe impl $crate::zero_copy::ZeroCopyElement for $ty {}
+        )+
+    };
+}
+
+zero_copy_element!(u64, i64, f64, Pubkey);
@@ -0,0 +1,191 @@
+//! Fixed-capacity double ended queue.
+
+use super::ZeroCopyElement;
+use crate::error::ErrorCode;
+use crate::Result;
+use bytemuck::{Pod, Zeroable};
+
+/// A fixed-capacity ring buffer holding up to `N` elements of type `T`.
+///
+/// Useful for event queues, where a program pushes to the back and a
+/// cranker consumes from the front.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct RingBuffer<T: ZeroCopyElement, const N: usize> {
+    head: u64,
+    len: u64,
+    buf: [T; N],
+}
+
+// Safety: the 16 byte header is followed by elements whose size is a multiple
+// of 8 bytes with an alignment of at most 8, so the struct has no padding.
+unsafe impl<T: ZeroCopyElement, const N: usize> Zeroable for RingBuffer<T, N> {}
+unsafe impl<T: ZeroCopyElement, const N: usize> Pod for RingBuffer<T, N> {}
+
+impl<T: ZeroCopyElement, const N: usize> RingBuffer<T, N> {
+    /// The maximum number of elements the buffer can hold.
+    pub const CAPACITY: usize = {
+        assert!(N > 0, "RingBuffer capacity must be greater than zero");
+        N
+    };
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == Self::CAPACITY
+    }
+
+    /// Appends an element to the back of the buffer.
+    pub fn push_back(&mut self, value: T) -> Result<()> {
+        if self.is_full() {
+            return Err(ErrorCode::ZeroCopyCollectionFull.into());
+        }
+        let slot = self.physical(self.len());
+        self.buf[slot] = value;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Prepends an element to the front of the buffer.
+    pub fn push_front(&mut self, value: T) -> Result<()> {
+        if self.is_full() {
+            return Err(ErrorCode::ZeroCopyCollectionFull.into());
+        }
+        let head = (self.physical(0) + Self::CAPACITY - 1) % Self::CAPACITY;
+        self.buf[head] = value;
+        self.head = head as u64;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes and returns the element at the front of the buffer.
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let value = self.buf[self.physical(0)];
+        self.head = self.physical(1) as u64;
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Removes and returns the element at the back of the buffer.
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let value = self.buf[self.physical(self.len() - 1)];
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.len().checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    /// Returns the element at position `index`, counted from the front.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        (index < self.len()).then(|| &self.buf[self.physical(index)])
+    }
+
+    /// Returns the element at position `index`, counted from the front.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len() {
+            let slot = self.physical(index);
+            Some(&mut self.buf[slot])
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over the elements from front to back.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        (0..self.len()).map(move |i| &self.buf[self.physical(i)])
+    }
+
+    /// Removes all elements. The backing memory is left untouched.
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+
+    fn physical(&self, index: usize) -> usize {
+        (self.head as usize + index) % Self::CAPACITY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem;
+
+    #[test]
+    fn zeroed_is_empty() {
+        let rb = RingBuffer::<u64, 4>::zeroed();
+        assert!(rb.is_empty());
+        assert_eq!(rb.front(), None);
+        assert_eq!(RingBuffer::<u64, 4>::CAPACITY, 4);
+    }
+
+    #[test]
+    fn push_pop_wraps_around() {
+        let mut rb = RingBuffer::<u64, 3>::zeroed();
+        rb.push_back(1).unwrap();
+        rb.push_back(2).unwrap();
+        rb.push_back(3).unwrap();
+        assert!(rb.is_full());
+        assert!(rb.push_back(4).is_err());
+
+        assert_eq!(rb.pop_front(), Some(1));
+        rb.push_back(4).unwrap();
+        assert_eq!(rb.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(rb.back(), Some(&4));
+
+        rb.push_front(0).unwrap_err();
+        assert_eq!(rb.pop_back(), Some(4));
+        rb.push_front(1).unwrap();
+        assert_eq!(rb.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(rb.get(2), Some(&3));
+        assert_eq!(rb.get(3), None);
+
+        *rb.get_mut(0).unwrap() = 10;
+        assert_eq!(rb.pop_front(), Some(10));
+        rb.clear();
+        assert_eq!(rb.pop_front(), None);
+    }
+
+    #[test]
+    fn head_out_of_range() {
+        let mut rb = RingBuffer::<u64, 3>::zeroed();
+        rb.push_back(1).unwrap();
+        rb.push_back(2).unwrap();
+        rb.head += 3 * 1000;
+        assert_eq!(rb.pop_front(), Some(1));
+        rb.push_front(0).unwrap();
+        assert_eq!(rb.iter().copied().collect::<Vec<_>>(), vec![0, 2]);
+    }
+
+    #[test]
+    fn in_place_over_bytes() {
+        let mut data = vec![0u64; mem::size_of::<RingBuffer<u64, 8>>() / 8];
+        {
+            let rb: &mut RingBuffer<u64, 8> =
+                bytemuck::from_bytes_mut(bytemuck::cast_slice_mut(&mut data));
+            rb.push_back(7).unwrap();
+            rb.push_back(9).unwrap();
+        }
+        let rb: &RingBuffer<u64, 8> = bytemuck::from_bytes(bytemuck::cast_slice(&data));
+        assert_eq!(rb.len(), 2);
+        assert_eq!(rb.front(), Some(&7));
+    }
+}
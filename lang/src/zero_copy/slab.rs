@@ -0,0 +1,191 @@
+//! Fixed-capacity node allocator.
+
+use super::ZeroCopyElement;
+use crate::error::ErrorCode;
+use crate::Result;
+use bytemuck::{Pod, Zeroable};
+
+const FREE: u32 = 0;
+const OCCUPIED: u32 = 1;
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+struct SlabNode<T: ZeroCopyElement> {
+    tag: u32,
+    next_free: u32,
+    value: T,
+}
+
+// Safety: `T` starts at offset 8 and its size is a multiple of 8 bytes with an
+// alignment of at most 8, so the node has no padding.
+unsafe impl<T: ZeroCopyElement> Zeroable for SlabNode<T> {}
+unsafe impl<T: ZeroCopyElement> Pod for SlabNode<T> {}
+
+/// A fixed-capacity allocator handing out stable `u32` handles to up to `N`
+/// values of type `T`.
+///
+/// Freed slots are recycled through an intrusive free list, so inserting
+/// and removing are both constant time. Handles stay valid until the value
+/// they point to is removed.
+///
+/// Element types without a [`ZeroCopyElement`] impl are rejected:
+///
+/// ```compile_fail
+/// # use anchor_lang::zero_copy::Slab;
+/// #[derive(Copy, Clone)]
+/// #[repr(C)]
+/// struct Padded([u8; 3]);
+/// unsafe impl bytemuck::Zeroable for Padded {}
+/// unsafe impl bytemuck::Pod for Padded {}
+///
+/// fn assert_pod<P: bytemuck::Pod>() {}
+/// assert_pod::<Slab<Padded, 4>>();
+/// ```
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct Slab<T: ZeroCopyElement, const N: usize> {
+    // Number of slots ever handed out. Slots past this index are untouched.
+    bump_index: u32,
+    free_list_len: u32,
+    free_list_head: u32,
+    len: u32,
+    nodes: [SlabNode<T>; N],
+}
+
+// Safety: the 16 byte header is followed by padding free nodes aligned to at
+// most 8 bytes.
+unsafe impl<T: ZeroCopyElement, const N: usize> Zeroable for Slab<T, N> {}
+unsafe impl<T: ZeroCopyElement, const N: usize> Pod for Slab<T, N> {}
+
+impl<T: ZeroCopyElement, const N: usize> Slab<T, N> {
+    /// The maximum number of values the slab can hold.
+    pub const CAPACITY: usize = {
+        assert!(N > 0, "Slab capacity must be greater than zero");
+        assert!(N <= i32::MAX as usize, "Slab capacity must fit in 31 bits");
+        N
+    };
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == Self::CAPACITY
+    }
+
+    /// Stores `value` and returns its handle.
+    pub fn insert(&mut self, value: T) -> Result<u32> {
+        let handle = if self.free_list_len > 0 {
+            let handle = self.free_list_head;
+            self.free_list_head = self.nodes[handle as usize].next_free;
+            self.free_list_len -= 1;
+            handle
+        } else if (self.bump_index as usize) < Self::CAPACITY {
+            let handle = self.bump_index;
+            self.bump_index += 1;
+            handle
+        } else {
+            return Err(ErrorCode::ZeroCopyCollectionFull.into());
+        };
+        self.nodes[handle as usize] = SlabNode {
+            tag: OCCUPIED,
+            next_free: 0,
+            value,
+        };
+        self.len += 1;
+        Ok(handle)
+    }
+
+    /// Removes and returns the value behind `handle`, if any.
+    pub fn remove(&mut self, handle: u32) -> Option<T> {
+        if !self.contains(handle) {
+            return None;
+        }
+        let node = &mut self.nodes[handle as usize];
+        let value = node.value;
+        node.tag = FREE;
+        node.next_free = self.free_list_head;
+        self.free_list_head = handle;
+        self.free_list_len += 1;
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn contains(&self, handle: u32) -> bool {
+        handle < self.bump_index && self.nodes[handle as usize].tag == OCCUPIED
+    }
+
+    pub fn get(&self, handle: u32) -> Option<&T> {
+        self.contains(handle)
+            .then(|| &self.nodes[handle as usize].value)
+    }
+
+    pub fn get_mut(&mut self, handle: u32) -> Option<&mut T> {
+        if self.contains(handle) {
+            Some(&mut self.nodes[handle as usize].value)
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over all stored values and their handles, in handle order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &T)> + '_ {
+        self.nodes[..self.bump_index as usize]
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.tag == OCCUPIED)
+            .map(|(handle, node)| (handle as u32, &node.value))
+    }
+
+    /// Removes all values, invalidating every handle.
+    pub fn clear(&mut self) {
+        *self = Self::zeroed();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_remove_recycles_handles() {
+        let mut slab = Slab::<u64, 3>::zeroed();
+        assert!(slab.is_empty());
+
+        let a = slab.insert(10).unwrap();
+        let b = slab.insert(20).unwrap();
+        let c = slab.insert(30).unwrap();
+        assert_eq!((a, b, c), (0, 1, 2));
+        assert!(slab.is_full());
+        assert!(slab.insert(40).is_err());
+
+        assert_eq!(slab.remove(b), Some(20));
+        assert_eq!(slab.remove(b), None);
+        assert_eq!(slab.get(b), None);
+        assert_eq!(slab.len(), 2);
+
+        let d = slab.insert(40).unwrap();
+        assert_eq!(d, b);
+        *slab.get_mut(d).unwrap() += 2;
+        assert_eq!(
+            slab.iter().map(|(h, v)| (h, *v)).collect::<Vec<_>>(),
+            vec![(0, 10), (1, 42), (2, 30)]
+        );
+    }
+
+    #[test]
+    fn unknown_handles_are_rejected() {
+        let mut slab = Slab::<u64, 4>::zeroed();
+        assert!(!slab.contains(0));
+        assert!(!slab.contains(100));
+        assert_eq!(slab.remove(3), None);
+        slab.insert(1).unwrap();
+        slab.clear();
+        assert!(slab.is_empty());
+        assert!(!slab.contains(0));
+    }
+}
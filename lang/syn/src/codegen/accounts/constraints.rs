@@ -1,5 +1,6 @@
-use quote::quote;
+use quote::{quote, quote_spanned};
 use std::collections::HashSet;
+use syn::spanned::Spanned;
 use syn::Expr;
 
 use crate::*;
@@ -357,10 +358,36 @@ fn generate_constraint_realloc(
 ) -> proc_macro2::TokenStream {
     let field = &f.ident;
     let account_name = field.to_string();
-    let new_space = &c.space;
+    let target_space = &c.space;
     let payer = &c.payer;
     let zero = &c.zero;
 
+    // Incremental reallocs grow by the largest permitted step and leave the
+    // rest to subsequent instructions.
+    let (new_space, record_target) = if c.incremental {
+        (
+            quote! {
+                let __new_space = ::std::cmp::min(
+                    __target_space,
+                    __field_info.data_len() + anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE,
+                );
+            },
+            quote! {
+                __realloc_targets.insert(#field.key(), __target_space);
+            },
+        )
+    } else {
+        (
+            quote! {
+                if __target_space > __field_info.data_len() + anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE {
+                    return Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::AccountReallocExceedsLimit).with_account_name(#account_name));
+                }
+                let __new_space = __target_space;
+            },
+            quote! {},
+        )
+    };
+
     let mut optional_check_scope = OptionalCheckScope::new_with_field(accs, field);
     let payer_optional_check = optional_check_scope.generate_check(payer);
     let system_program_optional_check =
@@ -376,9 +403,11 @@ fn generate_constraint_realloc(
 
         let __anchor_rent = anchor_lang::prelude::Rent::get()?;
         let __field_info = #field.to_account_info();
-        let __new_rent_minimum = __anchor_rent.minimum_balance(#new_space);
+        let __target_space: usize = #target_space;
+        #new_space
+        let __new_rent_minimum = __anchor_rent.minimum_balance(__new_space);
 
-        let __delta_space = (::std::convert::TryInto::<isize>::try_into(#new_space).unwrap())
+        let __delta_space = (::std::convert::TryInto::<isize>::try_into(__new_space).unwrap())
             .checked_sub(::std::convert::TryInto::try_into(__field_info.data_len()).unwrap())
             .unwrap();
 
@@ -386,10 +415,6 @@ fn generate_constraint_realloc(
             #payer_optional_check
             if __delta_space > 0 {
                 #system_program_optional_check
-                if ::std::convert::TryInto::<usize>::try_into(__delta_space).unwrap() > anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE {
-                    return Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::AccountReallocExceedsLimit).with_account_name(#account_name));
-                }
-
                 if __new_rent_minimum > __field_info.lamports() {
                     anchor_lang::system_program::transfer(
                         anchor_lang::context::CpiContext::new(
@@ -408,8 +433,9 @@ fn generate_constraint_realloc(
                 **__field_info.lamports.borrow_mut() = __field_info.lamports().checked_sub(__lamport_amt).unwrap();
             }
 
-            #field.to_account_info().realloc(#new_space, #zero)?;
+            #field.to_account_info().realloc(__new_space, #zero)?;
             __reallocs.insert(#field.key());
+            #record_target
         }
     }
 }
@@ -757,7 +783,12 @@ fn generate_constraint_init_group(
             }
         }
         InitKind::Program { owner } | InitKind::Interface { owner } => {
-            // Define the space variable.
+            // Define the space variable, defaulting to the discriminator plus
+            // the size of the account type when not given explicitly.
+            let space = match space {
+                Some(space) => quote! {#space},
+                None => generate_default_space(f),
+            };
             let space = quote! {let space = #space;};
 
             let system_program_optional_check = check_scope.generate_check(system_program);
@@ -1147,6 +1178,31 @@ impl<'a> OptionalCheckScope<'a> {
     }
 }
 
+// Space required by an account initialized without an explicit `space`
+// constraint. Borsh accounts must implement `Space`, through `DefaultSpace`
+// for the error to name the fix, zero copy accounts use their in-memory size.
+fn generate_default_space(f: &Field) -> proc_macro2::TokenStream {
+    match &f.ty {
+        Ty::Account(AccountTy {
+            account_type_path, ..
+        })
+        | Ty::InterfaceAccount(InterfaceAccountTy {
+            account_type_path, ..
+        }) => quote_spanned! {account_type_path.span()=>
+            <#account_type_path as anchor_lang::Discriminator>::DISCRIMINATOR.len()
+                + <#account_type_path as anchor_lang::__private::DefaultSpace>::INIT_SPACE
+        },
+        Ty::AccountLoader(AccountLoaderTy { account_type_path }) => {
+            quote_spanned! {account_type_path.span()=>
+                <#account_type_path as anchor_lang::Discriminator>::DISCRIMINATOR.len()
+                    + ::std::mem::size_of::<#account_type_path>()
+            }
+        }
+        // Rejected by the parser.
+        _ => unreachable!("space must be provided with init"),
+    }
+}
+
 fn generate_get_token_account_space(mint: &Expr) -> proc_macro2::TokenStream {
     quote! {
         {
@@ -29,15 +29,21 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
                 let ident = &f.ident;
                 let name_str = ident.to_string();
                 if f.constraints.is_close() {
-                    let close_target = &f.constraints.close.as_ref().unwrap().sol_dest;
+                    let close = f.constraints.close.as_ref().unwrap();
+                    let close_target = &close.sol_dest;
                     let close_target_optional_check =
                         OptionalCheckScope::new(accs).generate_check(close_target);
+                    let close_fn = if close.tombstone {
+                        quote! { anchor_lang::AccountsClose::close_tombstone }
+                    } else {
+                        quote! { anchor_lang::AccountsClose::close }
+                    };
 
                     quote! {
                         {
                             let #close_target = &self.#close_target;
                             #close_target_optional_check
-                            anchor_lang::AccountsClose::close(
+                            #close_fn(
                                 &self.#ident,
                                 #close_target.to_account_info(),
                             ).map_err(|e| e.with_account_name(#name_str))?;
@@ -25,7 +25,7 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
                     quote! {
                         #[cfg(feature = "anchor-debug")]
                         ::solana_program::log::sol_log(stringify!(#name));
-                        let #name: #ty = anchor_lang::Accounts::try_accounts(__program_id, __accounts, __ix_data, __bumps, __seeds, __state, __reallocs)?;
+                        let #name: #ty = anchor_lang::Accounts::try_accounts_with_realloc_targets(__program_id, __accounts, __ix_data, __bumps, __seeds, __state, __reallocs, __realloc_targets)?;
                     }
                 }
                 AccountField::Field(f) => {
@@ -71,7 +71,7 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
                         quote! {
                             #[cfg(feature = "anchor-debug")]
                             ::solana_program::log::sol_log(stringify!(#typed_name));
-                            let #typed_name = anchor_lang::Accounts::try_accounts(__program_id, __accounts, __ix_data, __bumps, __seeds, __state, __reallocs)
+                            let #typed_name = anchor_lang::Accounts::try_accounts_with_realloc_targets(__program_id, __accounts, __ix_data, __bumps, __seeds, __state, __reallocs, __realloc_targets)
                                 .map_err(|e| e.with_account_name(#name))?;
                         }
                     }
@@ -116,7 +116,6 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
     quote! {
         #[automatically_derived]
         impl<#combined_generics> anchor_lang::Accounts<#trait_generics> for #name<#struct_generics> #where_clause {
-            #[inline(never)]
             fn try_accounts(
                 __program_id: &anchor_lang::solana_program::pubkey::Pubkey,
                 __accounts: &mut &[anchor_lang::solana_program::account_info::AccountInfo<'info>],
@@ -125,6 +124,29 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
                 __seeds: &mut std::collections::BTreeMap<String, Vec<u8>>,
                 __state: &mut std::collections::BTreeMap<String, Vec<u8>>,
                 __reallocs: &mut std::collections::BTreeSet<anchor_lang::solana_program::pubkey::Pubkey>,
+            ) -> anchor_lang::Result<Self> {
+                Self::try_accounts_with_realloc_targets(
+                    __program_id,
+                    __accounts,
+                    __ix_data,
+                    __bumps,
+                    __seeds,
+                    __state,
+                    __reallocs,
+                    &mut std::collections::BTreeMap::new(),
+                )
+            }
+
+            #[inline(never)]
+            fn try_accounts_with_realloc_targets(
+                __program_id: &anchor_lang::solana_program::pubkey::Pubkey,
+                __accounts: &mut &[anchor_lang::solana_program::account_info::AccountInfo<'info>],
+                __ix_data: &[u8],
+                __bumps: &mut std::collections::BTreeMap<String, u8>,
+                __seeds: &mut std::collections::BTreeMap<String, Vec<u8>>,
+                __state: &mut std::collections::BTreeMap<String, Vec<u8>>,
+                __reallocs: &mut std::collections::BTreeSet<anchor_lang::solana_program::pubkey::Pubkey>,
+                __realloc_targets: &mut std::collections::BTreeMap<anchor_lang::solana_program::pubkey::Pubkey, usize>,
             ) -> anchor_lang::Result<Self> {
                 // Deserialize instruction, if declared.
                 #ix_de
@@ -0,0 +1,177 @@
+use super::common::{camel_ident, docs, snake_ident};
+use crate::idl::types::{Idl, IdlAccountItem};
+use heck::CamelCase;
+use quote::quote;
+
+pub fn generate(idl: &Idl) -> proc_macro2::TokenStream {
+    let account_structs = generate_structs(idl, false);
+
+    quote! {
+        /// An Anchor generated module, providing a set of structs
+        /// mirroring the accounts of each instruction, where each field is
+        /// a `Pubkey`. This is useful for specifying accounts for a client.
+        pub mod accounts {
+            use super::*;
+
+            #(#account_structs)*
+        }
+    }
+}
+
+/// Generates one accounts struct per instruction, named after it. Nested
+/// accounts get their own struct, named after the parent and the field.
+///
+/// With `cpi`, fields are `AccountInfo`s instead of `Pubkey`s.
+pub fn generate_structs(idl: &Idl, cpi: bool) -> Vec<proc_macro2::TokenStream> {
+    let mut structs = vec![];
+    for ix in &idl.instructions {
+        generate_struct(&ix.name.to_camel_case(), &ix.accounts, cpi, &mut structs);
+    }
+    structs
+}
+
+fn generate_struct(
+    name: &str,
+    accounts: &[IdlAccountItem],
+    cpi: bool,
+    structs: &mut Vec<proc_macro2::TokenStream>,
+) {
+    let struct_name = camel_ident(name);
+    let lifetime = |accounts: &[IdlAccountItem]| {
+        if cpi && !accounts.is_empty() {
+            quote! { <'info> }
+        } else {
+            quote! {}
+        }
+    };
+    let account_info = if cpi {
+        quote! { anchor_lang::solana_program::account_info::AccountInfo<'info> }
+    } else {
+        quote! { Pubkey }
+    };
+
+    let mut fields = vec![];
+    let mut metas = vec![];
+    for account in accounts {
+        match account {
+            IdlAccountItem::IdlAccounts(nested) => {
+                let nested_name = format!("{name}{}", nested.name.to_camel_case());
+                generate_struct(&nested_name, &nested.accounts, cpi, structs);
+
+                let field = snake_ident(&nested.name);
+                let symbol = camel_ident(&nested_name);
+                let lifetime = lifetime(&nested.accounts);
+                fields.push(quote! { pub #field: #symbol #lifetime });
+                metas.push(quote! {
+                    account_metas.extend(self.#field.to_account_metas(None));
+                });
+            }
+            IdlAccountItem::IdlAccount(account) => {
+                let docs = docs(&account.docs);
+                let field = snake_ident(&account.name);
+                let is_signer = account.is_signer;
+                let meta = match account.is_mut {
+                    false => {
+                        quote! { anchor_lang::solana_program::instruction::AccountMeta::new_readonly }
+                    }
+                    true => quote! { anchor_lang::solana_program::instruction::AccountMeta::new },
+                };
+                let (key, optional_key) = if cpi {
+                    (
+                        quote! { anchor_lang::Key::key(&self.#field) },
+                        quote! { anchor_lang::Key::key(#field) },
+                    )
+                } else {
+                    (quote! { self.#field }, quote! { *#field })
+                };
+                if account.is_optional.unwrap_or(false) {
+                    fields.push(quote! {
+                        #docs
+                        pub #field: Option<#account_info>
+                    });
+                    metas.push(quote! {
+                        if let Some(#field) = &self.#field {
+                            account_metas.push(#meta(#optional_key, #is_signer));
+                        } else {
+                            account_metas.push(anchor_lang::solana_program::instruction::AccountMeta::new_readonly(ID, false));
+                        }
+                    });
+                } else {
+                    fields.push(quote! {
+                        #docs
+                        pub #field: #account_info
+                    });
+                    metas.push(quote! {
+                        account_metas.push(#meta(#key, #is_signer));
+                    });
+                }
+            }
+        }
+    }
+
+    let metas = with_vec(quote! { account_metas }, metas);
+    let generics = lifetime(accounts);
+    let to_account_infos = if cpi {
+        let infos = accounts.iter().map(|account| {
+            let field = match account {
+                IdlAccountItem::IdlAccounts(nested) => snake_ident(&nested.name),
+                IdlAccountItem::IdlAccount(account) => snake_ident(&account.name),
+            };
+            quote! {
+                account_infos.extend(anchor_lang::ToAccountInfos::to_account_infos(&self.#field));
+            }
+        });
+        let infos = with_vec(quote! { account_infos }, infos.collect());
+        quote! {
+            #[automatically_derived]
+            impl<'info> anchor_lang::ToAccountInfos<'info> for #struct_name #generics {
+                #[allow(clippy::vec_init_then_push)]
+                fn to_account_infos(&self) -> Vec<anchor_lang::solana_program::account_info::AccountInfo<'info>> {
+                    #infos
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let derive = if cpi {
+        quote! {}
+    } else {
+        quote! { #[derive(AnchorSerialize)] }
+    };
+
+    structs.push(quote! {
+        #derive
+        pub struct #struct_name #generics {
+            #(#fields),*
+        }
+
+        #[automatically_derived]
+        impl #generics anchor_lang::ToAccountMetas for #struct_name #generics {
+            #[allow(clippy::vec_init_then_push)]
+            fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<anchor_lang::solana_program::instruction::AccountMeta> {
+                #metas
+            }
+        }
+
+        #to_account_infos
+    });
+}
+
+// Wraps the statements filling a vector, without a `mut` binding when there
+// are none, to keep the generated code warning free. Clients are compiled
+// from source, where clippy doesn't skip the generated code.
+fn with_vec(
+    name: proc_macro2::TokenStream,
+    stmts: Vec<proc_macro2::TokenStream>,
+) -> proc_macro2::TokenStream {
+    if stmts.is_empty() {
+        quote! { vec![] }
+    } else {
+        quote! {
+            let mut #name = vec![];
+            #(#stmts)*
+            #name
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::idl::types::{
+    EnumFields, IdlDefinedTypeArg, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy,
+};
+use heck::{CamelCase, SnakeCase};
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::quote;
+
+pub fn ident(name: &str) -> Ident {
+    Ident::new(name, Span::call_site())
+}
+
+pub fn snake_ident(name: &str) -> Ident {
+    ident(&name.to_snake_case())
+}
+
+pub fn camel_ident(name: &str) -> Ident {
+    ident(&name.to_camel_case())
+}
+
+// With `idl-build`, types with conflicting names are stored with their full
+// path, which doesn't exist in the declaring crate.
+pub fn defined_ident(name: &str) -> Ident {
+    ident(name.rsplit("::").next().unwrap_or(name))
+}
+
+pub fn discriminator(namespace: &str, name: &str) -> TokenStream {
+    let discriminator = crate::codegen::program::common::sighash(namespace, name);
+    format!("{discriminator:?}").parse().unwrap()
+}
+
+pub fn docs(docs: &Option<Vec<String>>) -> TokenStream {
+    let docs = docs.iter().flatten().map(|line| {
+        let line = format!(" {line}");
+        quote! { #[doc = #line] }
+    });
+    quote! { #(#docs)* }
+}
+
+pub fn ty(ty: &IdlType) -> TokenStream {
+    match ty {
+        IdlType::Bool => quote! { bool },
+        IdlType::U8 => quote! { u8 },
+        IdlType::I8 => quote! { i8 },
+        IdlType::U16 => quote! { u16 },
+        IdlType::I16 => quote! { i16 },
+        IdlType::U32 => quote! { u32 },
+        IdlType::I32 => quote! { i32 },
+        IdlType::F32 => quote! { f32 },
+        IdlType::U64 => quote! { u64 },
+        IdlType::I64 => quote! { i64 },
+        IdlType::F64 => quote! { f64 },
+        IdlType::U128 => quote! { u128 },
+        IdlType::I128 => quote! { i128 },
+        IdlType::U256 | IdlType::I256 => {
+            quote! { compile_error!("256-bit integers are not supported by `declare_program!`") }
+        }
+        IdlType::Bytes => quote! { Vec<u8> },
+        IdlType::String => quote! { String },
+        IdlType::PublicKey => quote! { Pubkey },
+        IdlType::Defined(name) => {
+            let name = defined_ident(name);
+            quote! { #name }
+        }
+        IdlType::Option(inner) => {
+            let inner = self::ty(inner);
+            quote! { Option<#inner> }
+        }
+        IdlType::Vec(inner) => {
+            let inner = self::ty(inner);
+            quote! { Vec<#inner> }
+        }
+        IdlType::Array(inner, len) => {
+            let inner = self::ty(inner);
+            let len = Literal::usize_unsuffixed(*len);
+            quote! { [#inner; #len] }
+        }
+        IdlType::GenericLenArray(inner, len) => {
+            let inner = self::ty(inner);
+            let len = ident(len);
+            quote! { [#inner; #len] }
+        }
+        IdlType::Generic(name) => {
+            let name = ident(name);
+            quote! { #name }
+        }
+        IdlType::DefinedWithTypeArgs { name, args } => {
+            let name = defined_ident(name);
+            let args = args.iter().map(|arg| match arg {
+                IdlDefinedTypeArg::Generic(generic) => {
+                    let generic = ident(generic);
+                    quote! { #generic }
+                }
+                // Only single tokens can be const generic arguments without braces.
+                IdlDefinedTypeArg::Value(value) => match value.parse::<TokenStream>() {
+                    Ok(value) if value.clone().into_iter().count() == 1 => value,
+                    Ok(value) => quote! { { #value } },
+                    Err(_) => quote! { compile_error!("Invalid generic value") },
+                },
+                IdlDefinedTypeArg::Type(ty) => self::ty(ty),
+            });
+            quote! { #name<#(#args),*> }
+        }
+    }
+}
+
+/// Returns the generic parameters declared by a type definition and the
+/// arguments to refer to it, e.g. `<T, const N: usize>` and `<T, N>`.
+pub fn generics(
+    defs: &[&IdlTypeDefinition],
+    def: &IdlTypeDefinition,
+) -> (TokenStream, TokenStream) {
+    let generics = match &def.generics {
+        Some(generics) if !generics.is_empty() => generics,
+        _ => return (quote! {}, quote! {}),
+    };
+    let params = generics.iter().map(|generic| {
+        let name = ident(generic);
+        if is_const_generic(defs, def, generic, 0) {
+            quote! { const #name: usize }
+        } else {
+            quote! { #name }
+        }
+    });
+    let args = generics.iter().map(|generic| ident(generic));
+    (quote! { <#(#params),*> }, quote! { <#(#args),*> })
+}
+
+// The IDL doesn't tell const generics apart, they are the ones used as an
+// array length, either directly or through another generic type.
+// Recursive types are only followed up to a fixed depth.
+fn is_const_generic(
+    defs: &[&IdlTypeDefinition],
+    def: &IdlTypeDefinition,
+    generic: &str,
+    depth: usize,
+) -> bool {
+    fn check(defs: &[&IdlTypeDefinition], ty: &IdlType, generic: &str, depth: usize) -> bool {
+        match ty {
+            IdlType::Option(inner) | IdlType::Vec(inner) | IdlType::Array(inner, _) => {
+                check(defs, inner, generic, depth)
+            }
+            IdlType::GenericLenArray(inner, len) => {
+                len == generic || check(defs, inner, generic, depth)
+            }
+            IdlType::DefinedWithTypeArgs { name, args } => {
+                args.iter().enumerate().any(|(i, arg)| match arg {
+                    IdlDefinedTypeArg::Generic(arg) if arg == generic => defs
+                        .iter()
+                        .find(|def| &def.name == name)
+                        .and_then(|def| {
+                            let param = def.generics.as_ref()?.get(i)?;
+                            Some(is_const_generic(defs, def, param, depth + 1))
+                        })
+                        .unwrap_or(false),
+                    IdlDefinedTypeArg::Type(ty) => check(defs, ty, generic, depth),
+                    _ => false,
+                })
+            }
+            _ => false,
+        }
+    }
+
+    if depth > 8 {
+        return false;
+    }
+    let tys: Vec<&IdlType> = match &def.ty {
+        IdlTypeDefinitionTy::Struct { fields } => fields.iter().map(|f| &f.ty).collect(),
+        IdlTypeDefinitionTy::Enum { variants } => variants
+            .iter()
+            .flat_map(|v| match &v.fields {
+                Some(EnumFields::Named(fields)) => fields.iter().map(|f| &f.ty).collect(),
+                Some(EnumFields::Tuple(tys)) => tys.iter().collect(),
+                None => vec![],
+            })
+            .collect(),
+        IdlTypeDefinitionTy::Alias { value } => vec![value],
+    };
+    tys.into_iter().any(|ty| check(defs, ty, generic, depth))
+}
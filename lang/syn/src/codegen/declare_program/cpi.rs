@@ -0,0 +1,102 @@
+use super::accounts::generate_structs;
+use super::common::{camel_ident, discriminator, docs, snake_ident, ty};
+use crate::codegen::program::common::SIGHASH_GLOBAL_NAMESPACE;
+use crate::idl::types::Idl;
+use heck::SnakeCase;
+use quote::quote;
+
+pub fn generate(idl: &Idl) -> proc_macro2::TokenStream {
+    let cpi_methods = idl.instructions.iter().map(|ix| {
+        let method_name = snake_ident(&ix.name);
+        let docs = docs(&ix.docs);
+        let ix_name_camel = camel_ident(&ix.name);
+        let accounts_ident = if ix.accounts.is_empty() {
+            quote! { accounts::#ix_name_camel }
+        } else {
+            quote! { accounts::#ix_name_camel<'info> }
+        };
+        let arg_names: Vec<_> = ix.args.iter().map(|arg| snake_ident(&arg.name)).collect();
+        let arg_tys = ix.args.iter().map(|arg| ty(&arg.ty));
+        let ix_variant = if ix.args.is_empty() {
+            quote! { instruction::#ix_name_camel }
+        } else {
+            quote! { instruction::#ix_name_camel { #(#arg_names),* } }
+        };
+        let sighash_tts = discriminator(SIGHASH_GLOBAL_NAMESPACE, &ix.name.to_snake_case());
+        let (method_ret, maybe_return) = match &ix.returns {
+            None => (quote! { anchor_lang::Result<()> }, quote! { Ok(()) }),
+            Some(ret_type) => {
+                let ret_type = ty(ret_type);
+                (
+                    quote! { anchor_lang::Result<Return::<#ret_type>> },
+                    quote! { Ok(Return::<#ret_type> { phantom: PhantomData }) },
+                )
+            }
+        };
+
+        quote! {
+            #docs
+            pub fn #method_name<'a, 'b, 'c, 'info>(
+                ctx: anchor_lang::context::CpiContext<'a, 'b, 'c, 'info, #accounts_ident>,
+                #(#arg_names: #arg_tys),*
+            ) -> #method_ret {
+                let ix = {
+                    let ix = #ix_variant;
+                    let mut ix_data = AnchorSerialize::try_to_vec(&ix)
+                        .map_err(|_| anchor_lang::error::ErrorCode::InstructionDidNotSerialize)?;
+                    let mut data = #sighash_tts.to_vec();
+                    data.append(&mut ix_data);
+                    let accounts = ctx.to_account_metas(None);
+                    anchor_lang::solana_program::instruction::Instruction {
+                        program_id: ctx.program.key(),
+                        accounts,
+                        data,
+                    }
+                };
+                let mut acc_infos = ctx.to_account_infos();
+                anchor_lang::solana_program::program::invoke_signed(
+                    &ix,
+                    &acc_infos,
+                    ctx.signer_seeds,
+                ).map_or_else(
+                    |e| Err(Into::into(e)),
+                    // Maybe handle Solana return data.
+                    |_| { #maybe_return }
+                )
+            }
+        }
+    });
+
+    let account_structs = generate_structs(idl, true);
+
+    quote! {
+        /// An Anchor generated module, providing a function per instruction
+        /// to invoke the program through CPI.
+        pub mod cpi {
+            use super::*;
+            use std::marker::PhantomData;
+
+            pub struct Return<T> {
+                phantom: std::marker::PhantomData<T>
+            }
+
+            impl<T: AnchorDeserialize> Return<T> {
+                pub fn get(&self) -> T {
+                    let (_key, data) = anchor_lang::solana_program::program::get_return_data().unwrap();
+                    T::try_from_slice(&data).unwrap()
+                }
+            }
+
+            #(#cpi_methods)*
+
+            /// An Anchor generated module, providing a set of structs
+            /// mirroring the accounts of each instruction, where each field
+            /// is an `AccountInfo`. This is useful for CPI.
+            pub mod accounts {
+                use super::*;
+
+                #(#account_structs)*
+            }
+        }
+    }
+}
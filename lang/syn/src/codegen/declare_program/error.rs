@@ -0,0 +1,89 @@
+use super::common::ident;
+use crate::idl::types::Idl;
+use quote::quote;
+
+// Mirrors the `#[error_code]` codegen. Codes in the IDL already include the
+// offset, so they are used as is.
+pub fn generate(idl: &Idl) -> proc_macro2::TokenStream {
+    let errors = match &idl.errors {
+        Some(errors) if !errors.is_empty() => errors,
+        _ => return quote! {},
+    };
+
+    let variants = errors.iter().map(|error| {
+        let name = ident(&error.name);
+        let code = error.code;
+        quote! { #name = #code }
+    });
+    let name_variant_dispatch = errors.iter().map(|error| {
+        let name = ident(&error.name);
+        let name_str = &error.name;
+        quote! { ErrorCode::#name => #name_str.to_string() }
+    });
+    let code_variant_dispatch = errors.iter().map(|error| {
+        let name = ident(&error.name);
+        let code = error.code;
+        quote! { #code => Some(ErrorCode::#name) }
+    });
+    let display_variant_dispatch = errors.iter().map(|error| {
+        let name = ident(&error.name);
+        match &error.msg {
+            None => quote! { ErrorCode::#name => <Self as std::fmt::Debug>::fmt(self, fmt) },
+            Some(msg) => quote! { ErrorCode::#name => fmt.write_str(#msg) },
+        }
+    });
+
+    quote! {
+        /// Errors of the program.
+        #[derive(std::fmt::Debug, Clone, Copy, PartialEq, Eq)]
+        #[repr(u32)]
+        pub enum ErrorCode {
+            #(#variants),*
+        }
+
+        impl ErrorCode {
+            /// Gets the name of this [ErrorCode].
+            pub fn name(&self) -> String {
+                match self {
+                    #(#name_variant_dispatch),*
+                }
+            }
+
+            /// Gets the [ErrorCode] of a custom program error code.
+            pub fn from_code(code: u32) -> Option<Self> {
+                match code {
+                    #(#code_variant_dispatch,)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl From<ErrorCode> for u32 {
+            fn from(e: ErrorCode) -> u32 {
+                e as u32
+            }
+        }
+
+        impl From<ErrorCode> for anchor_lang::error::Error {
+            fn from(error_code: ErrorCode) -> anchor_lang::error::Error {
+                anchor_lang::error::Error::from(
+                    anchor_lang::error::AnchorError {
+                        error_name: error_code.name(),
+                        error_code_number: error_code.into(),
+                        error_msg: error_code.to_string(),
+                        error_origin: None,
+                        compared_values: None
+                    }
+                )
+            }
+        }
+
+        impl std::fmt::Display for ErrorCode {
+            fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+                match self {
+                    #(#display_variant_dispatch),*
+                }
+            }
+        }
+    }
+}
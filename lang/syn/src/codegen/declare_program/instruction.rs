@@ -0,0 +1,82 @@
+use super::common::{camel_ident, discriminator, docs, snake_ident, ty};
+use crate::codegen::program::common::SIGHASH_GLOBAL_NAMESPACE;
+use crate::idl::types::Idl;
+use heck::SnakeCase;
+use quote::quote;
+
+pub fn generate(idl: &Idl) -> proc_macro2::TokenStream {
+    let variants = idl.instructions.iter().map(|ix| {
+        let ix_name_camel = camel_ident(&ix.name);
+        let docs = docs(&ix.docs);
+        let discriminator = discriminator(SIGHASH_GLOBAL_NAMESPACE, &ix.name.to_snake_case());
+        let ix_data_trait = quote! {
+            impl anchor_lang::Discriminator for #ix_name_camel {
+                const DISCRIMINATOR: [u8; 8] = #discriminator;
+            }
+            impl anchor_lang::InstructionData for #ix_name_camel {}
+            impl anchor_lang::Owner for #ix_name_camel {
+                fn owner() -> Pubkey {
+                    ID
+                }
+            }
+        };
+        let method_name = snake_ident(&ix.name);
+        let arg_names: Vec<_> = ix.args.iter().map(|arg| snake_ident(&arg.name)).collect();
+        let arg_tys: Vec<_> = ix.args.iter().map(|arg| ty(&arg.ty)).collect();
+        let ix_variant = if ix.args.is_empty() {
+            quote! { #ix_name_camel }
+        } else {
+            quote! { #ix_name_camel { #(#arg_names),* } }
+        };
+        let builder = quote! {
+            #docs
+            #[allow(clippy::too_many_arguments)]
+            pub fn #method_name(
+                accounts: super::accounts::#ix_name_camel,
+                #(#arg_names: #arg_tys),*
+            ) -> anchor_lang::solana_program::instruction::Instruction {
+                anchor_lang::solana_program::instruction::Instruction {
+                    program_id: ID,
+                    accounts: anchor_lang::ToAccountMetas::to_account_metas(&accounts, None),
+                    data: anchor_lang::InstructionData::data(&#ix_variant),
+                }
+            }
+        };
+        // If no args, output a "unit" variant instead of a struct variant.
+        if ix.args.is_empty() {
+            quote! {
+                #docs
+                #[derive(AnchorSerialize, AnchorDeserialize)]
+                pub struct #ix_name_camel;
+
+                #ix_data_trait
+
+                #builder
+            }
+        } else {
+            quote! {
+                #docs
+                #[derive(AnchorSerialize, AnchorDeserialize)]
+                pub struct #ix_name_camel {
+                    #(pub #arg_names: #arg_tys),*
+                }
+
+                #ix_data_trait
+
+                #builder
+            }
+        }
+    });
+
+    quote! {
+        /// An Anchor generated module containing the program's set of
+        /// instructions, where each instruction of the IDL is associated
+        /// with a struct defining its input arguments, and a function
+        /// building the full instruction from its accounts and arguments.
+        pub mod instruction {
+            use super::*;
+
+            #(#variants)*
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::idl::types::Idl;
+use crate::DeclaredProgram;
+use common::{camel_ident, docs, snake_ident};
+use quote::quote;
+
+mod accounts;
+mod common;
+mod cpi;
+mod error;
+mod instruction;
+mod pda;
+mod types;
+
+pub fn generate(program: &DeclaredProgram) -> proc_macro2::TokenStream {
+    let idl = &program.idl;
+    let mod_name = snake_ident(&idl.name);
+    let program_name = camel_ident(&idl.name);
+    let docs = docs(&idl.docs);
+    let idl_path = &program.idl_path;
+    let items = generate_items(idl);
+    let cpi = cpi::generate(idl);
+
+    quote! {
+        #docs
+        pub mod #mod_name {
+            use anchor_lang::prelude::*;
+
+            // Rebuild whenever the IDL changes.
+            const _: &[u8] = include_bytes!(#idl_path);
+
+            /// Module representing the program.
+            pub mod program {
+                use super::*;
+
+                /// Type representing the program.
+                #[derive(Clone)]
+                pub struct #program_name;
+
+                impl anchor_lang::Id for #program_name {
+                    fn id() -> Pubkey {
+                        ID
+                    }
+                }
+            }
+
+            #items
+            #cpi
+        }
+    }
+}
+
+/// Generates the items of a standalone client of the program, without the
+/// CPI module. The output has no inner attributes, so it can be either the
+/// root of a crate or `include!`d from a build script.
+pub fn generate_client(idl: &Idl) -> proc_macro2::TokenStream {
+    let items = generate_items(idl);
+
+    quote! {
+        use anchor_lang::prelude::*;
+
+        #items
+    }
+}
+
+// Items shared by `declare_program!` and clients.
+fn generate_items(idl: &Idl) -> proc_macro2::TokenStream {
+    let id = match idl
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get("address"))
+        .and_then(|address| address.as_str())
+    {
+        Some(address) => quote! { anchor_lang::declare_id!(#address); },
+        None => quote! {
+            compile_error!("The IDL has no `metadata.address` to declare the program id with");
+        },
+    };
+    let types = types::generate(idl);
+    let error = error::generate(idl);
+    let instruction = instruction::generate(idl);
+    let accounts = accounts::generate(idl);
+    let pda = pda::generate(idl);
+
+    quote! {
+        #id
+        #types
+        #error
+        #instruction
+        #accounts
+        #pda
+    }
+}
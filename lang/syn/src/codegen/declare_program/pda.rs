@@ -0,0 +1,173 @@
+use super::common::{ident, snake_ident, ty};
+use crate::idl::types::{Idl, IdlAccountItem, IdlPda, IdlSeed, IdlType};
+use heck::SnakeCase;
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde_json::Value as JsonValue;
+
+// Generates a helper finding the address of each PDA of the IDL, taking the
+// non constant seeds as arguments.
+pub fn generate(idl: &Idl) -> TokenStream {
+    let mut pdas: Vec<(String, &IdlPda, String)> = vec![];
+    for ix in &idl.instructions {
+        collect(&ix.name, &ix.accounts, &mut pdas);
+    }
+
+    let mut names = vec![];
+    let helpers = pdas.iter().filter_map(|(account, pda, ix)| {
+        // Accounts sharing a name and seeds across instructions share a helper.
+        let name = if pdas
+            .iter()
+            .any(|(other, other_pda, _)| other == account && other_pda != pda)
+        {
+            format!(
+                "find_{}_{}_address",
+                ix.to_snake_case(),
+                account.to_snake_case()
+            )
+        } else {
+            format!("find_{}_address", account.to_snake_case())
+        };
+        if names.contains(&name) {
+            return None;
+        }
+        names.push(name.clone());
+        generate_helper(&name, account, ix, pda)
+    });
+    let helpers: Vec<TokenStream> = helpers.collect();
+
+    quote! {
+        /// An Anchor generated module, providing a function per PDA of the
+        /// program to find its address from its seeds.
+        pub mod pda {
+            use super::*;
+
+            #(#helpers)*
+        }
+    }
+}
+
+fn collect<'a>(
+    ix: &str,
+    accounts: &'a [IdlAccountItem],
+    pdas: &mut Vec<(String, &'a IdlPda, String)>,
+) {
+    for account in accounts {
+        match account {
+            IdlAccountItem::IdlAccounts(nested) => collect(ix, &nested.accounts, pdas),
+            IdlAccountItem::IdlAccount(account) => {
+                if let Some(pda) = &account.pda {
+                    pdas.push((account.name.clone(), pda, ix.to_string()));
+                }
+            }
+        }
+    }
+}
+
+// Returns `None` if a seed can't be expressed, e.g. a constant of an
+// unsupported type.
+fn generate_helper(name: &str, account: &str, ix: &str, pda: &IdlPda) -> Option<TokenStream> {
+    let mut params: Vec<(String, TokenStream)> = vec![];
+    let mut seeds = vec![];
+    for seed in &pda.seeds {
+        seeds.push(generate_seed(seed, &mut params)?);
+    }
+    let program_id = match &pda.program_id {
+        None => quote! { ID },
+        Some(IdlSeed::Const(seed)) => {
+            let bytes = const_seed_bytes(&seed.ty, &seed.value)?;
+            if bytes.len() != 32 {
+                return None;
+            }
+            quote! { Pubkey::new_from_array([#(#bytes),*]) }
+        }
+        Some(IdlSeed::Arg(seed)) => seed_param(&seed.ty, &seed.path, &mut params)?.0,
+        Some(IdlSeed::Account(seed)) => seed_param(&seed.ty, &seed.path, &mut params)?.0,
+    };
+
+    let name = ident(name);
+    let doc = format!(" Finds the address of the `{account}` account of `{ix}`.");
+    let params = params.iter().map(|(name, ty)| {
+        let name = ident(name);
+        quote! { #name: #ty }
+    });
+
+    Some(quote! {
+        #[doc = #doc]
+        pub fn #name(#(#params),*) -> (Pubkey, u8) {
+            let seeds: &[&[u8]] = &[#(#seeds),*];
+            Pubkey::find_program_address(seeds, &#program_id)
+        }
+    })
+}
+
+fn generate_seed(seed: &IdlSeed, params: &mut Vec<(String, TokenStream)>) -> Option<TokenStream> {
+    match seed {
+        IdlSeed::Const(seed) => {
+            let bytes = const_seed_bytes(&seed.ty, &seed.value)?;
+            Some(quote! { &[#(#bytes),*] })
+        }
+        IdlSeed::Arg(seed) => seed_param(&seed.ty, &seed.path, params).map(|(_, bytes)| bytes),
+        IdlSeed::Account(seed) => seed_param(&seed.ty, &seed.path, params).map(|(_, bytes)| bytes),
+    }
+}
+
+// Adds the parameter for a seed coming from an argument or an account, and
+// returns it along with the expression of its bytes.
+fn seed_param(
+    seed_ty: &IdlType,
+    path: &str,
+    params: &mut Vec<(String, TokenStream)>,
+) -> Option<(TokenStream, TokenStream)> {
+    let name = path.replace('.', "_").to_snake_case();
+    let param = snake_ident(&name);
+    let (param_ty, bytes) = match seed_ty {
+        IdlType::String => (quote! { &str }, quote! { #param.as_bytes() }),
+        IdlType::Bytes | IdlType::Vec(_) => (quote! { &[u8] }, quote! { #param }),
+        IdlType::Array(inner, _) if **inner == IdlType::U8 => (ty(seed_ty), quote! { &#param[..] }),
+        IdlType::PublicKey => (quote! { Pubkey }, quote! { #param.as_ref() }),
+        IdlType::Bool => (quote! { bool }, quote! { &[#param as u8] }),
+        IdlType::U8 => (quote! { u8 }, quote! { &[#param] }),
+        IdlType::I8
+        | IdlType::U16
+        | IdlType::I16
+        | IdlType::U32
+        | IdlType::I32
+        | IdlType::U64
+        | IdlType::I64
+        | IdlType::U128
+        | IdlType::I128 => (ty(seed_ty), quote! { &#param.to_le_bytes() }),
+        _ => return None,
+    };
+    if !params.iter().any(|(existing, _)| existing == &name) {
+        params.push((name, param_ty));
+    }
+    Some((quote! { #param }, bytes))
+}
+
+fn const_seed_bytes(seed_ty: &IdlType, value: &JsonValue) -> Option<Vec<u8>> {
+    match value {
+        JsonValue::String(value) => Some(value.as_bytes().to_vec()),
+        JsonValue::Array(values) => values
+            .iter()
+            .map(|value| value.as_u64().and_then(|value| u8::try_from(value).ok()))
+            .collect(),
+        JsonValue::Number(value) => {
+            let value = value
+                .as_i64()
+                .map(i128::from)
+                .or_else(|| value.as_u64().map(i128::from))?;
+            let bytes = value.to_le_bytes();
+            let len = match seed_ty {
+                IdlType::U8 | IdlType::I8 => 1,
+                IdlType::U16 | IdlType::I16 => 2,
+                IdlType::U32 | IdlType::I32 => 4,
+                IdlType::U64 | IdlType::I64 => 8,
+                IdlType::U128 | IdlType::I128 => 16,
+                _ => return None,
+            };
+            Some(bytes[..len].to_vec())
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,256 @@
+use super::common::{defined_ident, discriminator, docs, generics, snake_ident, ty};
+use crate::idl::types::{EnumFields, Idl, IdlField, IdlTypeDefinition, IdlTypeDefinitionTy};
+use quote::quote;
+
+// Generates the user defined types, followed by the account types with their
+// serialization and ownership impls, mirroring `#[account]`.
+pub fn generate(idl: &Idl) -> proc_macro2::TokenStream {
+    let defs: Vec<&IdlTypeDefinition> = idl.types.iter().chain(idl.accounts.iter()).collect();
+
+    let types = idl.types.iter().map(|def| generate_type(&defs, def));
+    let accounts = idl.accounts.iter().map(|def| {
+        let name = defined_ident(&def.name);
+        let name_str = name.to_string();
+        let (params, args) = generics(&defs, def);
+        let bounds = if def.generics.iter().flatten().next().is_some() {
+            quote! { where Self: AnchorSerialize + AnchorDeserialize }
+        } else {
+            quote! {}
+        };
+        let discriminator = discriminator("account", &name_str);
+        let ty = generate_type(&defs, def);
+
+        quote! {
+            #ty
+
+            #[automatically_derived]
+            impl #params anchor_lang::AccountSerialize for #name #args #bounds {
+                fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> anchor_lang::Result<()> {
+                    writer
+                        .write_all(&#discriminator)
+                        .and_then(|_| AnchorSerialize::serialize(self, writer))
+                        .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotSerialize.into())
+                }
+            }
+
+            #[automatically_derived]
+            impl #params anchor_lang::AccountDeserialize for #name #args #bounds {
+                fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+                    if buf.len() < #discriminator.len() {
+                        return Err(anchor_lang::error::ErrorCode::AccountDiscriminatorNotFound.into());
+                    }
+                    let given_disc = &buf[..8];
+                    if given_disc != #discriminator {
+                        return Err(anchor_lang::error!(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch).with_account_name(#name_str));
+                    }
+                    Self::try_deserialize_unchecked(buf)
+                }
+
+                fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+                    let mut data: &[u8] = &buf[8..];
+                    AnchorDeserialize::deserialize(&mut data)
+                        .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize.into())
+                }
+            }
+
+            #[automatically_derived]
+            impl #params anchor_lang::Discriminator for #name #args {
+                const DISCRIMINATOR: [u8; 8] = #discriminator;
+            }
+
+            #[automatically_derived]
+            impl #params anchor_lang::Owner for #name #args {
+                fn owner() -> Pubkey {
+                    ID
+                }
+            }
+        }
+    });
+
+    let events = idl.events.iter().flatten().map(|event| {
+        let name = defined_ident(&event.name);
+        let discriminator = discriminator("event", &name.to_string());
+        let fields = event.fields.iter().map(|field| {
+            let name = snake_ident(&field.name);
+            let ty = ty(&field.ty);
+            quote! { pub #name: #ty }
+        });
+
+        quote! {
+            #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+            pub struct #name {
+                #(#fields),*
+            }
+
+            impl anchor_lang::Event for #name {
+                fn data(&self) -> Vec<u8> {
+                    let mut d = #discriminator.to_vec();
+                    d.append(&mut self.try_to_vec().unwrap());
+                    d
+                }
+            }
+
+            impl anchor_lang::Discriminator for #name {
+                const DISCRIMINATOR: [u8; 8] = #discriminator;
+            }
+        }
+    });
+
+    let decoders = generate_decoders(idl);
+
+    quote! {
+        #(#types)*
+        #(#accounts)*
+        #(#events)*
+        #decoders
+    }
+}
+
+// Generates the `ProgramAccount` and `ProgramEvent` enums, to decode any
+// account or event of the program from its discriminator.
+fn generate_decoders(idl: &Idl) -> proc_macro2::TokenStream {
+    // Generic accounts can't be part of the enum.
+    let accounts: Vec<_> = idl
+        .accounts
+        .iter()
+        .filter(|def| def.generics.iter().flatten().next().is_none())
+        .map(|def| defined_ident(&def.name))
+        .collect();
+    let account_decoder = if accounts.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            /// Any account of the program.
+            #[derive(Clone, Debug)]
+            pub enum ProgramAccount {
+                #(#accounts(#accounts)),*
+            }
+
+            impl ProgramAccount {
+                /// Decodes an account of the program from its data, including
+                /// the discriminator.
+                pub fn decode(data: &[u8]) -> anchor_lang::Result<Self> {
+                    if data.len() < 8 {
+                        return Err(anchor_lang::error::ErrorCode::AccountDiscriminatorNotFound.into());
+                    }
+                    match &data[..8] {
+                        #(
+                            disc if disc == <#accounts as anchor_lang::Discriminator>::DISCRIMINATOR => {
+                                <#accounts as anchor_lang::AccountDeserialize>::try_deserialize(&mut &data[..])
+                                    .map(Self::#accounts)
+                            }
+                        )*
+                        _ => Err(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch.into()),
+                    }
+                }
+            }
+        }
+    };
+
+    let events: Vec<_> = idl
+        .events
+        .iter()
+        .flatten()
+        .map(|event| defined_ident(&event.name))
+        .collect();
+    let event_decoder = if events.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            /// Any event of the program.
+            #[derive(Clone, Debug)]
+            pub enum ProgramEvent {
+                #(#events(#events)),*
+            }
+
+            impl ProgramEvent {
+                /// Decodes an event of the program from its data, including
+                /// the discriminator. Returns `None` for unknown or invalid
+                /// events.
+                pub fn decode(data: &[u8]) -> Option<Self> {
+                    if data.len() < 8 {
+                        return None;
+                    }
+                    let (given_disc, mut data) = data.split_at(8);
+                    match given_disc {
+                        #(
+                            disc if disc == <#events as anchor_lang::Discriminator>::DISCRIMINATOR => {
+                                <#events as AnchorDeserialize>::deserialize(&mut data)
+                                    .ok()
+                                    .map(Self::#events)
+                            }
+                        )*
+                        _ => None,
+                    }
+                }
+            }
+        }
+    };
+
+    quote! {
+        #account_decoder
+        #event_decoder
+    }
+}
+
+fn generate_type(defs: &[&IdlTypeDefinition], def: &IdlTypeDefinition) -> proc_macro2::TokenStream {
+    let name = defined_ident(&def.name);
+    let docs = docs(&def.docs);
+    let (params, _) = generics(defs, def);
+
+    match &def.ty {
+        IdlTypeDefinitionTy::Struct { fields } => {
+            let fields = generate_fields(fields, quote! { pub });
+            quote! {
+                #docs
+                #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+                pub struct #name #params {
+                    #fields
+                }
+            }
+        }
+        IdlTypeDefinitionTy::Enum { variants } => {
+            let variants = variants.iter().map(|variant| {
+                let name = defined_ident(&variant.name);
+                match &variant.fields {
+                    None => quote! { #name },
+                    Some(EnumFields::Named(fields)) => {
+                        let fields = generate_fields(fields, quote! {});
+                        quote! { #name { #fields } }
+                    }
+                    Some(EnumFields::Tuple(tys)) => {
+                        let tys = tys.iter().map(ty);
+                        quote! { #name(#(#tys),*) }
+                    }
+                }
+            });
+            quote! {
+                #docs
+                #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+                pub enum #name #params {
+                    #(#variants),*
+                }
+            }
+        }
+        IdlTypeDefinitionTy::Alias { value } => {
+            let value = ty(value);
+            quote! {
+                #docs
+                pub type #name #params = #value;
+            }
+        }
+    }
+}
+
+fn generate_fields(fields: &[IdlField], vis: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    let fields = fields.iter().map(|field| {
+        let docs = docs(&field.docs);
+        let name = snake_ident(&field.name);
+        let ty = ty(&field.ty);
+        quote! {
+            #docs
+            #vis #name: #ty
+        }
+    });
+    quote! { #(#fields),* }
+}
@@ -50,6 +50,20 @@ pub fn generate(error: Error) -> proc_macro2::TokenStream {
         })
         .collect();
 
+    // Each arm of the `match` statement for implementing the `from_code`
+    // function on the user defined error code.
+    let code_variant_dispatch: Vec<proc_macro2::TokenStream> = error
+        .raw_enum
+        .variants
+        .iter()
+        .map(|variant| {
+            let ident = &variant.ident;
+            quote! {
+                code if code == u32::from(#enum_name::#ident) => Some(#enum_name::#ident)
+            }
+        })
+        .collect();
+
     let offset = match &error.args {
         None => quote! { anchor_lang::error::ERROR_CODE_OFFSET},
         Some(args) => {
@@ -70,6 +84,24 @@ pub fn generate(error: Error) -> proc_macro2::TokenStream {
                     #(#name_variant_dispatch),*
                 }
             }
+
+            /// Gets the [#enum_name] of an error code, offset included.
+            pub fn from_code(code: u32) -> Option<Self> {
+                match code {
+                    #(#code_variant_dispatch,)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl anchor_lang::error::ProgramErrorCode for #enum_name {
+            fn from_code(code: u32) -> Option<Self> {
+                #enum_name::from_code(code)
+            }
+
+            fn name(&self) -> String {
+                #enum_name::name(self)
+            }
         }
 
         impl From<#enum_name> for u32 {
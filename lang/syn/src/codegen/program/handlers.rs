@@ -150,9 +150,12 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
 
                     let mut __reallocs = std::collections::BTreeSet::new();
 
+                    // Target sizes of `realloc::incremental` accounts.
+                    let mut __realloc_targets = std::collections::BTreeMap::new();
+
                     // Deserialize accounts.
                     let mut __remaining_accounts: &[AccountInfo] = __accounts;
-                    let mut __accounts = #anchor::try_accounts(
+                    let mut __accounts = #anchor::try_accounts_with_realloc_targets(
                         __program_id,
                         &mut __remaining_accounts,
                         __ix_data,
@@ -160,8 +163,19 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
                         &mut __seeds,
                         &mut __state,
                         &mut __reallocs,
+                        &mut __realloc_targets,
                     )?;
 
+                    // Accounts still growing towards their `realloc::incremental`
+                    // target are not handed to the user until fully grown.
+                    if !__realloc_targets.is_empty()
+                        && anchor_lang::ToAccountInfos::to_account_infos(&__accounts)
+                            .iter()
+                            .any(|info| __realloc_targets.get(info.key).map_or(false, |target| info.data_len() < *target))
+                    {
+                        return __accounts.exit(__program_id);
+                    }
+
                     // Invoke user defined handler.
                     let result = #program_name::#ix_method_name(
                         anchor_lang::context::Context::new(
@@ -0,0 +1,88 @@
+use crate::idl::types::Idl;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+/// Generates the source of a standalone Rust client from an IDL.
+///
+/// The client only depends on `anchor-lang`, and contains the types,
+/// accounts, events and `ErrorCode` of the program, along with:
+///
+/// - `instruction`: the instruction data and a builder per instruction
+/// - `accounts`: the accounts of each instruction
+/// - `pda`: a function per PDA finding its address from its seeds
+/// - `ProgramAccount` and `ProgramEvent`: decoders checking discriminators
+///
+/// The `accounts` and `instruction` structs can be passed as is to the
+/// `accounts` and `args` methods of the `anchor-client` `RequestBuilder`.
+pub fn generate(idl: &Idl) -> String {
+    crate::codegen::declare_program::generate_client(idl).to_string()
+}
+
+/// Generates the client of the IDL at `idl_path` into `out_path`. Meant to be
+/// called from a build script.
+///
+/// # Example
+///
+/// ```ignore
+/// // build.rs
+/// fn main() {
+///     let out_dir = std::env::var("OUT_DIR").unwrap();
+///     anchor_syn::idl::client::generate_to_file(
+///         "idls/counter.json",
+///         format!("{out_dir}/counter.rs"),
+///     )
+///     .unwrap();
+/// }
+///
+/// // src/lib.rs
+/// pub mod counter {
+///     include!(concat!(env!("OUT_DIR"), "/counter.rs"));
+/// }
+/// ```
+pub fn generate_to_file(
+    idl_path: impl AsRef<Path>,
+    out_path: impl AsRef<Path>,
+) -> std::io::Result<()> {
+    let idl_path = idl_path.as_ref();
+    println!("cargo:rerun-if-changed={}", idl_path.display());
+
+    let idl: Idl = serde_json::from_slice(&std::fs::read(idl_path)?)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    std::fs::write(out_path, generate(&idl))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_is_valid_rust() {
+        let idl: Idl =
+            serde_json::from_str(include_str!("../../../tests/idls/counter.json")).unwrap();
+        let client = syn::parse_file(&generate(&idl)).unwrap();
+        assert!(client.attrs.is_empty());
+
+        let item_names: Vec<String> = client
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                syn::Item::Mod(item) => Some(item.ident.to_string()),
+                syn::Item::Struct(item) => Some(item.ident.to_string()),
+                syn::Item::Enum(item) => Some(item.ident.to_string()),
+                _ => None,
+            })
+            .collect();
+        for name in [
+            "instruction",
+            "accounts",
+            "pda",
+            "Counter",
+            "ErrorCode",
+            "ProgramAccount",
+            "ProgramEvent",
+        ] {
+            assert!(item_names.iter().any(|item| item == name), "missing {name}");
+        }
+        assert!(!item_names.iter().any(|item| item == "cpi"));
+    }
+}
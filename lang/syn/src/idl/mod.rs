@@ -1,6 +1,9 @@
 #[cfg(feature = "idl-build")]
 pub mod build;
 
+#[cfg(feature = "idl-types")]
+pub mod client;
+
 #[cfg(feature = "idl-parse")]
 pub mod parse;
 
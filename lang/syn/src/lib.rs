@@ -54,6 +54,36 @@ impl ToTokens for Program {
     }
 }
 
+#[cfg(feature = "idl-types")]
+#[derive(Debug)]
+pub struct DeclaredProgram {
+    pub idl: idl::types::Idl,
+    // Absolute path of the IDL file, so that it can be tracked by the compiler.
+    pub idl_path: String,
+}
+
+#[cfg(feature = "idl-types")]
+impl Parse for DeclaredProgram {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        let path = <syn::LitStr as Parse>::parse(input)?;
+        parser::declare_program::parse(&path)
+    }
+}
+
+#[cfg(feature = "idl-types")]
+impl From<&DeclaredProgram> for TokenStream {
+    fn from(program: &DeclaredProgram) -> Self {
+        codegen::declare_program::generate(program)
+    }
+}
+
+#[cfg(feature = "idl-types")]
+impl ToTokens for DeclaredProgram {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend::<TokenStream>(self.into());
+    }
+}
+
 #[derive(Debug)]
 pub struct Ix {
     pub raw_method: ItemFn,
@@ -748,6 +778,7 @@ pub enum ConstraintToken {
     State(Context<ConstraintState>),
     Executable(Context<ConstraintExecutable>),
     Close(Context<ConstraintClose>),
+    CloseTombstone(Context<ConstraintCloseTombstone>),
     Payer(Context<ConstraintPayer>),
     Space(Context<ConstraintSpace>),
     Address(Context<ConstraintAddress>),
@@ -766,6 +797,7 @@ pub enum ConstraintToken {
     Realloc(Context<ConstraintRealloc>),
     ReallocPayer(Context<ConstraintReallocPayer>),
     ReallocZero(Context<ConstraintReallocZero>),
+    ReallocIncremental(Context<ConstraintReallocIncremental>),
 }
 
 impl Parse for ConstraintToken {
@@ -795,6 +827,8 @@ pub struct ConstraintReallocGroup {
     pub payer: Expr,
     pub space: Expr,
     pub zero: Expr,
+    // Grow by at most `MAX_PERMITTED_DATA_INCREASE` per instruction.
+    pub incremental: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -812,6 +846,9 @@ pub struct ConstraintReallocZero {
     pub zero: Expr,
 }
 
+#[derive(Debug, Clone)]
+pub struct ConstraintReallocIncremental {}
+
 #[derive(Debug, Clone)]
 pub struct ConstraintSigner {
     pub error: Option<Expr>,
@@ -923,8 +960,14 @@ pub enum InitKind {
 #[derive(Debug, Clone)]
 pub struct ConstraintClose {
     pub sol_dest: Ident,
+    // Keep the account owned by the program and mark it with the closed
+    // account discriminator instead of handing it back to the system program.
+    pub tombstone: bool,
 }
 
+#[derive(Debug, Clone)]
+pub struct ConstraintCloseTombstone {}
+
 #[derive(Debug, Clone)]
 pub struct ConstraintTokenMint {
     pub mint: Expr,
@@ -39,6 +39,9 @@ pub fn parse_token(stream: ParseStream) -> ParseResult<ConstraintToken> {
             ConstraintInit { if_needed: true },
         )),
         "zero" => ConstraintToken::Zeroed(Context::new(ident.span(), ConstraintZeroed {})),
+        "tombstone" => {
+            ConstraintToken::CloseTombstone(Context::new(ident.span(), ConstraintCloseTombstone {}))
+        }
         "mut" => ConstraintToken::Mut(Context::new(
             ident.span(),
             ConstraintMut {
@@ -231,6 +234,16 @@ pub fn parse_token(stream: ParseStream) -> ParseResult<ConstraintToken> {
                 stream.parse::<Token![:]>()?;
                 stream.parse::<Token![:]>()?;
                 let kw = stream.call(Ident::parse_any)?.to_string();
+                if kw == "incremental" {
+                    let span = ident
+                        .span()
+                        .join(stream.span())
+                        .unwrap_or_else(|| ident.span());
+                    return Ok(ConstraintToken::ReallocIncremental(Context::new(
+                        span,
+                        ConstraintReallocIncremental {},
+                    )));
+                }
                 stream.parse::<Token![=]>()?;
 
                 let span = ident
@@ -251,7 +264,7 @@ pub fn parse_token(stream: ParseStream) -> ParseResult<ConstraintToken> {
                             zero: stream.parse()?,
                         },
                     )),
-                    _ => return Err(ParseError::new(ident.span(), "Invalid attribute. realloc::payer and realloc::zero are the only valid attributes")),
+                    _ => return Err(ParseError::new(ident.span(), "Invalid attribute. realloc::payer, realloc::zero and realloc::incremental are the only valid attributes")),
                 }
             }
         }
@@ -318,6 +331,7 @@ pub fn parse_token(stream: ParseStream) -> ParseResult<ConstraintToken> {
                     span,
                     ConstraintClose {
                         sol_dest: stream.parse()?,
+                        tombstone: false,
                     },
                 )),
                 "address" => ConstraintToken::Address(Context::new(
@@ -362,6 +376,7 @@ pub struct ConstraintGroupBuilder<'ty> {
     pub payer: Option<Context<ConstraintPayer>>,
     pub space: Option<Context<ConstraintSpace>>,
     pub close: Option<Context<ConstraintClose>>,
+    pub close_tombstone: Option<Context<ConstraintCloseTombstone>>,
     pub address: Option<Context<ConstraintAddress>>,
     pub token_mint: Option<Context<ConstraintTokenMint>>,
     pub token_authority: Option<Context<ConstraintTokenAuthority>>,
@@ -378,6 +393,7 @@ pub struct ConstraintGroupBuilder<'ty> {
     pub realloc: Option<Context<ConstraintRealloc>>,
     pub realloc_payer: Option<Context<ConstraintReallocPayer>>,
     pub realloc_zero: Option<Context<ConstraintReallocZero>>,
+    pub realloc_incremental: Option<Context<ConstraintReallocIncremental>>,
 }
 
 impl<'ty> ConstraintGroupBuilder<'ty> {
@@ -399,6 +415,7 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             payer: None,
             space: None,
             close: None,
+            close_tombstone: None,
             address: None,
             token_mint: None,
             token_authority: None,
@@ -415,6 +432,7 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             realloc: None,
             realloc_payer: None,
             realloc_zero: None,
+            realloc_incremental: None,
         }
     }
 
@@ -576,10 +594,17 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
                         "space is not required for initializing an spl account",
                     ));
                 }
-                (false, false) => {
+                (false, false)
+                    if !matches!(
+                        self.f_ty,
+                        Some(Ty::Account(_))
+                            | Some(Ty::InterfaceAccount(_))
+                            | Some(Ty::AccountLoader(_))
+                    ) =>
+                {
                     return Err(ParseError::new(
                         i.span(),
-                        "space must be provided with init",
+                        "space must be provided with init unless the account is an Account, InterfaceAccount or AccountLoader",
                     ));
                 }
                 _ => (),
@@ -603,6 +628,7 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             payer,
             space,
             close,
+            close_tombstone,
             address,
             token_mint,
             token_authority,
@@ -619,6 +645,7 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             realloc,
             realloc_payer,
             realloc_zero,
+            realloc_incremental,
         } = self;
 
         // Converts Option<Context<T>> -> Option<T>.
@@ -771,6 +798,7 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
                 payer: into_inner!(realloc_payer).unwrap().target,
                 space: r.space.clone(),
                 zero: into_inner!(realloc_zero).unwrap().zero,
+                incremental: realloc_incremental.is_some(),
             }),
             zeroed: into_inner!(zeroed),
             mutable: into_inner!(mutable),
@@ -780,7 +808,10 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             owner: into_inner!(owner),
             rent_exempt: into_inner!(rent_exempt),
             executable: into_inner!(executable),
-            close: into_inner!(close),
+            close: into_inner!(close).map(|c| ConstraintClose {
+                tombstone: close_tombstone.is_some(),
+                ..c
+            }),
             address: into_inner!(address),
             associated_token: if !is_init { associated_token } else { None },
             seeds,
@@ -806,6 +837,7 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             ConstraintToken::Payer(c) => self.add_payer(c),
             ConstraintToken::Space(c) => self.add_space(c),
             ConstraintToken::Close(c) => self.add_close(c),
+            ConstraintToken::CloseTombstone(c) => self.add_close_tombstone(c),
             ConstraintToken::Address(c) => self.add_address(c),
             ConstraintToken::TokenAuthority(c) => self.add_token_authority(c),
             ConstraintToken::TokenMint(c) => self.add_token_mint(c),
@@ -824,6 +856,7 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             ConstraintToken::Realloc(c) => self.add_realloc(c),
             ConstraintToken::ReallocPayer(c) => self.add_realloc_payer(c),
             ConstraintToken::ReallocZero(c) => self.add_realloc_zero(c),
+            ConstraintToken::ReallocIncremental(c) => self.add_realloc_incremental(c),
         }
     }
 
@@ -960,6 +993,26 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
         Ok(())
     }
 
+    fn add_realloc_incremental(
+        &mut self,
+        c: Context<ConstraintReallocIncremental>,
+    ) -> ParseResult<()> {
+        if self.realloc.is_none() {
+            return Err(ParseError::new(
+                c.span(),
+                "realloc must be provided before realloc::incremental",
+            ));
+        }
+        if self.realloc_incremental.is_some() {
+            return Err(ParseError::new(
+                c.span(),
+                "realloc::incremental already provided",
+            ));
+        }
+        self.realloc_incremental.replace(c);
+        Ok(())
+    }
+
     fn add_close(&mut self, c: Context<ConstraintClose>) -> ParseResult<()> {
         if !matches!(self.f_ty, Some(Ty::Account(_)))
             && !matches!(self.f_ty, Some(Ty::AccountLoader(_)))
@@ -982,6 +1035,20 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
         Ok(())
     }
 
+    fn add_close_tombstone(&mut self, c: Context<ConstraintCloseTombstone>) -> ParseResult<()> {
+        if self.close.is_none() {
+            return Err(ParseError::new(
+                c.span(),
+                "close must be provided before tombstone",
+            ));
+        }
+        if self.close_tombstone.is_some() {
+            return Err(ParseError::new(c.span(), "tombstone already provided"));
+        }
+        self.close_tombstone.replace(c);
+        Ok(())
+    }
+
     fn add_address(&mut self, c: Context<ConstraintAddress>) -> ParseResult<()> {
         if self.address.is_some() {
             return Err(ParseError::new(c.span(), "address already provided"));
@@ -0,0 +1,23 @@
+use crate::idl::types::Idl;
+use crate::DeclaredProgram;
+use std::path::PathBuf;
+use syn::parse::{Error as ParseError, Result as ParseResult};
+use syn::LitStr;
+
+// Reads the IDL file at the given path, relative to the root of the crate
+// invoking the macro.
+pub fn parse(path: &LitStr) -> ParseResult<DeclaredProgram> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let idl_path = PathBuf::from(manifest_dir).join(path.value());
+    let idl_path_str = idl_path.display().to_string();
+
+    let json = std::fs::read_to_string(&idl_path)
+        .map_err(|e| ParseError::new(path.span(), format!("Unable to read {idl_path_str}: {e}")))?;
+    let idl: Idl = serde_json::from_str(&json)
+        .map_err(|e| ParseError::new(path.span(), format!("Invalid IDL {idl_path_str}: {e}")))?;
+
+    Ok(DeclaredProgram {
+        idl,
+        idl_path: idl_path_str,
+    })
+}
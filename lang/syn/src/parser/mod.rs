@@ -1,5 +1,7 @@
 pub mod accounts;
 pub mod context;
+#[cfg(feature = "idl-types")]
+pub mod declare_program;
 pub mod docs;
 pub mod error;
 pub mod program;
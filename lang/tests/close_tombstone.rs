@@ -0,0 +1,126 @@
+use anchor_lang::__private::CLOSED_ACCOUNT_DISCRIMINATOR;
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::Discriminator;
+
+// Needed to declare accounts.
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[account]
+pub struct Data {
+    pub value: u64,
+}
+
+#[derive(Accounts)]
+pub struct CloseData<'info> {
+    #[account(mut, close = destination, tombstone)]
+    pub data: Account<'info, Data>,
+    #[account(mut)]
+    pub destination: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UseData<'info> {
+    #[account(mut)]
+    pub data: Account<'info, Data>,
+}
+
+// Mirrors the input serialized by the runtime, which `AccountInfo::realloc`
+// reads the original and current data lengths from.
+#[repr(C)]
+struct SerializedKey {
+    _padding: u32,
+    original_data_len: u32,
+    key: Pubkey,
+}
+
+#[repr(C)]
+struct SerializedData {
+    data_len: u64,
+    data: [u8; 16],
+}
+
+fn try_accounts<'info, T: Accounts<'info>>(accounts: &[AccountInfo<'info>]) -> Result<T> {
+    T::try_accounts(
+        &crate::ID,
+        &mut &accounts[..],
+        &[],
+        &mut Default::default(),
+        &mut Default::default(),
+        &mut Default::default(),
+        &mut Default::default(),
+    )
+}
+
+#[test]
+fn test_close_tombstone_rejects_revival() {
+    let key = SerializedKey {
+        _padding: 0,
+        original_data_len: 16,
+        key: Pubkey::new_unique(),
+    };
+    let mut data = SerializedData {
+        data_len: 16,
+        data: [0; 16],
+    };
+    data.data[..8].copy_from_slice(&Data::DISCRIMINATOR);
+    data.data[8..].copy_from_slice(&7u64.to_le_bytes());
+    let mut lamports = 100;
+    let data_info = AccountInfo::new(
+        &key.key,
+        false,
+        true,
+        &mut lamports,
+        &mut data.data,
+        &crate::ID,
+        false,
+        0,
+    );
+    let destination_key = Pubkey::new_unique();
+    let mut destination_lamports = 5;
+    let mut destination_data = vec![];
+    let destination_info = AccountInfo::new(
+        &destination_key,
+        false,
+        true,
+        &mut destination_lamports,
+        &mut destination_data,
+        &system_program::ID,
+        false,
+        0,
+    );
+
+    let accounts = [data_info.clone(), destination_info.clone()];
+    let close: CloseData = try_accounts(&accounts).unwrap();
+    assert_eq!(close.data.value, 7);
+    close.exit(&crate::ID).unwrap();
+    drop(close);
+
+    // The account keeps its owner, but only holds the closed discriminator.
+    assert_eq!(data_info.lamports(), 0);
+    assert_eq!(destination_info.lamports(), 105);
+    assert_eq!(data_info.owner, &crate::ID);
+    assert_eq!(
+        &data_info.try_borrow_data().unwrap()[..],
+        &CLOSED_ACCOUNT_DISCRIMINATOR
+    );
+
+    // Refunding the account does not make it usable again.
+    **data_info.try_borrow_mut_lamports().unwrap() = 100;
+    assert_eq!(
+        try_accounts::<UseData>(std::slice::from_ref(&data_info))
+            .err()
+            .unwrap(),
+        Error::from(ErrorCode::AccountClosed).with_account_name("data")
+    );
+    assert_eq!(
+        try_accounts::<CloseData>(&accounts).err().unwrap(),
+        Error::from(ErrorCode::AccountClosed).with_account_name("data")
+    );
+
+    // Its lamports can only be reclaimed through `force_defund`.
+    anchor_lang::force_defund(&data_info, &destination_info, &crate::ID).unwrap();
+    assert_eq!(data_info.lamports(), 0);
+    assert_eq!(destination_info.lamports(), 205);
+}
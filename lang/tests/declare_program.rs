@@ -0,0 +1,216 @@
+#![allow(dead_code)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::account_info::AccountInfo;
+use anchor_lang::{Discriminator, InstructionData};
+
+declare_program!("tests/idls/counter.json");
+
+use counter::{Config, Counter, History, Mode};
+
+#[test]
+fn test_declared_id() {
+    assert_eq!(
+        counter::ID.to_string(),
+        "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS"
+    );
+    assert_eq!(counter::program::Counter::id(), counter::ID);
+    assert_eq!(Counter::owner(), counter::ID);
+}
+
+#[test]
+fn test_instruction_data() {
+    let ix = counter::instruction::IncrementBy {
+        amount: 5,
+        memo: Some("hi".to_string()),
+    };
+    let data = ix.data();
+    assert_eq!(
+        &data[..8],
+        &anchor_lang::solana_program::hash::hash(b"global:increment_by").to_bytes()[..8]
+    );
+    assert_eq!(
+        &data[8..],
+        &(5u64, Some("hi".to_string())).try_to_vec().unwrap()[..]
+    );
+
+    assert_eq!(
+        counter::instruction::Ping.data(),
+        counter::instruction::Ping::DISCRIMINATOR
+    );
+}
+
+#[test]
+fn test_accounts() {
+    let accounts = counter::accounts::Initialize {
+        counter: Pubkey::new_unique(),
+        common: counter::accounts::InitializeCommon {
+            payer: Pubkey::new_unique(),
+            system_program: Pubkey::new_unique(),
+        },
+    };
+    let metas = accounts.to_account_metas(None);
+    assert_eq!(metas.len(), 3);
+    assert_eq!(metas[0].pubkey, accounts.counter);
+    assert!(metas[0].is_signer && metas[0].is_writable);
+    assert_eq!(metas[1].pubkey, accounts.common.payer);
+    assert_eq!(metas[2].pubkey, accounts.common.system_program);
+    assert!(!metas[2].is_signer && !metas[2].is_writable);
+
+    // Missing optional accounts are replaced by the program id.
+    let accounts = counter::accounts::IncrementBy {
+        counter: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        log: None,
+    };
+    let metas = accounts.to_account_metas(None);
+    assert_eq!(metas[2].pubkey, counter::ID);
+    assert!(!metas[2].is_writable);
+}
+
+#[test]
+fn test_account_serialization() {
+    let account = Counter {
+        authority: Pubkey::new_unique(),
+        count: 7,
+        config: Config {
+            step: 2,
+            mode: Mode::Capped { max_value: 10 },
+            seed: [1, 2, 3, 4],
+        },
+        history: History {
+            values: [1, 2, 3, 4],
+        },
+    };
+    let mut data = vec![];
+    account.try_serialize(&mut data).unwrap();
+    assert_eq!(&data[..8], &Counter::DISCRIMINATOR);
+    assert_eq!(
+        &Counter::DISCRIMINATOR,
+        &anchor_lang::solana_program::hash::hash(b"account:Counter").to_bytes()[..8]
+    );
+
+    let deserialized = Counter::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(deserialized.count, 7);
+    assert_eq!(deserialized.history.values, [1, 2, 3, 4]);
+    assert!(matches!(
+        deserialized.config.mode,
+        Mode::Capped { max_value: 10 }
+    ));
+
+    data[0] ^= 1;
+    assert!(Counter::try_deserialize(&mut &data[..]).is_err());
+}
+
+#[test]
+fn test_event() {
+    let event = counter::Incremented { new_count: 3 };
+    let data = anchor_lang::Event::data(&event);
+    assert_eq!(&data[..8], &counter::Incremented::DISCRIMINATOR);
+    assert_eq!(&data[8..], &3u64.to_le_bytes());
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(u32::from(counter::ErrorCode::Overflow), 6000);
+    assert_eq!(u32::from(counter::ErrorCode::Unauthorized), 6001);
+
+    let error: Error = counter::ErrorCode::Overflow.into();
+    match error {
+        Error::AnchorError(error) => {
+            assert_eq!(error.error_code_number, 6000);
+            assert_eq!(error.error_name, "Overflow");
+            assert_eq!(error.error_msg, "The counter overflowed");
+        }
+        _ => panic!("expected an anchor error"),
+    }
+}
+
+#[test]
+fn test_instruction_builder() {
+    let accounts = counter::accounts::IncrementBy {
+        counter: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        log: None,
+    };
+    let ix = counter::instruction::increment_by(accounts, 5, None);
+    assert_eq!(ix.program_id, counter::ID);
+    assert_eq!(ix.accounts.len(), 3);
+    assert_eq!(
+        ix.data,
+        counter::instruction::IncrementBy {
+            amount: 5,
+            memo: None
+        }
+        .data()
+    );
+}
+
+#[test]
+fn test_pda() {
+    let authority = Pubkey::new_unique();
+    let (address, bump) = counter::pda::find_counter_address(authority, 3);
+    assert_eq!(
+        Pubkey::create_program_address(
+            &[b"counter", authority.as_ref(), &3u64.to_le_bytes(), &[bump]],
+            &counter::ID
+        )
+        .unwrap(),
+        address
+    );
+}
+
+#[test]
+fn test_decoders() {
+    let account = Counter {
+        authority: Pubkey::new_unique(),
+        count: 1,
+        config: Config {
+            step: 1,
+            mode: Mode::Up,
+            seed: [0; 4],
+        },
+        history: History { values: [0; 4] },
+    };
+    let mut data = vec![];
+    account.try_serialize(&mut data).unwrap();
+    match counter::ProgramAccount::decode(&data).unwrap() {
+        counter::ProgramAccount::Counter(decoded) => assert_eq!(decoded.count, 1),
+    }
+    assert!(counter::ProgramAccount::decode(&data[..4]).is_err());
+
+    let event = counter::Incremented { new_count: 3 };
+    match counter::ProgramEvent::decode(&anchor_lang::Event::data(&event)) {
+        Some(counter::ProgramEvent::Incremented(decoded)) => assert_eq!(decoded.new_count, 3),
+        _ => panic!("expected an event"),
+    }
+    assert!(counter::ProgramEvent::decode(&data).is_none());
+
+    assert_eq!(
+        counter::ErrorCode::from_code(6001),
+        Some(counter::ErrorCode::Unauthorized)
+    );
+    assert_eq!(counter::ErrorCode::from_code(6002), None);
+}
+
+// The CPI functions can't be invoked off-chain, only make sure they compile
+// with the expected signatures.
+fn increment_by<'info>(
+    program: AccountInfo<'info>,
+    counter: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+) -> Result<u64> {
+    let cpi_ctx = CpiContext::new(
+        program,
+        counter::cpi::accounts::IncrementBy {
+            counter,
+            authority,
+            log: None,
+        },
+    );
+    Ok(counter::cpi::increment_by(cpi_ctx, 1, None)?.get())
+}
+
+fn ping(program: AccountInfo) -> Result<()> {
+    counter::cpi::ping(CpiContext::new(program, counter::cpi::accounts::Ping {}))
+}
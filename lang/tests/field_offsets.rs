@@ -0,0 +1,98 @@
+#![allow(dead_code)]
+
+use anchor_lang::prelude::*;
+
+// Needed to declare accounts.
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[account]
+pub struct Vault {
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub amounts: [u64; 2],
+    pub name: String,
+    pub closed: bool,
+}
+
+#[account(zero_copy)]
+pub struct Pool {
+    pub flags: [u8; 2],
+    pub version: u16,
+    pub count: u32,
+    pub amount: u64,
+    pub owners: [Pubkey; 2],
+}
+
+#[account(zero_copy(unsafe))]
+pub struct PackedPool {
+    pub flag: u8,
+    pub amount: u64,
+}
+
+// Fields named after associated items of the account.
+#[account(zero_copy)]
+pub struct Queue {
+    pub head: u32,
+    pub tail: u32,
+}
+
+impl Queue {
+    pub fn head(&self) -> u32 {
+        self.head
+    }
+
+    pub fn tail(&self) -> u32 {
+        self.tail
+    }
+}
+
+#[test]
+fn borsh_field_offsets() {
+    assert_eq!(VaultOffsets::bump.offset, 8);
+    assert_eq!(VaultOffsets::bump.size, 1);
+    assert_eq!(VaultOffsets::authority.offset, 9);
+    assert_eq!(VaultOffsets::authority.size, 32);
+    assert_eq!(VaultOffsets::amounts.offset, 41);
+    assert_eq!(VaultOffsets::amounts.size, 16);
+
+    let vault = Vault {
+        bump: 1,
+        authority: Pubkey::new_unique(),
+        amounts: [2, 3],
+        name: "vault".to_string(),
+        closed: false,
+    };
+    let mut data = Vec::new();
+    vault.try_serialize(&mut data).unwrap();
+    let authority = VaultOffsets::authority;
+    assert_eq!(
+        &data[authority.offset..authority.offset + authority.size],
+        vault.authority.as_ref()
+    );
+}
+
+#[test]
+fn zero_copy_field_offsets() {
+    assert_eq!(PoolOffsets::flags.offset, 8);
+    assert_eq!(PoolOffsets::version.offset, 10);
+    assert_eq!(PoolOffsets::count.offset, 12);
+    assert_eq!(PoolOffsets::amount.offset, 16);
+    assert_eq!(PoolOffsets::amount.size, 8);
+    assert_eq!(PoolOffsets::owners.offset, 24);
+    assert_eq!(PoolOffsets::owners.size, 64);
+    assert_eq!(
+        PoolOffsets::owners.offset + PoolOffsets::owners.size,
+        8 + std::mem::size_of::<Pool>()
+    );
+
+    assert_eq!(PackedPoolOffsets::flag.offset, 8);
+    assert_eq!(PackedPoolOffsets::amount.offset, 9);
+}
+
+#[test]
+fn field_offsets_apart_from_account() {
+    let queue = Queue { head: 1, tail: 2 };
+    assert_eq!((queue.head(), queue.tail()), (1, 2));
+    assert_eq!(QueueOffsets::head.offset, 8);
+    assert_eq!(QueueOffsets::tail.offset, 12);
+}
@@ -0,0 +1,157 @@
+#![allow(dead_code)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_stubs;
+use anchor_lang::solana_program::system_instruction::SystemInstruction;
+use anchor_lang::solana_program::{entrypoint::ProgramResult, system_program};
+use std::cell::RefCell;
+
+// Needed to declare accounts.
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[account]
+#[derive(InitSpace)]
+pub struct Data {
+    pub authority: Pubkey,
+    #[max_len(10)]
+    pub name: String,
+}
+
+#[account(zero_copy)]
+pub struct Book {
+    pub orders: [u64; 16],
+}
+
+// `space` is derived from the account type when omitted.
+#[derive(Accounts)]
+pub struct InitWithoutSpace<'info> {
+    #[account(init, payer = payer)]
+    pub data: Account<'info, Data>,
+    #[account(init, payer = payer)]
+    pub boxed: Box<Account<'info, Data>>,
+    #[account(init, payer = payer)]
+    pub book: AccountLoader<'info, Book>,
+    #[account(init, payer = payer, seeds = [b"data"], bump)]
+    pub pda: Option<Account<'info, Data>>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Records the space of every account created through the system program and
+// hands the new account its lamports, zeroed data and owner.
+struct CreateAccountStubs;
+
+thread_local! {
+    static CREATED: RefCell<Vec<(Pubkey, u64)>> = const { RefCell::new(Vec::new()) };
+}
+
+impl program_stubs::SyscallStubs for CreateAccountStubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let (lamports, space, owner) = match bincode::deserialize(&instruction.data).unwrap() {
+            SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            } => (lamports, space, owner),
+            ix => panic!("unexpected system instruction {ix:?}"),
+        };
+        let to = &instruction.accounts[1].pubkey;
+        let info = account_infos.iter().find(|info| info.key == to).unwrap();
+        **info.try_borrow_mut_lamports()? = lamports;
+        *info.try_borrow_mut_data()? = Box::leak(vec![0; space as usize].into_boxed_slice());
+        info.assign(&owner);
+        CREATED.with(|created| created.borrow_mut().push((*to, space)));
+        Ok(())
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+}
+
+#[test]
+fn test_default_space() {
+    program_stubs::set_syscall_stubs(Box::new(CreateAccountStubs));
+
+    let keys: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let mut lamports = [0u64; 3];
+    let mut data: [Vec<u8>; 3] = Default::default();
+    let (payer_key, mut payer_lamports, mut payer_data) =
+        (Pubkey::new_unique(), 1_000_000_000, vec![]);
+    let (system_key, mut system_lamports, mut system_data) = (system_program::ID, 1, vec![]);
+    let (program_key, mut program_lamports, mut program_data) = (crate::ID, 1, vec![]);
+
+    let mut accounts = vec![];
+    for ((key, lamports), data) in keys.iter().zip(&mut lamports).zip(&mut data) {
+        accounts.push(AccountInfo::new(
+            key,
+            true,
+            true,
+            lamports,
+            data,
+            &system_key,
+            false,
+            0,
+        ));
+    }
+    // The optional `pda` account is omitted.
+    accounts.push(AccountInfo::new(
+        &program_key,
+        false,
+        false,
+        &mut program_lamports,
+        &mut program_data,
+        &system_key,
+        true,
+        0,
+    ));
+    accounts.push(AccountInfo::new(
+        &payer_key,
+        true,
+        true,
+        &mut payer_lamports,
+        &mut payer_data,
+        &system_key,
+        false,
+        0,
+    ));
+    accounts.push(AccountInfo::new(
+        &system_key,
+        false,
+        false,
+        &mut system_lamports,
+        &mut system_data,
+        &system_key,
+        true,
+        0,
+    ));
+
+    InitWithoutSpace::try_accounts(
+        &crate::ID,
+        &mut &accounts[..],
+        &[],
+        &mut Default::default(),
+        &mut Default::default(),
+        &mut Default::default(),
+        &mut Default::default(),
+    )
+    .unwrap();
+
+    let created = CREATED.with(|created| created.take());
+    assert_eq!(
+        created,
+        vec![
+            (keys[0], 8 + 32 + 4 + 10),
+            (keys[1], 8 + 32 + 4 + 10),
+            (keys[2], 8 + 8 * 16),
+        ]
+    );
+}
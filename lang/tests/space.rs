@@ -147,3 +147,93 @@ fn test_full_path() {
 fn test_const() {
     assert_eq!(TestConst::INIT_SPACE, (4 + 10) + 10)
 }
+
+#[derive(InitSpace)]
+pub struct TestTupleStruct(pub u8, #[max_len(10)] pub String);
+
+#[derive(InitSpace)]
+pub struct TestUnitStruct;
+
+#[derive(InitSpace)]
+pub enum TestDataEnum {
+    Unit,
+    Tuple(u64, Pubkey),
+    Named {
+        #[max_len(3)]
+        test_vec: Vec<u16>,
+    },
+}
+
+#[derive(InitSpace)]
+pub struct TestMaps {
+    #[max_len(5, 8)]
+    pub test_btree_map: std::collections::BTreeMap<String, u32>,
+    #[max_len(2, 3, 4)]
+    pub test_hash_map: std::collections::HashMap<u8, Vec<String>>,
+}
+
+#[derive(InitSpace)]
+pub struct TestTuple {
+    #[max_len(6)]
+    pub test_tuple: (u8, String, (u16, Pubkey)),
+    pub test_unit: (),
+}
+
+#[derive(InitSpace)]
+pub struct TestOptionChain {
+    #[max_len(3, 7)]
+    pub test_option: Option<Vec<String>>,
+    pub test_box: Box<u64>,
+}
+
+pub struct NoSpace;
+
+#[derive(InitSpace)]
+pub struct TestSpaceOverride {
+    #[space(64)]
+    pub test_external: NoSpace,
+    #[space(MAX_LEN)]
+    pub test_const: NoSpace,
+    pub test_u8: u8,
+}
+
+#[derive(InitSpace)]
+pub enum TestGenericEnum<T: Space> {
+    Empty,
+    Value(T),
+}
+
+#[test]
+fn test_tuple_and_unit_struct() {
+    assert_eq!(TestTupleStruct::INIT_SPACE, 1 + (4 + 10));
+    assert_eq!(TestUnitStruct::INIT_SPACE, 0);
+}
+
+#[test]
+fn test_data_enum() {
+    assert_eq!(TestDataEnum::INIT_SPACE, 1 + 8 + 32);
+    assert_eq!(TestGenericEnum::<TestBasicVarAccount>::INIT_SPACE, 1 + 31);
+}
+
+#[test]
+fn test_maps() {
+    assert_eq!(
+        TestMaps::INIT_SPACE,
+        (4 + 5 * ((4 + 8) + 4)) + (4 + 2 * (1 + (4 + 3 * (4 + 4))))
+    );
+}
+
+#[test]
+fn test_tuple() {
+    assert_eq!(TestTuple::INIT_SPACE, 1 + (4 + 6) + 2 + 32);
+}
+
+#[test]
+fn test_option_chain() {
+    assert_eq!(TestOptionChain::INIT_SPACE, 1 + 4 + 3 * (4 + 7) + 8);
+}
+
+#[test]
+fn test_space_override() {
+    assert_eq!(TestSpaceOverride::INIT_SPACE, 64 + 10 + 1);
+}
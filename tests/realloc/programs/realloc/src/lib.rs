@@ -20,6 +20,14 @@ pub mod realloc {
         Ok(())
     }
 
+    pub fn realloc_incremental(ctx: Context<ReallocIncremental>, len: u32) -> Result<()> {
+        ctx.accounts
+            .sample
+            .data
+            .resize_with(len as usize, Default::default);
+        Ok(())
+    }
+
     pub fn realloc2(ctx: Context<Realloc2>, len: u16) -> Result<()> {
         ctx.accounts
             .sample1
@@ -70,6 +78,26 @@ pub struct Realloc<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(len: u32)]
+pub struct ReallocIncremental<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"sample"],
+        bump = sample.bump,
+        realloc = Sample::space(len as usize),
+        realloc::payer = authority,
+        realloc::zero = false,
+        realloc::incremental,
+    )]
+    pub sample: Account<'info, Sample>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 #[instruction(len: u16)]
 pub struct Realloc2<'info> {
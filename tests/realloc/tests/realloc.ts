@@ -68,6 +68,34 @@ describe("realloc", () => {
     assert.lengthOf(s.data, 1);
   });
 
+  it("realloc incremental", async () => {
+    const len = 25000;
+    const target = 8 + (4 + len) + 1;
+
+    // The handler is skipped until the account reached its target size.
+    await program.methods
+      .reallocIncremental(len)
+      .accounts({ authority: authority.publicKey, sample })
+      .rpc();
+    let info = await program.provider.connection.getAccountInfo(sample);
+    assert.strictEqual(info.data.length, 8 + (4 + 1) + 1 + 10240);
+    let s = await program.account.sample.fetch(sample);
+    assert.lengthOf(s.data, 1);
+
+    await program.methods
+      .reallocIncremental(len)
+      .accounts({ authority: authority.publicKey, sample })
+      .rpc();
+    await program.methods
+      .reallocIncremental(len)
+      .accounts({ authority: authority.publicKey, sample })
+      .rpc();
+    info = await program.provider.connection.getAccountInfo(sample);
+    assert.strictEqual(info.data.length, target);
+    s = await program.account.sample.fetch(sample);
+    assert.lengthOf(s.data, len);
+  });
+
   it("fails with duplicate account reallocations", async () => {
     try {
       await program.methods